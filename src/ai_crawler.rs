@@ -0,0 +1,123 @@
+use serde_derive::Deserialize;
+
+use super::Error;
+
+/// The rules shipped with the crate, see `AiCrawlerParser::default`
+static AI_CRAWLERS: &str = include_str!("ai_crawlers.yaml");
+
+/// What an AI crawler collects pages for
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AiPurpose {
+    /// Collecting data for model training
+    Training,
+    /// Indexing pages for AI search results
+    Search,
+    /// Fetching pages on behalf of a user's prompt
+    Assistant,
+}
+
+/// Describes an AI or LLM crawler identified from a user agent string
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct AiCrawler {
+    pub name: String,
+    pub operator: String,
+    pub purpose: AiPurpose,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AiCrawlerFile {
+    ai_crawlers: Vec<AiCrawlerEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AiCrawlerEntry {
+    regex: String,
+    name: String,
+    operator: String,
+    purpose: AiPurpose,
+}
+
+#[derive(Debug)]
+struct Rule {
+    regex: fancy_regex::Regex,
+    crawler: AiCrawler,
+}
+
+/// Identifies AI and LLM crawlers, separately from the generic bot detection
+/// of the uap-core dataset. The list of crawlers changes far more often than
+/// the rest of the dataset, so besides the rules shipped with the crate it
+/// can be loaded from a file in the format of `src/ai_crawlers.yaml`. Link
+/// preview fetchers of chat and social apps are identified by
+/// `LinkPreviewParser` instead.
+///
+/// ```rust
+/// # use uaparser::*;
+/// let parser = AiCrawlerParser::default();
+/// let crawler = parser
+///     .parse("Mozilla/5.0 AppleWebKit/537.36 (KHTML, like Gecko; compatible; GPTBot/1.2; +https://openai.com/gptbot)")
+///     .unwrap();
+///
+/// assert_eq!(crawler.operator, "OpenAI");
+/// assert_eq!(crawler.purpose, AiPurpose::Training);
+/// assert_eq!(crawler.version.as_deref(), Some("1.2"));
+/// ```
+#[derive(Debug)]
+pub struct AiCrawlerParser {
+    rules: Vec<Rule>,
+}
+
+impl Default for AiCrawlerParser {
+    fn default() -> AiCrawlerParser {
+        AiCrawlerParser::from_bytes(AI_CRAWLERS.as_bytes())
+            .expect("bundled AI crawler rules are valid")
+    }
+}
+
+impl AiCrawlerParser {
+    /// Attempts to construct an `AiCrawlerParser` from the path to a rule file
+    #[cfg(feature = "fs")]
+    pub fn from_yaml(path: &str) -> Result<AiCrawlerParser, Error> {
+        let file = std::fs::File::open(path)?;
+        let crawler_file: AiCrawlerFile = serde_yaml::from_reader(file)?;
+        AiCrawlerParser::from_crawler_file(crawler_file)
+    }
+
+    /// Attempts to construct an `AiCrawlerParser` from the raw bytes of a rule
+    /// file
+    pub fn from_bytes(bytes: &[u8]) -> Result<AiCrawlerParser, Error> {
+        let crawler_file: AiCrawlerFile = serde_yaml::from_slice(bytes)?;
+        AiCrawlerParser::from_crawler_file(crawler_file)
+    }
+
+    fn from_crawler_file(crawler_file: AiCrawlerFile) -> Result<AiCrawlerParser, Error> {
+        let mut rules = Vec::new();
+
+        for entry in crawler_file.ai_crawlers {
+            rules.push(Rule {
+                regex: fancy_regex::Regex::new(&entry.regex)?,
+                crawler: AiCrawler {
+                    name: entry.name,
+                    operator: entry.operator,
+                    purpose: entry.purpose,
+                    version: None,
+                },
+            });
+        }
+
+        Ok(AiCrawlerParser { rules })
+    }
+
+    /// Returns the AI crawler `user_agent` belongs to, if any
+    pub fn parse(&self, user_agent: &str) -> Option<AiCrawler> {
+        self.rules.iter().find_map(|rule| {
+            let captures = rule.regex.captures(user_agent).ok()??;
+            let version = captures.get(1).map(|m| m.as_str().to_string());
+            Some(AiCrawler {
+                version,
+                ..rule.crawler.clone()
+            })
+        })
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+use serde_derive::Deserialize;
+
+use super::Category;
+
+/// Renames families in results, e.g. to consolidate `Chrome Mobile` and
+/// `Chrome` into one entry of a dashboard. Aliases are given per category,
+/// see `UserAgentParserBuilder::family_aliases`, and can be deserialized:
+///
+/// ```yaml
+/// user_agent:
+///   Chrome Mobile: Chrome
+/// os:
+///   Mac OS X: macOS
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FamilyAliases {
+    pub user_agent: HashMap<String, String>,
+    pub os: HashMap<String, String>,
+    pub device: HashMap<String, String>,
+}
+
+impl FamilyAliases {
+    pub fn new() -> FamilyAliases {
+        FamilyAliases::default()
+    }
+
+    /// Returns aliases merging the mobile and desktop variants of common
+    /// browsers, and using the current names of renamed operating systems
+    pub fn consolidated() -> FamilyAliases {
+        let mut aliases = FamilyAliases::new();
+        for (family, alias) in &[
+            ("Chrome Mobile", "Chrome"),
+            ("Chrome Mobile iOS", "Chrome"),
+            ("Edge Mobile", "Edge"),
+            ("Firefox Mobile", "Firefox"),
+            ("Firefox iOS", "Firefox"),
+            ("Mobile Safari", "Safari"),
+            ("Opera Mobile", "Opera"),
+        ] {
+            aliases = aliases.alias(Category::UserAgent, *family, *alias);
+        }
+        for (family, alias) in &[("Mac OS X", "macOS"), ("Chrome OS", "ChromeOS")] {
+            aliases = aliases.alias(Category::Os, *family, *alias);
+        }
+        aliases
+    }
+
+    /// Reports `family` as `alias` in results of `category`
+    pub fn alias(
+        mut self,
+        category: Category,
+        family: impl Into<String>,
+        alias: impl Into<String>,
+    ) -> FamilyAliases {
+        self.map_mut(category).insert(family.into(), alias.into());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.user_agent.is_empty() && self.os.is_empty() && self.device.is_empty()
+    }
+
+    /// Replaces `family` with its alias in `category`, if it has one
+    pub fn apply(&self, category: Category, family: &mut String) {
+        if let Some(alias) = self.map(category).get(family.as_str()) {
+            family.clear();
+            family.push_str(alias);
+        }
+    }
+
+    fn map(&self, category: Category) -> &HashMap<String, String> {
+        match category {
+            Category::UserAgent => &self.user_agent,
+            Category::Os => &self.os,
+            Category::Device => &self.device,
+        }
+    }
+
+    fn map_mut(&mut self, category: Category) -> &mut HashMap<String, String> {
+        match category {
+            Category::UserAgent => &mut self.user_agent,
+            Category::Os => &mut self.os,
+            Category::Device => &mut self.device,
+        }
+    }
+}
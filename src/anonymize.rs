@@ -0,0 +1,152 @@
+use std::io::{BufRead, Write};
+
+use super::{Client, Error, Parser};
+
+impl Client {
+    /// Returns a generalized description that keeps the browser family and
+    /// its major version, the operating system family and the device class,
+    /// like `Firefox 70; Linux; desktop`, to replace user agents in datasets
+    /// that mustn't retain them
+    pub fn generalized(&self) -> String {
+        let user_agent = &self.user_agent;
+        let browser = match &user_agent.major {
+            Some(major) => format!("{} {}", user_agent.family, major),
+            None => user_agent.family.clone(),
+        };
+        format!(
+            "{}; {}; {}",
+            browser,
+            self.os.family,
+            self.device_type().as_str()
+        )
+    }
+}
+
+/// Rewrites the user agents in `column` (zero-based) of a log into their
+/// `Client::generalized` form, leaving every other column and line untouched.
+/// Columns are separated by `delimiter`, and may be wrapped in double quotes
+/// or, as timestamps of access logs are, in brackets. Quoted user agents
+/// stay quoted. Returns the number of rewritten lines.
+///
+/// The first `header_rows` lines are copied as they are, as are empty lines
+/// and lines that aren't valid UTF-8. Line endings, `\n` or `\r\n`, are
+/// kept.
+///
+/// ```rust
+/// # use uaparser::*;
+/// # let parser = UserAgentParser::from_yaml("./src/core/regexes.yaml").unwrap();
+/// let log = "ip,user_agent,status\n\
+///            10.0.0.1,\"Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Firefox/70.0\",200\n";
+/// let mut output = Vec::new();
+/// anonymize_log(&parser, log.as_bytes(), &mut output, 1, ',', 1)?;
+///
+/// assert_eq!(
+///     String::from_utf8(output).unwrap(),
+///     "ip,user_agent,status\n10.0.0.1,\"Firefox 70; Linux; desktop\",200\n"
+/// );
+/// # Ok::<_, Error>(())
+/// ```
+pub fn anonymize_log<P, R, W>(
+    parser: &P,
+    mut input: R,
+    mut output: W,
+    column: usize,
+    delimiter: char,
+    header_rows: usize,
+) -> Result<u64, Error>
+where
+    P: Parser + ?Sized,
+    R: BufRead,
+    W: Write,
+{
+    let mut rewritten = 0;
+    let mut buffer = Vec::new();
+
+    for row in 0.. {
+        buffer.clear();
+        if input.read_until(b'\n', &mut buffer)? == 0 {
+            break;
+        }
+
+        let content = buffer.strip_suffix(b"\n").unwrap_or(&buffer);
+        let content = content.strip_suffix(b"\r").unwrap_or(content);
+        let ending = &buffer[content.len()..];
+
+        let line = match std::str::from_utf8(content) {
+            Ok(line) if row >= header_rows && !line.is_empty() => line,
+            _ => {
+                output.write_all(&buffer)?;
+                continue;
+            }
+        };
+
+        match fields(line, delimiter).nth(column) {
+            Some((start, end)) => {
+                let field = &line[start..end];
+                let quoted =
+                    field.len() >= 2 && field.starts_with('"') && field.ends_with('"');
+                let user_agent = if quoted {
+                    field[1..field.len() - 1].replace("\"\"", "\"")
+                } else {
+                    field.to_owned()
+                };
+
+                let generalized = parser.parse(&user_agent).generalized();
+                output.write_all(&line.as_bytes()[..start])?;
+                if quoted {
+                    write!(output, "\"{}\"", generalized.replace('"', "\"\""))?;
+                } else {
+                    output.write_all(generalized.as_bytes())?;
+                }
+                output.write_all(&line.as_bytes()[end..])?;
+                rewritten += 1;
+            }
+            None => output.write_all(line.as_bytes())?,
+        }
+        output.write_all(ending)?;
+    }
+
+    output.flush()?;
+    Ok(rewritten)
+}
+
+/// Returns the byte ranges of the fields of `line`
+fn fields(line: &str, delimiter: char) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let mut chars = line.char_indices().peekable();
+    let mut start = 0;
+    let mut done = false;
+
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+
+        let mut closing = match chars.peek() {
+            Some(&(_, '"')) => Some('"'),
+            Some(&(_, '[')) => Some(']'),
+            _ => None,
+        };
+        if closing.is_some() {
+            chars.next();
+        }
+
+        while let Some((index, c)) = chars.next() {
+            match closing {
+                // A doubled quote is an escaped one
+                Some('"') if c == '"' && chars.peek().map(|&(_, c)| c) == Some('"') => {
+                    chars.next();
+                }
+                Some(close) if c == close => closing = None,
+                None if c == delimiter => {
+                    let field = (start, index);
+                    start = index + c.len_utf8();
+                    return Some(field);
+                }
+                _ => {}
+            }
+        }
+
+        done = true;
+        Some((start, line.len()))
+    })
+}
@@ -0,0 +1,122 @@
+use super::Product;
+
+/// Tokens of runtimes that embed a browser engine into desktop applications.
+/// Tauri and CEF applications only carry these if they add them to their
+/// user agent, which they don't by default.
+const RUNTIME_TOKENS: &[&str] = &["Electron/", "Tauri/", "CEF/"];
+
+/// Product tokens that are part of every browser user agent, rather than
+/// naming the embedding application
+const BROWSER_TOKENS: &[&str] = &[
+    "Mozilla",
+    "AppleWebKit",
+    "Chrome",
+    "Chromium",
+    "HeadlessChrome",
+    "Safari",
+    "Gecko",
+    "Version",
+    "Mobile",
+];
+
+/// Names of Android apps by the package sent in `X-Requested-With`
+const ANDROID_APPS: &[(&str, &str)] = &[
+    ("com.facebook.katana", "Facebook"),
+    ("com.facebook.lite", "Facebook Lite"),
+    ("com.facebook.orca", "Messenger"),
+    ("com.instagram.android", "Instagram"),
+    ("com.zhiliaoapp.musically", "TikTok"),
+    ("com.ss.android.ugc.trill", "TikTok"),
+    ("com.twitter.android", "X"),
+    ("com.linkedin.android", "LinkedIn"),
+    ("com.pinterest", "Pinterest"),
+    ("com.snapchat.android", "Snapchat"),
+    ("com.reddit.frontpage", "Reddit"),
+    ("com.whatsapp", "WhatsApp"),
+    ("org.telegram.messenger", "Telegram"),
+    ("jp.naver.line.android", "LINE"),
+    ("com.tencent.mm", "WeChat"),
+    ("com.google.android.gm", "Gmail"),
+    ("com.google.android.googlequicksearchbox", "Google"),
+    ("com.microsoft.office.outlook", "Outlook"),
+    ("com.microsoft.teams", "Teams"),
+    ("com.slack", "Slack"),
+    ("com.discord", "Discord"),
+];
+
+/// Packages of browsers, which some send in `X-Requested-With` themselves
+/// rather than an embedding app
+const BROWSER_PACKAGES: &[&str] = &[
+    "com.android.browser",
+    "com.android.chrome",
+    "com.brave.browser",
+    "com.microsoft.emmx",
+    "com.opera.browser",
+    "com.sec.android.app.sbrowser",
+    "org.mozilla.firefox",
+];
+
+/// Tells whether `user_agent` is that of an Android WebView, by the `wv`
+/// token of Android 5 and later, or the `Version/4.0` token that older
+/// WebViews send next to `Chrome/`
+fn is_android_webview(user_agent: &str) -> bool {
+    user_agent.contains("Android")
+        && (user_agent.contains("; wv)")
+            || (user_agent.contains(" Version/") && user_agent.contains(" Chrome/")))
+}
+
+/// Returns the app of an Android WebView from the package name sent in the
+/// `X-Requested-With` header along with `user_agent`. Unknown packages are
+/// reported by their name, while user agents of anything but a WebView,
+/// values that aren't package names in reverse domain notation, like the
+/// `XMLHttpRequest` sent by JavaScript libraries, and the packages of
+/// browsers yield `None`.
+pub(crate) fn from_package(user_agent: &str, package: &str) -> Option<Product> {
+    let package = package.trim();
+    let is_package = package.contains('.')
+        && (package.split('.')).all(|part| {
+            part.starts_with(|c: char| c.is_ascii_alphabetic())
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+    if !is_package
+        || !is_android_webview(user_agent)
+        || BROWSER_PACKAGES.contains(&package)
+    {
+        return None;
+    }
+
+    let name = ANDROID_APPS
+        .iter()
+        .find(|(known, _)| *known == package)
+        .map_or(package, |(_, name)| name);
+
+    Some(Product {
+        name: name.to_owned(),
+        version: None,
+    })
+}
+
+/// Finds the runtime of desktop applications built with Electron and similar
+/// runtimes, along with the application itself, e.g. `Slack 4.36.140` in
+/// `... Slack/4.36.140 Chrome/118.0.5993.159 Electron/27.1.3 Safari/537.36`.
+/// The application is the last product token before the runtime that isn't
+/// part of a regular browser user agent.
+pub(crate) fn detect(user_agent: &str) -> Option<(Option<Product>, Product)> {
+    let tokens: Vec<&str> = user_agent.split_whitespace().collect();
+
+    let position = tokens.iter().position(|token| {
+        RUNTIME_TOKENS
+            .iter()
+            .any(|runtime| token.starts_with(runtime))
+    })?;
+    let runtime = Product::from_token(tokens[position])?;
+
+    let app = tokens[..position]
+        .iter()
+        .rev()
+        .filter(|token| token.contains('/'))
+        .filter_map(|token| Product::from_token(token))
+        .find(|product| !BROWSER_TOKENS.contains(&product.name.as_str()));
+
+    Some((app, runtime))
+}
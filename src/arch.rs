@@ -0,0 +1,112 @@
+use super::{Deserialize, Serialize, OS};
+
+/// CPU architecture of the machine an `OS` runs on
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "async-graphql", derive(async_graphql::Enum))]
+#[serde(rename_all = "snake_case")]
+pub enum Arch {
+    X86,
+    X86_64,
+    Arm,
+    Arm64,
+}
+
+impl Arch {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Arch::X86 => "x86",
+            Arch::X86_64 => "x86_64",
+            Arch::Arm => "arm",
+            Arch::Arm64 => "arm64",
+        }
+    }
+}
+
+/// Tokens identifying an architecture, in order of precedence. 64-bit tokens
+/// come first, since 32-bit browsers on 64-bit systems often list both, as in
+/// `WOW64` or `i686 on x86_64`. Compared in lowercase against whole tokens of
+/// the user agent, optionally followed by a variant letter as in `armv7l`.
+const ARCH_TOKENS: &[(&str, Arch, u8)] = &[
+    ("wow64", Arch::X86_64, 64),
+    ("win64", Arch::X86_64, 64),
+    ("x86_64", Arch::X86_64, 64),
+    ("x86-64", Arch::X86_64, 64),
+    ("amd64", Arch::X86_64, 64),
+    ("x64", Arch::X86_64, 64),
+    ("aarch64", Arch::Arm64, 64),
+    ("arm64", Arch::Arm64, 64),
+    ("armv8", Arch::Arm, 32),
+    ("armv7", Arch::Arm, 32),
+    ("armv6", Arch::Arm, 32),
+    ("arm", Arch::Arm, 32),
+    ("i686", Arch::X86, 32),
+    ("i586", Arch::X86, 32),
+    ("i386", Arch::X86, 32),
+];
+
+/// Derives the architecture and bitness of the operating system from the
+/// tokens of a user agent string
+pub(crate) fn detect(user_agent: &str) -> Option<(Arch, u8)> {
+    let user_agent = user_agent.to_ascii_lowercase();
+    let tokens: Vec<&str> = user_agent
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+        .filter(|token| !token.is_empty())
+        .collect();
+
+    let matches = |arch_token: &str| {
+        tokens
+            .iter()
+            .any(|token| match token.strip_prefix(arch_token) {
+                Some(variant) => {
+                    variant.is_empty()
+                        || (variant.len() == 1
+                            && variant.as_bytes()[0].is_ascii_lowercase())
+                }
+                None => false,
+            })
+    };
+
+    ARCH_TOKENS
+        .iter()
+        .find(|(token, _, _)| matches(token))
+        .map(|&(_, arch, bitness)| (arch, bitness))
+}
+
+impl OS {
+    /// Overrides the architecture and bitness with the values of the
+    /// `Sec-CH-UA-Arch` and `Sec-CH-UA-Bitness` client hints, which are more
+    /// reliable than user agent tokens. Both may be given with or without
+    /// their surrounding quotes. Unknown values are ignored.
+    pub fn apply_client_hints(&mut self, arch: Option<&str>, bitness: Option<&str>) {
+        let unquote = |value: &str| value.trim().trim_matches('"').to_ascii_lowercase();
+
+        let bitness = bitness
+            .map(unquote)
+            .and_then(|bitness| bitness.parse::<u8>().ok())
+            .or(self.bitness);
+
+        // Without an architecture hint, the one detected from the user agent
+        // is adjusted to the hinted bitness rather than left contradicting it
+        let family = match arch.map(unquote).as_deref() {
+            Some("x86") => Some(Arch::X86),
+            Some("arm") => Some(Arch::Arm),
+            Some(_) => None,
+            None => self.arch,
+        };
+
+        let arch = match (family, bitness) {
+            (Some(Arch::X86 | Arch::X86_64), Some(64)) => Some(Arch::X86_64),
+            (Some(Arch::X86 | Arch::X86_64), Some(32)) => Some(Arch::X86),
+            (Some(Arch::Arm | Arch::Arm64), Some(64)) => Some(Arch::Arm64),
+            (Some(Arch::Arm | Arch::Arm64), Some(32)) => Some(Arch::Arm),
+            (family, _) => family,
+        };
+
+        if let Some(arch) = arch {
+            self.arch = Some(arch);
+        }
+        self.bitness = bitness;
+    }
+}
@@ -0,0 +1,143 @@
+//! Command line tools around the UA Parser
+//!
+//! ```text
+//! uap anonymize --regexes <path> [--column <n>] [--delimiter <char>]
+//!               [--header-rows <n>] [input] [output]
+//! ```
+//!
+//! `anonymize` rewrites the user agents in a column of a log file into their
+//! generalized form, such as `Firefox 70; Linux; desktop`, keeping all other
+//! columns. Columns are zero-based and tab-separated by default. The first
+//! `--header-rows` lines, none by default, are copied as they are. The log is
+//! read from standard input and written to standard output, unless paths are
+//! given.
+
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    process,
+};
+
+use uaparser::{anonymize_log, Error, UserAgentParser};
+
+const USAGE: &str = "usage: uap anonymize --regexes <path> [--column <n>] \
+                     [--delimiter <char>] [--header-rows <n>] [input] [output]";
+
+struct Anonymize {
+    regexes: String,
+    column: usize,
+    delimiter: char,
+    header_rows: usize,
+    input: Option<String>,
+    output: Option<String>,
+}
+
+impl Anonymize {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Anonymize, String> {
+        let mut regexes = None;
+        let mut column = 0;
+        let mut delimiter = '\t';
+        let mut header_rows = 0;
+        let mut paths = Vec::new();
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| format!("missing value of `{}`", name))
+            };
+
+            match arg.as_str() {
+                "--regexes" => regexes = Some(value("--regexes")?),
+                "--column" => {
+                    column = (value("--column")?.parse())
+                        .map_err(|_| "`--column` must be a number".to_owned())?
+                }
+                "--header-rows" => {
+                    header_rows = (value("--header-rows")?.parse())
+                        .map_err(|_| "`--header-rows` must be a number".to_owned())?
+                }
+                "--delimiter" => {
+                    let value = value("--delimiter")?;
+                    let mut chars = value.chars();
+                    delimiter = match (chars.next(), chars.next()) {
+                        (Some(c), None) => c,
+                        _ if value == "\\t" => '\t',
+                        _ => return Err("`--delimiter` must be one character".to_owned()),
+                    };
+                }
+                flag if flag.starts_with("--") => {
+                    return Err(format!("unknown option `{}`", flag))
+                }
+                _ => paths.push(arg),
+            }
+        }
+
+        if paths.len() > 2 {
+            return Err("too many paths".to_owned());
+        }
+        let mut paths = paths.into_iter();
+
+        Ok(Anonymize {
+            regexes: regexes.ok_or("missing `--regexes`")?,
+            column,
+            delimiter,
+            header_rows,
+            input: paths.next(),
+            output: paths.next(),
+        })
+    }
+
+    fn run(self) -> Result<u64, Error> {
+        let parser = UserAgentParser::from_yaml(&self.regexes)?;
+
+        let output: Box<dyn Write> = match &self.output {
+            Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+            None => Box::new(BufWriter::new(io::stdout().lock())),
+        };
+
+        match &self.input {
+            Some(path) => {
+                let input = BufReader::new(File::open(path)?);
+                anonymize_log(
+                    &parser,
+                    input,
+                    output,
+                    self.column,
+                    self.delimiter,
+                    self.header_rows,
+                )
+            }
+            None => anonymize_log(
+                &parser,
+                io::stdin().lock(),
+                output,
+                self.column,
+                self.delimiter,
+                self.header_rows,
+            ),
+        }
+    }
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+
+    let command = match args.next().as_deref() {
+        Some("anonymize") => Anonymize::parse(args),
+        Some("-h") | Some("--help") => {
+            println!("{}", USAGE);
+            return;
+        }
+        _ => Err(USAGE.to_owned()),
+    };
+
+    let command = command.unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        process::exit(2);
+    });
+
+    if let Err(error) = command.run() {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
+}
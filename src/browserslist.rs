@@ -0,0 +1,465 @@
+use std::{
+    cmp::Ordering,
+    collections::BTreeSet,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::{
+    caniuse::{self, CaniuseData},
+    Client,
+};
+
+/// Agents of `dead`, i.e. browsers without updates for two years, along with
+/// the last version considered dead
+const DEAD: &[(&str, &str)] = &[
+    ("ie", "11"),
+    ("ie_mob", "11"),
+    ("bb", "10"),
+    ("op_mob", "12.1"),
+    ("samsung", "4"),
+    ("baidu", "13.52"),
+];
+
+/// The Firefox releases of `Firefox ESR` still receiving updates
+const FIREFOX_ESR: &[&str] = &["115", "128"];
+
+/// Names of browsers in queries, besides the caniuse agents themselves
+const ALIASES: &[(&str, &str)] = &[
+    ("explorer", "ie"),
+    ("explorermobile", "ie_mob"),
+    ("blackberry", "bb"),
+    ("ff", "firefox"),
+    ("fx", "firefox"),
+    ("ios", "ios_saf"),
+    ("operamobile", "op_mob"),
+    ("operamini", "op_mini"),
+    ("chromeandroid", "and_chr"),
+    ("firefoxandroid", "and_ff"),
+    ("ucandroid", "and_uc"),
+    ("qqandroid", "and_qq"),
+];
+
+/// An error in a browserslist query
+#[derive(Debug)]
+pub enum QueryError {
+    /// The query uses syntax that isn't supported, see `Browserslist`
+    Unsupported(String),
+    /// The query names a browser that isn't in the data
+    UnknownBrowser(String),
+}
+
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            QueryError::Unsupported(query) => write!(f, "unsupported query `{}`", query),
+            QueryError::UnknownBrowser(name) => write!(f, "unknown browser `{}`", name),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// The browser versions selected by a browserslist query, resolved against
+/// `CaniuseData`, to check clients against the same query used at build time.
+///
+/// Queries are combined with `,` or `or`, intersected with `and` and negated
+/// with `not`, either as `, not dead` or `and not dead`. Supported are:
+///
+/// - `defaults`, `dead`, `Firefox ESR` and `unreleased versions`, also for a
+///   single browser like `unreleased Chrome versions`
+/// - `> 0.5%` and the other comparisons of global usage, and `cover 99.5%`
+/// - `last 2 versions` and `last 2 major versions`, also for a single browser
+///   like `last 2 Chrome versions`
+/// - `last 2 years` and `since 2020`, `since 2020-03` or `since 2020-03-15`
+/// - `Safari >= 15` and the other comparisons, `Safari 15`, `Safari 14-16` and
+///   `op_mini all`
+/// - `supports webp`, `fully supports webp` and `partially supports webp`
+///
+/// Usage in single regions, `extends`, configs and queries of Node.js and
+/// Electron versions aren't supported.
+///
+/// ```rust
+/// # use uaparser::*;
+/// # let parser = UserAgentParser::from_yaml("./src/core/regexes.yaml").unwrap();
+/// # let data = CaniuseData::from_json(br#"{"agents": {"firefox": {"version_list": [
+/// #     {"version": "69", "global_usage": 0.1, "release_date": 1},
+/// #     {"version": "70", "global_usage": 0.8, "release_date": 2}
+/// # ]}}}"#).unwrap();
+/// let query = Browserslist::new("> 0.5%, not dead", &data)?;
+/// let client = parser.parse("Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Firefox/70.0");
+/// assert!(query.matches(&client));
+/// # Ok::<_, QueryError>(())
+/// ```
+///
+/// With the `caniuse-bundled` feature, queries can be resolved against the
+/// snapshot embedded in the crate with `Browserslist::bundled`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Browserslist {
+    selected: BTreeSet<(String, String)>,
+}
+
+type Selection = BTreeSet<(String, String)>;
+
+impl Browserslist {
+    /// Resolves `query` against `data`
+    pub fn new(query: &str, data: &CaniuseData) -> Result<Browserslist, QueryError> {
+        Ok(Browserslist {
+            selected: evaluate(query, data)?,
+        })
+    }
+
+    /// Resolves `query` against the caniuse data embedded in the crate, see
+    /// `CaniuseData::bundled`
+    #[cfg(feature = "caniuse-bundled")]
+    pub fn bundled(query: &str) -> Result<Browserslist, QueryError> {
+        Browserslist::new(query, CaniuseData::bundled())
+    }
+
+    /// Returns whether the browser of `client` is among the selected versions
+    pub fn matches(&self, client: &Client) -> bool {
+        let (agent, version) = match caniuse::agent(client) {
+            Some(agent) => agent,
+            None => return false,
+        };
+
+        (self.selected.iter())
+            .filter(|(selected, _)| selected == agent)
+            .any(|(_, release)| caniuse::covers(release, &version))
+    }
+
+    /// Returns the selected browsers as caniuse agents and releases, e.g.
+    /// `("chrome", "118")`
+    pub fn browsers(&self) -> impl Iterator<Item = (&str, &str)> {
+        (self.selected.iter()).map(|(agent, release)| (agent.as_str(), release.as_str()))
+    }
+}
+
+fn evaluate(query: &str, data: &CaniuseData) -> Result<Selection, QueryError> {
+    let query = query.to_ascii_lowercase();
+    let mut selected = Selection::new();
+
+    for part in query.split(',').flat_map(|part| part.split(" or ")) {
+        let part = part.trim();
+        if let Some(negated) = part.strip_prefix("not ") {
+            let removed = intersection(negated, data)?;
+            selected.retain(|browser| !removed.contains(browser));
+        } else {
+            selected.extend(intersection(part, data)?);
+        }
+    }
+
+    Ok(selected)
+}
+
+fn intersection(query: &str, data: &CaniuseData) -> Result<Selection, QueryError> {
+    let mut parts = query.split(" and ");
+    let mut selected = atom(parts.next().unwrap_or_default().trim(), data)?;
+    for part in parts {
+        let part = part.trim();
+        let (negated, part) = match part.strip_prefix("not ") {
+            Some(part) => (true, part),
+            None => (false, part),
+        };
+        let other = atom(part, data)?;
+        selected.retain(|browser| other.contains(browser) != negated);
+    }
+    Ok(selected)
+}
+
+fn atom(query: &str, data: &CaniuseData) -> Result<Selection, QueryError> {
+    let unsupported = || QueryError::Unsupported(query.to_owned());
+    let words: Vec<&str> = match split_comparison(query) {
+        Some((op, usage)) if usage.ends_with('%') => vec![op, usage],
+        _ => query.split_whitespace().collect(),
+    };
+
+    match words.as_slice() {
+        ["defaults"] => evaluate("> 0.5%, last 2 versions, firefox esr, not dead", data),
+        ["dead"] => Ok(releases(data, |agent, release, _| {
+            DEAD.iter().any(|&(dead, last)| {
+                dead == agent
+                    && caniuse::parse_version(release).is_some_and(|version| {
+                        let last = caniuse::parse_version(last).unwrap_or_default();
+                        caniuse::compare(&version, &last) != Ordering::Greater
+                    })
+            })
+        })),
+        ["firefox" | "ff" | "fx", "esr"] => Ok(releases(data, |agent, release, _| {
+            agent == "firefox" && FIREFOX_ESR.contains(&release)
+        })),
+        ["unreleased", "versions"] => Ok(releases(data, |_, _, release| {
+            release.release_date.is_none()
+        })),
+        ["unreleased", name, "versions"] => {
+            let agent = agent_name(name, data)?;
+            Ok(releases(data, |name, _, release| {
+                name == agent && release.release_date.is_none()
+            }))
+        }
+        ["cover", usage] => {
+            let usage = percentage(usage).ok_or_else(unsupported)?;
+            Ok(cover(data, usage))
+        }
+        [op, usage] if usage.ends_with('%') => {
+            let usage = percentage(usage).ok_or_else(unsupported)?;
+            let op = comparison(op).ok_or_else(unsupported)?;
+            Ok(releases(data, |_, _, release| {
+                op(release
+                    .global_usage
+                    .partial_cmp(&usage)
+                    .unwrap_or(Ordering::Less))
+            }))
+        }
+        ["last", count, "version" | "versions"] => {
+            let count = count.parse().map_err(|_| unsupported())?;
+            let mut selected = Selection::new();
+            for agent in data.agents.keys() {
+                selected.extend(last_versions(data, agent, count, false));
+            }
+            Ok(selected)
+        }
+        ["last", count, "major", "version" | "versions"] => {
+            let count = count.parse().map_err(|_| unsupported())?;
+            let mut selected = Selection::new();
+            for agent in data.agents.keys() {
+                selected.extend(last_versions(data, agent, count, true));
+            }
+            Ok(selected)
+        }
+        ["last", count, name, "version" | "versions"] => {
+            let count = count.parse().map_err(|_| unsupported())?;
+            Ok(last_versions(data, agent_name(name, data)?, count, false))
+        }
+        ["last", count, name, "major", "version" | "versions"] => {
+            let count = count.parse().map_err(|_| unsupported())?;
+            Ok(last_versions(data, agent_name(name, data)?, count, true))
+        }
+        ["last", years, "year" | "years"] => {
+            let years: f64 = years.parse().map_err(|_| unsupported())?;
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            Ok(released_since(
+                data,
+                now.as_secs() as i64 - (years * YEAR) as i64,
+            ))
+        }
+        ["since", date] => {
+            let since = timestamp(date).ok_or_else(unsupported)?;
+            Ok(released_since(data, since))
+        }
+        ["supports", feature] | ["partially", "supports", feature] => {
+            Ok(supporting(data, feature, &["y", "a"]))
+        }
+        ["fully", "supports", feature] => Ok(supporting(data, feature, &["y"])),
+        [name, op, version] => {
+            let agent = agent_name(name, data)?;
+            let op = comparison(op).ok_or_else(unsupported)?;
+            let bound = caniuse::parse_version(version).ok_or_else(unsupported)?;
+            Ok(releases(data, |name, release, _| {
+                name == agent && version_matches(release, &bound, &op)
+            }))
+        }
+        [name, "all"] => {
+            let agent = agent_name(name, data)?;
+            Ok(releases(data, |name, _, _| name == agent))
+        }
+        [name, version] => {
+            let agent = agent_name(name, data)?;
+            let exact = |release: &str| release == *version;
+            if (data.agents[agent].version_list.iter()).any(|r| exact(&r.version)) {
+                return Ok(releases(data, |name, release, _| {
+                    name == agent && exact(release)
+                }));
+            }
+
+            let (low, high) = version.split_once('-').unwrap_or((version, version));
+            let low = caniuse::parse_version(low).ok_or_else(unsupported)?;
+            let high = caniuse::parse_version(high).ok_or_else(unsupported)?;
+            let at_least = comparison(">=").ok_or_else(unsupported)?;
+            let at_most = comparison("<=").ok_or_else(unsupported)?;
+            Ok(releases(data, |name, release, _| {
+                name == agent
+                    && version_matches(release, &low, &at_least)
+                    && version_matches(release, &high, &at_most)
+            }))
+        }
+        _ => Err(unsupported()),
+    }
+}
+
+/// The average length of a year in seconds
+const YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
+
+/// Splits a comparison without spaces, like `>0.5%`, into operator and value
+fn split_comparison(query: &str) -> Option<(&str, &str)> {
+    let value = query.trim_start_matches(['<', '>', '=']);
+    let op = &query[..query.len() - value.len()];
+    if op.is_empty() || value.is_empty() || value.contains(' ') {
+        return None;
+    }
+    Some((op, value))
+}
+
+fn percentage(usage: &str) -> Option<f64> {
+    usage.strip_suffix('%')?.parse().ok()
+}
+
+/// Selects the releases of every agent for which `select` returns `true`
+fn releases<F>(data: &CaniuseData, select: F) -> Selection
+where
+    F: Fn(&str, &str, &caniuse::Release) -> bool,
+{
+    let mut selected = Selection::new();
+    for (agent, details) in &data.agents {
+        for release in &details.version_list {
+            if select(agent, &release.version, release) {
+                selected.insert((agent.clone(), release.version.clone()));
+            }
+        }
+    }
+    selected
+}
+
+/// Selects the `count` latest released versions of `agent`, or all releases
+/// of its `count` latest major versions if `major` is set
+fn last_versions(
+    data: &CaniuseData,
+    agent: &str,
+    count: usize,
+    major: bool,
+) -> Selection {
+    let released = (data.agents.get(agent).into_iter())
+        .flat_map(|details| &details.version_list)
+        .filter(|release| release.release_date.is_some())
+        .collect::<Vec<_>>();
+
+    let major_of = |release: &caniuse::Release| {
+        let version = release.version.split(['.', '-']).next().unwrap_or_default();
+        version.to_owned()
+    };
+
+    let mut majors = Vec::new();
+    let mut selected = Selection::new();
+    for release in released.iter().rev() {
+        let key = if major {
+            major_of(release)
+        } else {
+            release.version.clone()
+        };
+        if !majors.contains(&key) {
+            if majors.len() == count {
+                break;
+            }
+            majors.push(key);
+        }
+        selected.insert((agent.to_owned(), release.version.clone()));
+    }
+    selected
+}
+
+/// Selects the releases with the most global usage until they cover `usage`
+/// percent of it
+fn cover(data: &CaniuseData, usage: f64) -> Selection {
+    let mut releases = (data.agents.iter())
+        .flat_map(|(agent, details)| {
+            (details.version_list.iter()).map(move |release| (agent, release))
+        })
+        .collect::<Vec<_>>();
+    releases.sort_by(|(_, a), (_, b)| {
+        (b.global_usage.partial_cmp(&a.global_usage)).unwrap_or(Ordering::Equal)
+    });
+
+    let mut covered = 0.0;
+    let mut selected = Selection::new();
+    for (agent, release) in releases {
+        if covered >= usage {
+            break;
+        }
+        covered += release.global_usage;
+        selected.insert((agent.clone(), release.version.clone()));
+    }
+    selected
+}
+
+/// Selects the releases published at or after the Unix timestamp `since`
+fn released_since(data: &CaniuseData, since: i64) -> Selection {
+    releases(data, |_, _, release| {
+        release.release_date.is_some_and(|date| date >= since)
+    })
+}
+
+/// Selects the releases whose support of `feature` is one of `accepted`,
+/// ignoring notes and flags
+fn supporting(data: &CaniuseData, feature: &str, accepted: &[&str]) -> Selection {
+    let stats = match data.data.get(feature) {
+        Some(feature) => &feature.stats,
+        None => return Selection::new(),
+    };
+
+    releases(data, |agent, version, _| {
+        (stats.get(agent))
+            .and_then(|releases| releases.get(version))
+            .and_then(|support| support.split_whitespace().next())
+            .is_some_and(|support| accepted.contains(&support))
+    })
+}
+
+/// Parses a date like `2020`, `2020-03` or `2020-03-15` to the Unix timestamp
+/// of its start in UTC
+fn timestamp(date: &str) -> Option<i64> {
+    let mut parts = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let year = parts.next()??;
+    let month = parts.next().unwrap_or(Some(1))?;
+    let day = parts.next().unwrap_or(Some(1))?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Days since the epoch of the proleptic Gregorian calendar, with years
+    // starting in March so that leap days come last
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era =
+        year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some((era * 146_097 + day_of_era - 719_468) * 86_400)
+}
+
+/// Compares the lower end of a release range with `bound`
+fn version_matches(release: &str, bound: &[u32], op: &impl Fn(Ordering) -> bool) -> bool {
+    let low = release.split('-').next().unwrap_or(release);
+    match caniuse::parse_version(low) {
+        Some(version) => op(caniuse::compare(&version, bound)),
+        None => false,
+    }
+}
+
+fn comparison(op: &str) -> Option<impl Fn(Ordering) -> bool> {
+    let accepted: &[Ordering] = match op {
+        ">" => &[Ordering::Greater],
+        ">=" => &[Ordering::Greater, Ordering::Equal],
+        "<" => &[Ordering::Less],
+        "<=" => &[Ordering::Less, Ordering::Equal],
+        _ => return None,
+    };
+    Some(move |ordering| accepted.contains(&ordering))
+}
+
+/// Resolves the name of a browser in a query to its caniuse agent
+fn agent_name<'a>(name: &'a str, data: &CaniuseData) -> Result<&'a str, QueryError> {
+    let agent = (ALIASES.iter())
+        .find(|(alias, _)| *alias == name)
+        .map_or(name, |&(_, agent)| agent);
+
+    if data.agents.contains_key(agent) {
+        Ok(agent)
+    } else {
+        Err(QueryError::UnknownBrowser(name.to_owned()))
+    }
+}
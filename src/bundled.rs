@@ -0,0 +1,49 @@
+use std::{convert::Infallible, str::FromStr, sync::OnceLock};
+
+use super::{Client, Parser, UserAgentParser};
+
+static PARSER: OnceLock<UserAgentParser> = OnceLock::new();
+
+impl UserAgentParser {
+    /// Returns a parser over the uap-core dataset embedded into the binary. It
+    /// is built on first use and shared by all later callers. See
+    /// `UserAgentParser::bundled_version` for the dataset it was built from.
+    pub fn bundled() -> &'static UserAgentParser {
+        PARSER.get_or_init(|| {
+            UserAgentParser::from_bytes(uaparser_data::REGEXES.as_bytes())
+                .expect("bundled regexes are invalid")
+        })
+    }
+
+    /// Returns the version of the bundled dataset, either the name of a
+    /// pinned snapshot like `2024-01` or `latest`
+    pub fn bundled_version() -> &'static str {
+        uaparser_data::VERSION
+    }
+}
+
+/// Parses a `Client` with the bundled parser, which never fails
+///
+/// ```rust
+/// # use uaparser::*;
+/// # fn main() -> Result<(), std::convert::Infallible> {
+/// let client: Client = "Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Firefox/70.0".parse()?;
+/// assert_eq!(client.user_agent.family, "Firefox");
+/// # Ok(())
+/// # }
+/// ```
+impl FromStr for Client {
+    type Err = Infallible;
+
+    fn from_str(user_agent: &str) -> Result<Client, Infallible> {
+        Ok(Client::from(user_agent))
+    }
+}
+
+/// Parses a `Client` with the bundled parser, which also provides
+/// `TryFrom<&str>` for generic code
+impl From<&str> for Client {
+    fn from(user_agent: &str) -> Client {
+        UserAgentParser::bundled().parse(user_agent)
+    }
+}
@@ -0,0 +1,513 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use super::{Client, Device, Parser, UserAgent, OS};
+
+type TransformFn = dyn Fn(&mut String) + Send + Sync;
+
+/// Locales in the forms `KeyNormalizer::locales` strips, for its samples
+const SAMPLE_LOCALES: &[&str] = &[
+    "en-US", "en-GB", "de-DE", "fr-FR", "es-ES", "it-IT", "nl-NL", "pl-PL", "pt-BR",
+    "ru-RU", "tr-TR", "ja-JP", "ko-KR", "zh-CN", "zh-TW", "en_us", "de_de", "zh_cn",
+];
+
+/// User agents sending a locale, with `{}` in its place
+const LOCALE_TEMPLATES: &[&str] = &[
+    "Mozilla/5.0 (Linux; U; Android 4.4.2; {}; GT-I9505 Build/KOT49H) \
+     AppleWebKit/534.30 (KHTML, like Gecko) Version/4.0 Mobile Safari/534.30",
+    "Mozilla/5.0 (Windows; U; Windows NT 6.1; {}; rv:1.9.2) Gecko/20100115 \
+     Firefox/3.6",
+    "Mozilla/5.0 (Macintosh; U; Intel Mac OS X 10_6_8; {}) AppleWebKit/533.21.1 \
+     (KHTML, like Gecko) Version/5.0.5 Safari/533.21.1",
+    "Mozilla/5.0 (iPhone; U; CPU iPhone OS 4_3 like Mac OS X; {}) \
+     AppleWebKit/533.17.9 (KHTML, like Gecko) Version/5.0.2 Mobile/8F190 \
+     Safari/6533.18.5",
+    "Opera/9.80 (J2ME/MIDP; Opera Mini/4.2.14912/870; U; {}) Presto/2.4.15",
+    "Mozilla/5.0 (X11; {}; Linux x86_64) Firefox/70.0",
+];
+
+/// Hashes in the forms `KeyNormalizer::build_hashes` strips, for its samples
+const SAMPLE_HASHES: &[&str] = &[
+    "3f9a2c1e7b",
+    "a1b2c3d4e5f6",
+    "DEADBEEF01",
+    "0123456789abcdef0123456789abcdef01234567",
+];
+
+/// User agents sending a build hash, with `{}` in its place
+const HASH_TEMPLATES: &[&str] = &[
+    "Mozilla/5.0 (Linux; Android 10; K Build/{}) AppleWebKit/537.36 \
+     (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36",
+    "Mozilla/5.0 (X11; Linux x86_64) Firefox/70.0 Build/{}",
+    "MyApp/1.2.3-{} CFNetwork/1240.0.4 Darwin/20.6.0",
+    "MyApp/1.2.3 ({}) Dalvik/2.1.0 (Linux; U; Android 11; Pixel 5)",
+];
+
+/// A transform of `KeyNormalizer`, along with the user agents it's checked
+/// against, if any
+struct Transform {
+    apply: Box<TransformFn>,
+    samples: Option<fn() -> Vec<String>>,
+}
+
+/// Fills every template with every token
+fn samples(templates: &[&str], tokens: &[&str]) -> Vec<String> {
+    (templates.iter())
+        .flat_map(|template| {
+            tokens
+                .iter()
+                .map(move |token| template.replace("{}", token))
+        })
+        .collect()
+}
+
+/// Storage for the results of `CachedParser`, keyed by the complete user
+/// agent. Implementations backed by a shared store, like `RedisCache`, let
+/// several instances of a service reuse each other's results.
+///
+/// Caches are best-effort: failing to read counts as a miss, and failing to
+/// write is ignored, so that an unavailable store never breaks parsing.
+pub trait UaCache {
+    /// Returns the cached result for `user_agent`, unless it has expired
+    fn get(&self, user_agent: &str) -> Option<Client>;
+
+    /// Stores the result for `user_agent`, to expire after `ttl` if set
+    fn put(&self, user_agent: &str, client: &Client, ttl: Option<Duration>);
+}
+
+impl<C: UaCache + ?Sized> UaCache for &C {
+    fn get(&self, user_agent: &str) -> Option<Client> {
+        (**self).get(user_agent)
+    }
+
+    fn put(&self, user_agent: &str, client: &Client, ttl: Option<Duration>) {
+        (**self).put(user_agent, client, ttl)
+    }
+}
+
+impl<C: UaCache + ?Sized> UaCache for std::sync::Arc<C> {
+    fn get(&self, user_agent: &str) -> Option<Client> {
+        (**self).get(user_agent)
+    }
+
+    fn put(&self, user_agent: &str, client: &Client, ttl: Option<Duration>) {
+        (**self).put(user_agent, client, ttl)
+    }
+}
+
+#[derive(Debug)]
+struct LruEntry {
+    client: Client,
+    expires: Option<Instant>,
+    /// Position of the entry in `LruState::order`
+    used: u64,
+}
+
+#[derive(Debug, Default)]
+struct LruState {
+    entries: HashMap<String, LruEntry>,
+    /// User agents by the time they were last used, oldest first
+    order: BTreeMap<u64, String>,
+    clock: u64,
+}
+
+/// An in-memory `UaCache` holding up to `capacity` user agents, which evicts
+/// the least recently used one to make room for another
+#[derive(Debug)]
+pub struct LruCache {
+    capacity: usize,
+    state: Mutex<LruState>,
+}
+
+impl LruCache {
+    pub fn new(capacity: usize) -> LruCache {
+        LruCache {
+            capacity,
+            state: Mutex::new(LruState::default()),
+        }
+    }
+
+    /// Returns the number of user agents in the cache, including expired ones
+    /// that weren't looked up since
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns all unexpired entries, least recently used first
+    pub(crate) fn entries(&self) -> Vec<(String, Client)> {
+        let state = self.lock();
+        let now = Instant::now();
+        (state.order.values())
+            .filter_map(|user_agent| {
+                let entry = state.entries.get(user_agent)?;
+                let expired = entry.expires.is_some_and(|expires| expires <= now);
+                (!expired).then(|| (user_agent.clone(), entry.client.clone()))
+            })
+            .collect()
+    }
+
+    /// Drops all entries whose user agent `keep` rejects
+    pub(crate) fn retain(&self, mut keep: impl FnMut(&str) -> bool) {
+        let mut state = self.lock();
+        let state = &mut *state;
+        let entries = &mut state.entries;
+        state.order.retain(|_, user_agent| {
+            let kept = keep(user_agent);
+            if !kept {
+                entries.remove(user_agent.as_str());
+            }
+            kept
+        });
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LruState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl UaCache for LruCache {
+    fn get(&self, user_agent: &str) -> Option<Client> {
+        let mut state = self.lock();
+        let state = &mut *state;
+        let entry = state.entries.get_mut(user_agent)?;
+
+        if entry
+            .expires
+            .is_some_and(|expires| expires <= Instant::now())
+        {
+            state.order.remove(&entry.used);
+            state.entries.remove(user_agent);
+            return None;
+        }
+
+        state.clock += 1;
+        let name = state.order.remove(&entry.used).unwrap_or_default();
+        state.order.insert(state.clock, name);
+        entry.used = state.clock;
+        Some(entry.client.clone())
+    }
+
+    fn put(&self, user_agent: &str, client: &Client, ttl: Option<Duration>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut state = self.lock();
+        state.clock += 1;
+        let entry = LruEntry {
+            client: client.clone(),
+            expires: ttl.map(|ttl| Instant::now() + ttl),
+            used: state.clock,
+        };
+
+        if let Some(previous) = state.entries.insert(user_agent.to_owned(), entry) {
+            state.order.remove(&previous.used);
+        }
+        let clock = state.clock;
+        state.order.insert(clock, user_agent.to_owned());
+
+        while state.entries.len() > self.capacity {
+            match state.order.pop_first() {
+                Some((_, oldest)) => state.entries.remove(&oldest),
+                None => break,
+            };
+        }
+    }
+}
+
+/// Transforms user agents into the keys `CachedParser` looks them up by, so
+/// that near-duplicates differing only in insignificant tokens share an
+/// entry. Transforms run in the order they were added.
+///
+/// The built-in transforms are checked against a parser with `verify`
+/// before they're used, and dropped if the rules of the parser look at the
+/// tokens they strip. Custom transforms aren't checked.
+///
+/// ```rust
+/// # use uaparser::*;
+/// let normalizer = KeyNormalizer::new().build_hashes().locales();
+/// assert_eq!(
+///     normalizer.normalize("Mozilla/5.0 (Linux; U; Android 4.4; de-DE) Build/3f9a2c1e7b"),
+///     "Mozilla/5.0 (Linux; U; Android 4.4) Build/"
+/// );
+/// ```
+#[derive(Default)]
+pub struct KeyNormalizer {
+    transforms: Vec<Transform>,
+}
+
+impl std::fmt::Debug for KeyNormalizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyNormalizer")
+            .field("transforms", &self.transforms.len())
+            .finish()
+    }
+}
+
+impl KeyNormalizer {
+    pub fn new() -> KeyNormalizer {
+        KeyNormalizer::default()
+    }
+
+    /// Appends a transform of the key. It's the caller's responsibility that
+    /// it only strips what no rule of the parser looks at, since `verify`
+    /// can't check it.
+    pub fn transform<F>(mut self, transform: F) -> KeyNormalizer
+    where
+        F: Fn(&mut String) + Send + Sync + 'static,
+    {
+        self.transforms.push(Transform {
+            apply: Box::new(transform),
+            samples: None,
+        });
+        self
+    }
+
+    /// Appends a built-in transform, checked against `samples` by `verify`
+    fn checked<F>(mut self, samples: fn() -> Vec<String>, transform: F) -> KeyNormalizer
+    where
+        F: Fn(&mut String) + Send + Sync + 'static,
+    {
+        self.transforms.push(Transform {
+            apply: Box::new(transform),
+            samples: Some(samples),
+        });
+        self
+    }
+
+    /// Drops every built-in transform that changes the result of `parser`
+    /// for any of a set of sample user agents carrying the tokens it strips,
+    /// as when a rule captures a build hash or only matches some locales.
+    /// `CachedParser::normalize_keys` and `Parser::parse_batch_normalized`
+    /// do this themselves.
+    pub fn verify(mut self, parser: &impl Parser) -> KeyNormalizer {
+        self.transforms.retain(|transform| {
+            let samples = match transform.samples {
+                Some(samples) => samples(),
+                None => return true,
+            };
+            samples.iter().all(|sample| {
+                let mut key = sample.clone();
+                (transform.apply)(&mut key);
+                key == *sample || parser.parse(&key) == parser.parse(sample)
+            })
+        });
+        self
+    }
+
+    /// Strips hexadecimal runs of at least eight characters with both digits
+    /// and letters, like commit hashes in build numbers
+    pub fn build_hashes(self) -> KeyNormalizer {
+        let samples = || samples(HASH_TEMPLATES, SAMPLE_HASHES);
+        self.checked(samples, |key| {
+            let mut stripped = String::with_capacity(key.len());
+            let mut rest = key.as_str();
+            while !rest.is_empty() {
+                let alphanumeric = rest.starts_with(|c: char| c.is_ascii_alphanumeric());
+                let end = (rest
+                    .find(|c: char| c.is_ascii_alphanumeric() != alphanumeric))
+                .unwrap_or(rest.len());
+                let token = &rest[..end];
+                let is_hash = alphanumeric
+                    && token.len() >= 8
+                    && token.chars().all(|c| c.is_ascii_hexdigit())
+                    && token.contains(|c: char| c.is_ascii_digit())
+                    && token.contains(|c: char| c.is_ascii_alphabetic());
+                if !is_hash {
+                    stripped.push_str(token);
+                }
+                rest = &rest[end..];
+            }
+            *key = stripped;
+        })
+    }
+
+    /// Strips locales like `en-US` from the `;` separated lists in
+    /// parentheses
+    pub fn locales(self) -> KeyNormalizer {
+        let samples = || samples(LOCALE_TEMPLATES, SAMPLE_LOCALES);
+        self.checked(samples, |key| {
+            let mut stripped = String::with_capacity(key.len());
+            let mut rest = key.as_str();
+            while let Some(open) = rest.find('(') {
+                let close = match rest[open..].find(')') {
+                    Some(close) => open + close,
+                    None => break,
+                };
+                stripped.push_str(&rest[..=open]);
+                let parts = rest[open + 1..close]
+                    .split(';')
+                    .filter(|part| !is_locale(part.trim()))
+                    .collect::<Vec<_>>();
+                stripped.push_str(parts.join(";").trim_start());
+                stripped.push(')');
+                rest = &rest[close + 1..];
+            }
+            stripped.push_str(rest);
+            *key = stripped;
+        })
+    }
+
+    /// Returns the key of `user_agent`
+    pub fn normalize<'a>(&self, user_agent: &'a str) -> Cow<'a, str> {
+        if self.transforms.is_empty() {
+            return Cow::Borrowed(user_agent);
+        }
+
+        let mut key = user_agent.to_owned();
+        for transform in &self.transforms {
+            (transform.apply)(&mut key);
+        }
+        if key == user_agent {
+            Cow::Borrowed(user_agent)
+        } else {
+            Cow::Owned(key)
+        }
+    }
+}
+
+/// Returns whether `part` looks like `en-US` or `en_us`. Bare two letter
+/// tokens like `de` aren't taken as locales, since tokens like the `wv` of
+/// Android WebViews look the same.
+fn is_locale(part: &str) -> bool {
+    let letters = |s: &str| s.len() == 2 && s.chars().all(|c| c.is_ascii_alphabetic());
+
+    match part.split_once(['-', '_']) {
+        Some((language, region)) => {
+            letters(language)
+                && language.chars().all(|c| c.is_ascii_lowercase())
+                && letters(region)
+        }
+        None => false,
+    }
+}
+
+/// A `Parser` answering from a `UaCache` before falling back to another
+/// parser, whose full results it stores in the cache. Results of single
+/// categories are taken from cached full results, but aren't stored. Match
+/// info describes a single user agent, so it's neither stored nor returned,
+/// whether a result came from the cache or not.
+///
+/// With a `KeyNormalizer`, results are looked up by the normalized user
+/// agent. Later user agents with the same key are answered from the cache
+/// without being parsed, so the built-in transforms are verified against
+/// the parser first, see `KeyNormalizer::verify`, and those that rules look
+/// at aren't applied. In addition, a result is only stored under a
+/// normalized key if parsing the key itself gives the same result, and
+/// under the complete user agent otherwise.
+///
+/// ```rust
+/// # use uaparser::*;
+/// # use std::time::Duration;
+/// let parser = CachedParser::new(
+///     UserAgentParser::from_yaml("./src/core/regexes.yaml")?,
+///     LruCache::new(10_000),
+/// )
+/// .ttl(Duration::from_secs(3600));
+///
+/// let client = parser.parse("Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Firefox/70.0");
+/// assert_eq!(parser.cache().len(), 1);
+/// # Ok::<_, Error>(())
+/// ```
+#[derive(Debug)]
+pub struct CachedParser<P, C> {
+    parser: P,
+    cache: C,
+    ttl: Option<Duration>,
+    normalizer: Option<KeyNormalizer>,
+}
+
+impl<P: Parser, C: UaCache> CachedParser<P, C> {
+    pub fn new(parser: P, cache: C) -> CachedParser<P, C> {
+        CachedParser {
+            parser,
+            cache,
+            ttl: None,
+            normalizer: None,
+        }
+    }
+
+    /// Looks up results by the key `normalizer` produces, after dropping the
+    /// transforms that would change results of the parser
+    pub fn normalize_keys(mut self, normalizer: KeyNormalizer) -> CachedParser<P, C> {
+        self.normalizer = Some(normalizer.verify(&self.parser));
+        self
+    }
+
+    /// Lets stored results expire after `ttl`, so that they're parsed again
+    /// once a fleet has moved to a newer dataset. Results don't expire by
+    /// default.
+    pub fn ttl(mut self, ttl: Duration) -> CachedParser<P, C> {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn cache(&self) -> &C {
+        &self.cache
+    }
+
+    fn key<'a>(&self, user_agent: &'a str) -> Cow<'a, str> {
+        match &self.normalizer {
+            Some(normalizer) => normalizer.normalize(user_agent),
+            None => Cow::Borrowed(user_agent),
+        }
+    }
+
+    fn lookup(&self, user_agent: &str) -> Option<Client> {
+        let key = self.key(user_agent);
+        self.cache.get(&key).or_else(|| match key {
+            Cow::Owned(_) => self.cache.get(user_agent),
+            Cow::Borrowed(_) => None,
+        })
+    }
+}
+
+impl<P: Parser, C: UaCache> Parser for CachedParser<P, C> {
+    fn parse(&self, user_agent: &str) -> Client {
+        if let Some(client) = self.lookup(user_agent) {
+            return client;
+        }
+
+        let mut client = self.parser.parse(user_agent);
+        client.match_info = None;
+
+        let key = self.key(user_agent);
+        let preserved = key == user_agent || {
+            let mut normalized = self.parser.parse(&key);
+            normalized.match_info = None;
+            normalized == client
+        };
+        let key = if preserved { &key } else { user_agent };
+        self.cache.put(key, &client, self.ttl);
+        client
+    }
+
+    fn parse_device(&self, user_agent: &str) -> Device {
+        match self.lookup(user_agent) {
+            Some(client) => client.device,
+            None => self.parser.parse_device(user_agent),
+        }
+    }
+
+    fn parse_os(&self, user_agent: &str) -> OS {
+        match self.lookup(user_agent) {
+            Some(client) => client.os,
+            None => self.parser.parse_os(user_agent),
+        }
+    }
+
+    fn parse_user_agent(&self, user_agent: &str) -> UserAgent {
+        match self.lookup(user_agent) {
+            Some(client) => client.user_agent,
+            None => self.parser.parse_user_agent(user_agent),
+        }
+    }
+}
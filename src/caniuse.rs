@@ -0,0 +1,196 @@
+#[cfg(feature = "caniuse-bundled")]
+use std::sync::OnceLock;
+use std::{cmp::Ordering, collections::HashMap};
+
+use serde_derive::Deserialize;
+
+use super::{Client, Error};
+
+/// A snapshot of the caniuse data trimmed to the browser releases and a few
+/// features, see `CaniuseData::bundled`
+#[cfg(feature = "caniuse-bundled")]
+static BUNDLED: &str = include_str!("caniuse.json");
+
+/// The browser and feature data of caniuse, read from the
+/// `fulldata-json/data-2.0.json` file of the caniuse repository or the
+/// `caniuse-db` package. Since it's updated far more often than the crate is
+/// released, the snapshot of the `caniuse-bundled` feature is only meant for
+/// when the data can't be shipped alongside the application.
+///
+/// ```rust
+/// # use uaparser::*;
+/// # let parser = UserAgentParser::from_yaml("./src/core/regexes.yaml").unwrap();
+/// let data = CaniuseData::from_json(br#"{
+///     "agents": {"firefox": {"version_list": [{"version": "70", "release_date": 1}]}},
+///     "data": {"webp": {"stats": {"firefox": {"64": "n", "70": "y"}}}}
+/// }"#)?;
+///
+/// let client = parser.parse("Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Firefox/70.0");
+/// assert_eq!(client.supports("webp", &data), Some(true));
+/// # Ok::<_, Error>(())
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+pub struct CaniuseData {
+    pub(crate) agents: HashMap<String, Agent>,
+    #[serde(default)]
+    pub(crate) data: HashMap<String, Feature>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct Feature {
+    /// The support of every release of every agent, e.g. `y`, `n` or `a x #2`
+    pub stats: HashMap<String, HashMap<String, String>>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct Agent {
+    pub version_list: Vec<Release>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct Release {
+    pub version: String,
+    #[serde(default)]
+    pub global_usage: f64,
+    /// Unset for versions that aren't released yet
+    #[cfg_attr(not(feature = "browserslist"), allow(dead_code))]
+    pub release_date: Option<i64>,
+}
+
+impl CaniuseData {
+    /// Attempts to load `CaniuseData` from the path to `data-2.0.json`
+    #[cfg(feature = "fs")]
+    pub fn from_path(path: impl AsRef<std::path::Path>) -> Result<CaniuseData, Error> {
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    /// Attempts to load `CaniuseData` from the contents of `data-2.0.json`
+    pub fn from_json(bytes: &[u8]) -> Result<CaniuseData, Error> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    /// Returns the snapshot of the caniuse data embedded in the crate. Besides
+    /// the releases of every browser with their usage, it only has the
+    /// features `webp`, `avif`, `jpegxl` and `es6-module`.
+    #[cfg(feature = "caniuse-bundled")]
+    pub fn bundled() -> &'static CaniuseData {
+        static DATA: OnceLock<CaniuseData> = OnceLock::new();
+        DATA.get_or_init(|| {
+            CaniuseData::from_json(BUNDLED.as_bytes())
+                .expect("the embedded caniuse data is valid")
+        })
+    }
+
+    /// Returns the share of global usage, in percent, of the browser release
+    /// of `client`
+    pub fn usage(&self, client: &Client) -> Option<f64> {
+        let (agent, version) = agent(client)?;
+        let releases = &self.agents.get(agent)?.version_list;
+        (releases.iter())
+            .find(|release| covers(&release.version, &version))
+            .map(|release| release.global_usage)
+    }
+
+    /// Returns whether the browser of `client` supports the caniuse `feature`,
+    /// like `webp`. Partial support and support behind a prefix or flag
+    /// count as unsupported. Returns `None` if the browser, its version or the
+    /// feature is unknown, including for browsers that caniuse doesn't list,
+    /// whatever their engine.
+    pub fn supports(&self, client: &Client, feature: &str) -> Option<bool> {
+        let (agent, version) = agent(client)?;
+        let stats = self.data.get(feature)?.stats.get(agent)?;
+        let (_, support) =
+            (stats.iter()).find(|(release, _)| covers(release, &version))?;
+
+        let mut flags = support.split_whitespace();
+        match flags.next()? {
+            "u" => None,
+            "y" => Some(flags.all(|flag| flag.starts_with('#'))),
+            _ => Some(false),
+        }
+    }
+}
+
+impl Client {
+    /// Returns whether the browser supports the caniuse `feature` according
+    /// to `data`, see `CaniuseData::supports`
+    pub fn supports(&self, feature: &str, data: &CaniuseData) -> Option<bool> {
+        data.supports(self, feature)
+    }
+}
+
+/// Returns the caniuse agent of `client` along with its version. Browsers on
+/// iOS all use the system's WebKit, so they're reported as `ios_saf` with the
+/// version of iOS. Browsers that caniuse doesn't list have no agent, even if
+/// they're based on Chromium, since their support of features can differ.
+pub(crate) fn agent(client: &Client) -> Option<(&'static str, Vec<u32>)> {
+    let ua = &client.user_agent;
+    let agent = match ua.family.as_str() {
+        _ if client.os.family == "iOS" => "ios_saf",
+        "Chrome" | "Chromium" | "HeadlessChrome" => "chrome",
+        "Chrome Mobile" | "Chrome Mobile WebView" => "and_chr",
+        "Firefox" => "firefox",
+        "Firefox Mobile" => "and_ff",
+        "Safari" => "safari",
+        "Edge" => "edge",
+        "Opera" => "opera",
+        "Opera Mobile" => "op_mob",
+        "Opera Mini" => "op_mini",
+        "Samsung Internet" => "samsung",
+        "IE" => "ie",
+        "IE Mobile" => "ie_mob",
+        "UC Browser" => "and_uc",
+        "QQ Browser" => "and_qq",
+        "Baidu Browser" => "baidu",
+        "Android" => "android",
+        _ => return None,
+    };
+
+    let version = if agent == "ios_saf" {
+        [&client.os.major, &client.os.minor, &client.os.patch]
+    } else {
+        [&ua.major, &ua.minor, &ua.patch]
+    };
+    let version = (version.iter())
+        .map_while(|part| part.as_deref()?.parse().ok())
+        .collect();
+
+    Some((agent, version))
+}
+
+/// Returns whether `version` falls into a caniuse release like `118`, `17.0`
+/// or the range `16.6-16.7`, compared at the precision of the release.
+/// `all` covers every version, and releases like `TP` none.
+pub(crate) fn covers(release: &str, version: &[u32]) -> bool {
+    if release == "all" {
+        return true;
+    }
+
+    let (low, high) = release.split_once('-').unwrap_or((release, release));
+    match (parse_version(low), parse_version(high)) {
+        (Some(low), Some(high)) => {
+            compare(version, &low) != Ordering::Less
+                && compare(version, &high) != Ordering::Greater
+        }
+        _ => false,
+    }
+}
+
+pub(crate) fn parse_version(version: &str) -> Option<Vec<u32>> {
+    version.split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// Compares `version` to `bound` at the precision of `bound`, treating missing
+/// parts of `version` as zero
+pub(crate) fn compare(version: &[u32], bound: &[u32]) -> Ordering {
+    let parts = bound.iter().enumerate();
+    for (index, bound) in parts {
+        let part = version.get(index).copied().unwrap_or(0);
+        match part.cmp(bound) {
+            Ordering::Equal => continue,
+            ordering => return ordering,
+        }
+    }
+    Ordering::Equal
+}
@@ -0,0 +1,21 @@
+use serde_derive::Deserialize;
+
+/// The three kinds of rules in a `regexes.yaml` dataset
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    UserAgent,
+    Os,
+    Device,
+}
+
+impl Category {
+    /// Returns the key of the category's rules in a dataset
+    pub fn key(self) -> &'static str {
+        match self {
+            Category::UserAgent => "user_agent_parsers",
+            Category::Os => "os_parsers",
+            Category::Device => "device_parsers",
+        }
+    }
+}
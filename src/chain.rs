@@ -0,0 +1,157 @@
+use super::{Client, Device, MatchInfo, Parser, UserAgent, OS};
+
+/// A `Parser` consulting several parsers in order, e.g. one over custom rules
+/// before one over the uap-core dataset. Every category is resolved on its
+/// own, taking the first result that a rule of the parser matched, see
+/// `Parser::parse_matched`, so priorities can differ by category without
+/// merging rule files.
+///
+/// ```rust
+/// # use uaparser::*;
+/// let mut app = Client::default();
+/// app.user_agent.family = "MyApp".to_owned();
+///
+/// let mut linux = Client::default();
+/// linux.os.family = "Linux".to_owned();
+///
+/// let parser = ChainedParser::new()
+///     .with(MockParser::new().with("MyApp/1.0 (Linux)", app))
+///     .with(MockParser::with_fallback(linux));
+///
+/// let client = parser.parse("MyApp/1.0 (Linux)");
+/// assert_eq!(client.user_agent.family, "MyApp");
+/// assert_eq!(client.os.family, "Linux");
+/// ```
+#[derive(Default)]
+pub struct ChainedParser {
+    parsers: Vec<Box<dyn Parser + Send + Sync>>,
+    match_info: bool,
+}
+
+impl std::fmt::Debug for ChainedParser {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChainedParser")
+            .field("parsers", &self.parsers.len())
+            .field("match_info", &self.match_info)
+            .finish()
+    }
+}
+
+impl ChainedParser {
+    /// Creates an empty chain, which returns `Other` for every user agent
+    pub fn new() -> ChainedParser {
+        ChainedParser::default()
+    }
+
+    /// Appends `parser`, which is consulted after all parsers added before
+    pub fn with<P>(mut self, parser: P) -> ChainedParser
+    where
+        P: Parser + Send + Sync + 'static,
+    {
+        self.parsers.push(Box::new(parser));
+        self
+    }
+
+    /// Attaches `MatchInfo` to the results of `parse`, with every category
+    /// attributed to the rule of the parser that supplied it. The dataset of
+    /// each rule tells them apart, so the parsers should be built with both
+    /// `UserAgentParserBuilder::match_info` and `dataset_id`. Categories of
+    /// parsers that don't report match info are `None`.
+    ///
+    /// ```rust
+    /// # use uaparser::*;
+    /// let internal = r#"
+    /// user_agent_parsers: []
+    /// os_parsers: []
+    /// device_parsers:
+    ///   - regex: '(Kiosk)'
+    /// "#;
+    /// let internal = UserAgentParser::builder()
+    ///     .match_info(true)
+    ///     .dataset_id("internal")
+    ///     .build_from_bytes(internal.as_bytes())?;
+    /// let core = UserAgentParser::builder()
+    ///     .match_info(true)
+    ///     .dataset_id("uap-core")
+    ///     .build_from_yaml("./src/core/regexes.yaml")?;
+    ///
+    /// let parser = ChainedParser::new().with(internal).with(core).match_info(true);
+    /// let client = parser.parse("Mozilla/5.0 (X11; Linux x86_64; Kiosk) Firefox/70.0");
+    /// let info = client.match_info.unwrap();
+    /// assert_eq!(info.device.unwrap().dataset.as_deref(), Some("internal"));
+    /// assert_eq!(info.os.unwrap().dataset.as_deref(), Some("uap-core"));
+    /// # Ok::<_, Error>(())
+    /// ```
+    pub fn match_info(mut self, enabled: bool) -> ChainedParser {
+        self.match_info = enabled;
+        self
+    }
+
+    /// Resolves every category from the results of the parsers in order,
+    /// up to the first one that leaves no category unmatched
+    fn resolve(&self, user_agent: &str) -> (Client, [bool; 3]) {
+        let mut client = Client::default();
+        let mut info = MatchInfo::default();
+        // Whether the device, OS and user agent were matched
+        let mut matched = [false; 3];
+
+        for parser in &self.parsers {
+            let (mut result, found) = parser.parse_matched(user_agent);
+            let mut rules = result.match_info.take().unwrap_or_default();
+            let dataset = rules.dataset.take();
+            let attributed = (rules.device.iter_mut())
+                .chain(rules.os.iter_mut())
+                .chain(rules.user_agent.iter_mut());
+            for rule in attributed {
+                rule.dataset = rule.dataset.take().or_else(|| dataset.clone());
+            }
+
+            if !matched[0] {
+                matched[0] = found[0];
+                client.device = result.device;
+                info.device = rules.device;
+            }
+            if !matched[1] {
+                matched[1] = found[1];
+                client.os = result.os;
+                info.os = rules.os;
+            }
+            if !matched[2] {
+                matched[2] = found[2];
+                client.user_agent = result.user_agent;
+                info.user_agent = rules.user_agent;
+            }
+
+            if matched.iter().all(|&matched| matched) {
+                break;
+            }
+        }
+
+        if self.match_info {
+            client.match_info = Some(info);
+        }
+        (client, matched)
+    }
+}
+
+impl Parser for ChainedParser {
+    fn parse(&self, user_agent: &str) -> Client {
+        self.resolve(user_agent).0
+    }
+
+    fn parse_matched(&self, user_agent: &str) -> (Client, [bool; 3]) {
+        self.resolve(user_agent)
+    }
+
+    fn parse_device(&self, user_agent: &str) -> Device {
+        self.resolve(user_agent).0.device
+    }
+
+    fn parse_os(&self, user_agent: &str) -> OS {
+        self.resolve(user_agent).0.os
+    }
+
+    fn parse_user_agent(&self, user_agent: &str) -> UserAgent {
+        self.resolve(user_agent).0.user_agent
+    }
+}
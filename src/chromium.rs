@@ -0,0 +1,22 @@
+/// Tokens carrying the version of the Chromium engine a browser is based on
+const TOKENS: &[&str] = &["Chrome/", "Chromium/"];
+
+/// Finds the version of Chromium in user agents of Chromium based browsers,
+/// like Samsung Internet, Opera, Brave or Edge, which report their own
+/// version as the family version
+pub(crate) fn detect(user_agent: &str) -> Option<String> {
+    TOKENS.iter().find_map(|token| {
+        let start = user_agent.find(token)? + token.len();
+        let version = &user_agent[start..];
+        let end = version
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(version.len());
+        let version = version[..end].trim_end_matches('.');
+
+        if version.is_empty() {
+            None
+        } else {
+            Some(version.to_owned())
+        }
+    })
+}
@@ -1,10 +1,146 @@
-use super::{Deserialize, Device, UserAgent, OS};
+use std::{
+    borrow::Cow,
+    hash::{Hash, Hasher},
+};
+
+use super::{Deserialize, Device, MatchInfo, Product, Serialize, UserAgent, OS};
 
 /// Houses the `Device`, `OS`, and `UserAgent` structs, which each get parsed
-/// out from a user agent string by a `UserAgentParser`.
-#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+/// out from a user agent string by a `UserAgentParser`. Clients compare and
+/// hash by their parsed fields only, so results are equal regardless of
+/// whether the parser attached `MatchInfo`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "async-graphql", derive(async_graphql::SimpleObject))]
 pub struct Client {
     pub device: Device,
     pub os: OS,
     pub user_agent: UserAgent,
+    /// The rules behind each field, if requested from the parser
+    #[serde(skip)]
+    #[cfg_attr(feature = "arbitrary", arbitrary(default))]
+    #[cfg_attr(feature = "async-graphql", graphql(skip))]
+    pub match_info: Option<MatchInfo>,
+}
+
+impl PartialEq for Client {
+    fn eq(&self, other: &Client) -> bool {
+        self.device == other.device
+            && self.os == other.os
+            && self.user_agent == other.user_agent
+    }
+}
+
+impl Eq for Client {}
+
+impl Hash for Client {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.device.hash(state);
+        self.os.hash(state);
+        self.user_agent.hash(state);
+    }
+}
+
+impl Client {
+    /// Returns the result for user agents no rule matches, with the `Other`
+    /// family in every category
+    pub fn unknown() -> Client {
+        Client::default()
+    }
+
+    /// Returns whether no rule matched in any category, judged by the `Other`
+    /// family. Results of parsers with another `fallback_family` need to be
+    /// compared against that instead.
+    pub fn is_unknown(&self) -> bool {
+        self.device.is_unknown() && self.os.is_unknown() && self.user_agent.is_unknown()
+    }
+
+    /// Tells whether a rule matched the device, OS and user agent, in that
+    /// order. This is taken from the match info if present, and otherwise
+    /// judged by `is_unknown` of each category.
+    pub(crate) fn matched(&self) -> [bool; 3] {
+        match &self.match_info {
+            Some(info) => [
+                info.device.is_some(),
+                info.os.is_some(),
+                info.user_agent.is_some(),
+            ],
+            None => [
+                !self.device.is_unknown(),
+                !self.os.is_unknown(),
+                !self.user_agent.is_unknown(),
+            ],
+        }
+    }
+
+    /// Returns whether nothing at all is known, i.e. every category is
+    /// unknown and carries no further fields
+    pub fn is_empty(&self) -> bool {
+        self.device.is_empty() && self.os.is_empty() && self.user_agent.is_empty()
+    }
+
+    /// Lists every field under the flat, prefixed name used when a nested
+    /// structure isn't an option, e.g. `ua_family` or `device_brand`
+    pub(crate) fn flat_fields(&self) -> Vec<(&'static str, Option<Cow<'_, str>>)> {
+        fn borrowed(value: Option<&String>) -> Option<Cow<'_, str>> {
+            value.map(|value| Cow::Borrowed(value.as_str()))
+        }
+        fn product(product: &Option<Product>) -> [Option<Cow<'_, str>>; 2] {
+            let name = borrowed(product.as_ref().map(|p| &p.name));
+            [
+                name,
+                borrowed(product.as_ref().and_then(|p| p.version.as_ref())),
+            ]
+        }
+
+        let ua = &self.user_agent;
+        let [app_name, app_version] = product(&ua.app);
+        let [runtime_name, runtime_version] = product(&ua.runtime);
+        let [framework_name, framework_version] = product(&ua.framework);
+
+        vec![
+            ("ua_family", Some(Cow::Borrowed(ua.family.as_str()))),
+            ("ua_major", borrowed(ua.major.as_ref())),
+            ("ua_minor", borrowed(ua.minor.as_ref())),
+            ("ua_patch", borrowed(ua.patch.as_ref())),
+            (
+                "ua_chromium_version",
+                borrowed(ua.chromium_version.as_ref()),
+            ),
+            ("ua_app_name", app_name),
+            ("ua_app_version", app_version),
+            ("ua_runtime_name", runtime_name),
+            ("ua_runtime_version", runtime_version),
+            ("ua_framework_name", framework_name),
+            ("ua_framework_version", framework_version),
+            ("os_family", Some(Cow::Borrowed(self.os.family.as_str()))),
+            ("os_major", borrowed(self.os.major.as_ref())),
+            ("os_minor", borrowed(self.os.minor.as_ref())),
+            ("os_patch", borrowed(self.os.patch.as_ref())),
+            ("os_patch_minor", borrowed(self.os.patch_minor.as_ref())),
+            (
+                "os_arch",
+                self.os.arch.map(|arch| Cow::Borrowed(arch.as_str())),
+            ),
+            (
+                "os_bitness",
+                self.os
+                    .bitness
+                    .map(|bitness| Cow::Owned(bitness.to_string())),
+            ),
+            ("os_distro", borrowed(self.os.distro.as_ref())),
+            (
+                "os_distro_version",
+                borrowed(self.os.distro_version.as_ref()),
+            ),
+            (
+                "device_family",
+                Some(Cow::Borrowed(self.device.family.as_str())),
+            ),
+            ("device_brand", borrowed(self.device.brand.as_ref())),
+            ("device_model", borrowed(self.device.model.as_ref())),
+            ("device_kind", borrowed(self.device.kind.as_ref())),
+        ]
+    }
 }
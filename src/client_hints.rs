@@ -0,0 +1,137 @@
+/// A User-Agent Client Hint that a server can ask browsers to send
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ClientHint {
+    Arch,
+    Bitness,
+    FormFactors,
+    FullVersionList,
+    Mobile,
+    Model,
+    Platform,
+    PlatformVersion,
+    Wow64,
+}
+
+impl ClientHint {
+    /// The request header carrying this hint, e.g. `Sec-CH-UA-Model`
+    pub fn header_name(self) -> &'static str {
+        match self {
+            ClientHint::Arch => "Sec-CH-UA-Arch",
+            ClientHint::Bitness => "Sec-CH-UA-Bitness",
+            ClientHint::FormFactors => "Sec-CH-UA-Form-Factors",
+            ClientHint::FullVersionList => "Sec-CH-UA-Full-Version-List",
+            ClientHint::Mobile => "Sec-CH-UA-Mobile",
+            ClientHint::Model => "Sec-CH-UA-Model",
+            ClientHint::Platform => "Sec-CH-UA-Platform",
+            ClientHint::PlatformVersion => "Sec-CH-UA-Platform-Version",
+            ClientHint::Wow64 => "Sec-CH-UA-WoW64",
+        }
+    }
+
+    /// The `Permissions-Policy` feature controlling this hint, e.g.
+    /// `ch-ua-model`
+    pub fn policy_feature(self) -> &'static str {
+        match self {
+            ClientHint::Arch => "ch-ua-arch",
+            ClientHint::Bitness => "ch-ua-bitness",
+            ClientHint::FormFactors => "ch-ua-form-factors",
+            ClientHint::FullVersionList => "ch-ua-full-version-list",
+            ClientHint::Mobile => "ch-ua-mobile",
+            ClientHint::Model => "ch-ua-model",
+            ClientHint::Platform => "ch-ua-platform",
+            ClientHint::PlatformVersion => "ch-ua-platform-version",
+            ClientHint::Wow64 => "ch-ua-wow64",
+        }
+    }
+
+    /// Whether browsers send this hint by default, without being asked to
+    pub fn is_low_entropy(self) -> bool {
+        matches!(self, ClientHint::Mobile | ClientHint::Platform)
+    }
+}
+
+/// Builds the response headers of the Client Hints handshake: `Accept-CH`
+/// asks the browser to send the given hints on subsequent requests, and
+/// `Permissions-Policy` delegates them to third-party origins.
+///
+/// ```rust
+/// # use uaparser::*;
+/// let hints = AcceptClientHints::new()
+///     .hint(ClientHint::Model)
+///     .hint(ClientHint::PlatformVersion)
+///     .delegate_to("https://cdn.example.com");
+///
+/// assert_eq!(
+///     hints.accept_ch(),
+///     "Sec-CH-UA-Model, Sec-CH-UA-Platform-Version"
+/// );
+/// assert_eq!(
+///     hints.permissions_policy(),
+///     r#"ch-ua-model=(self "https://cdn.example.com"), ch-ua-platform-version=(self "https://cdn.example.com")"#
+/// );
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AcceptClientHints {
+    hints: Vec<ClientHint>,
+    origins: Vec<String>,
+}
+
+impl AcceptClientHints {
+    pub fn new() -> AcceptClientHints {
+        AcceptClientHints::default()
+    }
+
+    /// Requests the given hint. Low-entropy hints are sent by default but may
+    /// still be listed.
+    pub fn hint(mut self, hint: ClientHint) -> AcceptClientHints {
+        if !self.hints.contains(&hint) {
+            self.hints.push(hint);
+        }
+        self
+    }
+
+    /// Requests everything needed to identify the device model
+    pub fn model(self) -> AcceptClientHints {
+        self.hint(ClientHint::Model)
+    }
+
+    /// Requests everything needed to determine the platform and its version
+    pub fn platform_version(self) -> AcceptClientHints {
+        self.hint(ClientHint::Platform)
+            .hint(ClientHint::PlatformVersion)
+    }
+
+    /// Requests the full versions of all brands the browser identifies as
+    pub fn full_version_list(self) -> AcceptClientHints {
+        self.hint(ClientHint::FullVersionList)
+    }
+
+    /// Allows the hints to be sent to a third-party origin, such as a CDN
+    pub fn delegate_to(mut self, origin: impl Into<String>) -> AcceptClientHints {
+        self.origins.push(origin.into());
+        self
+    }
+
+    /// The value of the `Accept-CH` response header
+    pub fn accept_ch(&self) -> String {
+        self.hints
+            .iter()
+            .map(|hint| hint.header_name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// The value of the `Permissions-Policy` response header
+    pub fn permissions_policy(&self) -> String {
+        let allowlist = std::iter::once("self".to_string())
+            .chain(self.origins.iter().map(|origin| format!("\"{}\"", origin)))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        self.hints
+            .iter()
+            .map(|hint| format!("{}=({})", hint.policy_feature(), allowlist))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
@@ -0,0 +1,137 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use serde_derive::Deserialize;
+
+use super::{
+    Category, Dictionary, DictionaryParser, Error, RegexConfig, UserAgentParser,
+    UserAgentParserBuilder,
+};
+
+/// Parser settings read from a configuration file, so that operators can tune
+/// a parser without rebuilding the application. Files are YAML, or TOML with
+/// the `toml` feature if their extension is `.toml`. Every key is optional:
+///
+/// ```yaml
+/// dataset: regexes.yaml        # relative to the configuration file
+/// categories: [user_agent, os] # rules of other categories are dropped
+/// cache_size: 10000            # distinct user agents cached by `build`
+/// size_limit: 10485760         # bytes per compiled rule
+/// memory_budget: 67108864      # bytes for all rules, dropping those that don't fit
+/// regex:                       # overrides for single categories
+///   device: { size_limit: 2097152, dfa_size_limit: 1048576, unicode: false }
+/// fallback_family: Unknown
+/// case_insensitive: false
+/// prefilter: true
+/// desktop_shortcut: false
+/// match_info: false
+/// ```
+///
+/// Options are added along with those of the builder, so configurations
+/// created in code start from `Default::default`.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+#[non_exhaustive]
+pub struct ParserConfig {
+    /// Path of the `regexes.yaml` dataset
+    pub dataset: Option<PathBuf>,
+    /// Categories to load rules for, or all of them if unset
+    pub categories: Option<Vec<Category>>,
+    pub cache_size: Option<usize>,
+    pub size_limit: Option<usize>,
+    /// See `UserAgentParserBuilder::memory_budget`
+    pub memory_budget: Option<usize>,
+    /// How the rules of single categories are compiled, see
+    /// `UserAgentParserBuilder::regex_config`
+    pub regex: HashMap<Category, RegexConfig>,
+    pub fallback_family: Option<String>,
+    pub case_insensitive: bool,
+    /// Ignored without the `prefilter` feature
+    pub prefilter: bool,
+    pub desktop_shortcut: bool,
+    pub match_info: bool,
+}
+
+impl ParserConfig {
+    /// Reads the configuration file at `path`. A relative `dataset` is
+    /// resolved against the directory of the file.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<ParserConfig, Error> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)?;
+
+        let mut config: ParserConfig = match path.extension() {
+            #[cfg(feature = "toml")]
+            Some(extension) if extension == "toml" => toml::from_slice(&bytes)?,
+            _ => serde_yaml::from_slice(&bytes)?,
+        };
+
+        if let (Some(dataset), Some(base)) = (&mut config.dataset, path.parent()) {
+            *dataset = base.join(&*dataset);
+        }
+
+        Ok(config)
+    }
+
+    /// Returns a builder with the configured options. The dataset and cache
+    /// aren't part of the builder, see `build`.
+    pub fn builder(&self) -> UserAgentParserBuilder {
+        let mut builder = UserAgentParser::builder()
+            .case_insensitive(self.case_insensitive)
+            .desktop_shortcut(self.desktop_shortcut)
+            .match_info(self.match_info);
+        #[cfg(feature = "prefilter")]
+        {
+            builder = builder.prefilter(self.prefilter);
+        }
+
+        if let Some(family) = &self.fallback_family {
+            builder = builder.fallback_family(family.as_str());
+        }
+        if let Some(bytes) = self.size_limit {
+            builder = builder.size_limit(bytes);
+        }
+        if let Some(bytes) = self.memory_budget {
+            builder = builder.memory_budget(bytes);
+        }
+        for (&category, &config) in &self.regex {
+            builder = builder.regex_config(category, config);
+        }
+        if let Some(categories) = &self.categories {
+            let categories = categories.clone();
+            builder =
+                builder.filter_rules(move |category, _| categories.contains(&category));
+        }
+
+        builder
+    }
+
+    /// Builds a parser over the configured dataset, caching up to
+    /// `cache_size` results if set
+    pub fn build(&self) -> Result<DictionaryParser<UserAgentParser>, Error> {
+        let dataset = self.dataset.as_deref().ok_or_else(|| {
+            Error::IO(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "the configuration doesn't name a dataset",
+            ))
+        })?;
+
+        let parser = self.builder().build_from_path(dataset)?;
+        let parser = DictionaryParser::new(parser, Dictionary::new());
+
+        Ok(match self.cache_size {
+            Some(limit) => parser.cache(limit),
+            None => parser,
+        })
+    }
+}
+
+impl UserAgentParserBuilder {
+    /// Returns a builder with the options of the configuration file at `path`,
+    /// see `ParserConfig`. The dataset and cache settings of the file only
+    /// apply to `ParserConfig::build`.
+    pub fn from_config(path: impl AsRef<Path>) -> Result<UserAgentParserBuilder, Error> {
+        Ok(ParserConfig::from_path(path)?.builder())
+    }
+}
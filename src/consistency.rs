@@ -0,0 +1,183 @@
+use super::Client;
+
+/// The class of inconsistency an `Anomaly` describes
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AnomalyKind {
+    /// The device or browser belongs to one platform while the platform tokens
+    /// of the user agent string point to another
+    PlatformMismatch,
+    /// The user agent string carries tokens of more than one platform
+    ConflictingPlatforms,
+    /// The browser is paired with a rendering engine it never shipped with
+    EngineMismatch,
+    /// A version that was never released for the given browser or platform
+    ImpossibleVersion,
+}
+
+/// Describes a single inconsistency between the tokens of a user agent string
+/// and the `Client` parsed from it
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+    pub description: String,
+}
+
+impl Anomaly {
+    fn new(kind: AnomalyKind, description: impl Into<String>) -> Anomaly {
+        Anomaly {
+            kind,
+            description: description.into(),
+        }
+    }
+}
+
+impl Client {
+    /// Checks the `Client` parsed from `user_agent` against the raw tokens of
+    /// that string and returns every internal inconsistency found. Genuine
+    /// browsers practically never produce any, so a non-empty result is a
+    /// strong signal for spoofed or hand-crafted user agents.
+    pub fn anomalies(&self, user_agent: &str) -> Vec<Anomaly> {
+        let mut anomalies = Vec::new();
+
+        check_platforms(user_agent, &mut anomalies);
+        check_apple_device(self, user_agent, &mut anomalies);
+        check_engine(self, user_agent, &mut anomalies);
+        check_versions(self, &mut anomalies);
+
+        anomalies
+    }
+}
+
+const PLATFORM_TOKENS: &[(&str, &[&str])] = &[
+    ("Windows", &["Windows NT"]),
+    ("macOS", &["Macintosh"]),
+    ("Android", &["Android"]),
+    ("iOS", &["iPhone", "iPad", "iPod"]),
+    ("Chrome OS", &["CrOS"]),
+];
+
+fn check_platforms(user_agent: &str, anomalies: &mut Vec<Anomaly>) {
+    // Windows Phone deliberately lists Android and iPhone tokens for
+    // compatibility, so multiple platforms are expected there.
+    if user_agent.contains("Windows Phone") {
+        return;
+    }
+
+    let platforms: Vec<&str> = PLATFORM_TOKENS
+        .iter()
+        .filter(|(_, tokens)| tokens.iter().any(|token| user_agent.contains(token)))
+        .map(|(name, _)| *name)
+        .collect();
+
+    if platforms.len() > 1 {
+        anomalies.push(Anomaly::new(
+            AnomalyKind::ConflictingPlatforms,
+            format!(
+                "user agent claims several platforms: {}",
+                platforms.join(", ")
+            ),
+        ));
+    }
+}
+
+fn check_apple_device(client: &Client, user_agent: &str, anomalies: &mut Vec<Anomaly>) {
+    let family = &client.device.family;
+    let is_apple_mobile = ["iPhone", "iPad", "iPod"]
+        .iter()
+        .any(|name| family.starts_with(name));
+
+    if !is_apple_mobile {
+        return;
+    }
+
+    if user_agent.contains("Windows NT") || user_agent.contains("Android") {
+        anomalies.push(Anomaly::new(
+            AnomalyKind::PlatformMismatch,
+            format!("{} device with a non-Apple platform token", family),
+        ));
+    } else if client.os.family != "iOS" && !client.os.is_unknown() {
+        anomalies.push(Anomaly::new(
+            AnomalyKind::PlatformMismatch,
+            format!("{} device running {}", family, client.os.family),
+        ));
+    }
+}
+
+fn check_engine(client: &Client, user_agent: &str, anomalies: &mut Vec<Anomaly>) {
+    let family = client.user_agent.family.as_str();
+    let major = parse_version(&client.user_agent.major);
+
+    match (family, major) {
+        // Every Blink based Chrome release reports the frozen WebKit build.
+        ("Chrome", Some(major))
+        | ("Chrome Mobile", Some(major))
+        | ("Chromium", Some(major))
+            if major >= 28 =>
+        {
+            match webkit_build(user_agent) {
+                Some("537.36") => {}
+                Some(build) => anomalies.push(Anomaly::new(
+                    AnomalyKind::EngineMismatch,
+                    format!("{} {} with AppleWebKit/{}", family, major, build),
+                )),
+                None => anomalies.push(Anomaly::new(
+                    AnomalyKind::EngineMismatch,
+                    format!("{} without an AppleWebKit token", family),
+                )),
+            }
+        }
+        ("Firefox", _) | ("Firefox Mobile", _) if user_agent.contains("AppleWebKit") => {
+            anomalies.push(Anomaly::new(
+                AnomalyKind::EngineMismatch,
+                format!("{} with an AppleWebKit token", family),
+            ));
+        }
+        ("Mobile Safari", _) if !matches!(client.os.family.as_str(), "iOS" | "Other") => {
+            anomalies.push(Anomaly::new(
+                AnomalyKind::PlatformMismatch,
+                format!("Mobile Safari running on {}", client.os.family),
+            ));
+        }
+        _ => {}
+    }
+}
+
+fn check_versions(client: &Client, anomalies: &mut Vec<Anomaly>) {
+    let family = client.user_agent.family.as_str();
+    let major = parse_version(&client.user_agent.major);
+    let on_windows = client.os.family.starts_with("Windows");
+
+    match (family, major) {
+        ("IE", Some(major)) if major > 11 => anomalies.push(Anomaly::new(
+            AnomalyKind::ImpossibleVersion,
+            format!("Internet Explorer {} was never released", major),
+        )),
+        ("IE", _) if !on_windows && !client.os.is_unknown() => {
+            anomalies.push(Anomaly::new(
+                AnomalyKind::PlatformMismatch,
+                format!("Internet Explorer running on {}", client.os.family),
+            ))
+        }
+        // Safari for Windows was discontinued after the 5.1 release.
+        ("Safari", Some(major)) if on_windows && major > 5 => {
+            anomalies.push(Anomaly::new(
+                AnomalyKind::ImpossibleVersion,
+                format!("Safari {} was never released for Windows", major),
+            ))
+        }
+        _ => {}
+    }
+}
+
+fn parse_version(version: &Option<String>) -> Option<u32> {
+    version.as_ref().and_then(|v| v.parse().ok())
+}
+
+fn webkit_build(user_agent: &str) -> Option<&str> {
+    let start = user_agent.find("AppleWebKit/")? + "AppleWebKit/".len();
+    let rest = &user_agent[start..];
+    let end = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(rest.len());
+    Some(&rest[..end])
+}
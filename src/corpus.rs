@@ -0,0 +1,123 @@
+//! A versioned sample of real-world user agents, grouped by the kind of client
+//! sending them. Unlike the uap-core test cases it resembles the traffic of a
+//! typical website, which makes it suitable for benchmarks, property tests and
+//! warming up a cache before serving requests:
+//!
+//! ```rust
+//! # use uaparser::*;
+//! let parser = CachedParser::new(
+//!     UserAgentParser::from_yaml("./src/core/regexes.yaml")?,
+//!     LruCache::new(1024),
+//! );
+//!
+//! for user_agent in corpus::user_agents() {
+//!     parser.parse(user_agent);
+//! }
+//!
+//! assert_eq!(parser.cache().len(), corpus::samples().len());
+//! # Ok::<_, Error>(())
+//! ```
+//!
+//! The samples are replaced as browsers release new versions. Record
+//! `corpus::version()` alongside benchmark results to know whether they can be
+//! compared.
+
+use std::{collections::BTreeMap, sync::OnceLock};
+
+use serde_derive::Deserialize;
+
+static CORPUS: &str = include_str!("corpus.yaml");
+
+/// The kind of client a sample user agent belongs to
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[serde(rename_all = "snake_case")]
+pub enum Segment {
+    Desktop,
+    Mobile,
+    Tablet,
+    Tv,
+    Console,
+    /// Search engines, crawlers and monitoring services
+    Bot,
+    /// Native and Electron apps, including in-app browsers
+    App,
+    /// HTTP clients of programming languages and command line tools
+    Library,
+}
+
+impl Segment {
+    pub const ALL: [Segment; 8] = [
+        Segment::Desktop,
+        Segment::Mobile,
+        Segment::Tablet,
+        Segment::Tv,
+        Segment::Console,
+        Segment::Bot,
+        Segment::App,
+        Segment::Library,
+    ];
+}
+
+/// A user agent of the corpus
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Sample {
+    pub segment: Segment,
+    pub user_agent: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CorpusFile {
+    version: String,
+    segments: BTreeMap<Segment, Vec<String>>,
+}
+
+struct Corpus {
+    version: String,
+    samples: Vec<Sample>,
+}
+
+fn corpus() -> &'static Corpus {
+    static GLOBAL: OnceLock<Corpus> = OnceLock::new();
+    GLOBAL.get_or_init(|| {
+        let file: CorpusFile =
+            serde_yaml::from_str(CORPUS).expect("the embedded corpus is valid");
+        let samples = file
+            .segments
+            .into_iter()
+            .flat_map(|(segment, user_agents)| {
+                user_agents.into_iter().map(move |user_agent| Sample {
+                    segment,
+                    user_agent,
+                })
+            })
+            .collect();
+
+        Corpus {
+            version: file.version,
+            samples,
+        }
+    })
+}
+
+/// The version of the corpus, changed whenever samples are added or replaced
+pub fn version() -> &'static str {
+    &corpus().version
+}
+
+/// All samples, ordered by segment
+pub fn samples() -> &'static [Sample] {
+    &corpus().samples
+}
+
+/// The user agents of all samples
+pub fn user_agents() -> impl Iterator<Item = &'static str> {
+    samples().iter().map(|sample| sample.user_agent.as_str())
+}
+
+/// The user agents of the samples in `segment`
+pub fn segment(segment: Segment) -> impl Iterator<Item = &'static str> {
+    samples()
+        .iter()
+        .filter(move |sample| sample.segment == segment)
+        .map(|sample| sample.user_agent.as_str())
+}
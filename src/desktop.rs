@@ -0,0 +1,42 @@
+/// Platform tokens of desktop browsers on Windows and Linux, on which the
+/// device rules of uap-core find nothing. macOS isn't among them, since its
+/// user agents are reported as a `Mac` device.
+const DESKTOP: &[&str] = &["(Windows NT ", "(X11; Linux ", "(X11; Ubuntu; Linux "];
+
+/// Tokens of anything that might still be matched by a device rule: mobile
+/// and tablet platforms, consoles and TVs on a desktop OS, and bots, which
+/// are reported as `Spider`. Compared in lowercase.
+const NOT_DESKTOP: &[&str] = &[
+    "android",
+    "mobile",
+    "phone",
+    "tablet",
+    "touch",
+    "arm",
+    "ipad",
+    "kindle",
+    "silk",
+    "xbox",
+    "playstation",
+    "nintendo",
+    "tv",
+    "tizen",
+    "webos",
+    "crkey",
+    "bot",
+    "spider",
+    "crawl",
+    "http",
+    "@",
+];
+
+/// Tells desktop browsers on Windows and Linux apart from everything else
+/// with a few substring searches, see `UserAgentParserBuilder::desktop_shortcut`
+pub(crate) fn is_plain_desktop(user_agent: &str) -> bool {
+    if !DESKTOP.iter().any(|token| user_agent.contains(token)) {
+        return false;
+    }
+
+    let user_agent = user_agent.to_ascii_lowercase();
+    !NOT_DESKTOP.iter().any(|token| user_agent.contains(token))
+}
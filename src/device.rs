@@ -1,15 +1,39 @@
-use super::Deserialize;
+use super::{Deserialize, Serialize};
 
 pub type Family = String;
 pub type Brand = String;
 pub type Model = String;
 
-/// Describes the `Family`, `Brand` and `Model` of a `Device`
-#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+/// Describes the `Family`, `Brand` and `Model` of a `Device`, along with its
+/// kind where the dataset provides one
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "async-graphql", derive(async_graphql::SimpleObject))]
 pub struct Device {
     pub family: Family,
     pub brand: Option<Brand>,
     pub model: Option<Model>,
+    /// The type of device, like `smartphone` or `tablet`, as set by the
+    /// `type_replacement` of rules in extended datasets
+    pub kind: Option<String>,
+}
+
+impl Device {
+    /// Returns the device of user agents no rule matches
+    pub fn unknown() -> Device {
+        Device::default()
+    }
+
+    /// Returns whether the family is `Other`, as when no rule matched
+    pub fn is_unknown(&self) -> bool {
+        self.family == "Other"
+    }
+
+    /// Returns whether the family is unknown and no other field is set
+    pub fn is_empty(&self) -> bool {
+        *self == Device::default()
+    }
 }
 
 impl Default for Device {
@@ -18,6 +42,7 @@ impl Default for Device {
             family: "Other".to_string(),
             brand: None,
             model: None,
+            kind: None,
         }
     }
 }
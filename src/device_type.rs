@@ -0,0 +1,136 @@
+use super::{Client, VoicePlatform};
+
+/// Broad class of hardware a `Client` runs on, derived heuristically from the
+/// parsed `Device`, `OS` and `UserAgent`
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DeviceType {
+    Desktop,
+    Smartphone,
+    Tablet,
+    FeaturePhone,
+    Tv,
+    Console,
+    Wearable,
+    SmartSpeaker,
+    Bot,
+    Unknown,
+}
+
+const TABLET_HINTS: &[&str] = &["iPad", "Tablet", "Kindle", "Galaxy Tab", "Nexus 7"];
+const TV_HINTS: &[&str] = &["TV", "Chromecast", "Roku", "Television"];
+const CONSOLE_HINTS: &[&str] = &["PlayStation", "Xbox", "Nintendo"];
+const WEARABLE_HINTS: &[&str] = &["Watch", "Glass"];
+const FEATURE_PHONE_HINTS: &[&str] = &["Feature Phone", "JioPhone"];
+const FEATURE_PHONE_OS: &[&str] = &[
+    "KaiOS",
+    "Symbian OS",
+    "Series 30+",
+    "Series 40",
+    "Series 60",
+];
+const MOBILE_OS: &[&str] = &[
+    "iOS",
+    "Android",
+    "Windows Phone",
+    "BlackBerry OS",
+    "Firefox OS",
+    "Sailfish",
+    "Tizen",
+];
+const DESKTOP_OS: &[&str] = &[
+    "Windows",
+    "Mac OS X",
+    "Linux",
+    "Ubuntu",
+    "Chrome OS",
+    "FreeBSD",
+    "OpenBSD",
+    "NetBSD",
+    "Solaris",
+];
+
+impl Client {
+    /// Classifies the hardware this `Client` runs on. Dedicated device rules
+    /// take precedence over the operating system, so an Android tablet or TV
+    /// is not reported as a smartphone. A known `Device::kind` set by the
+    /// dataset takes precedence over both.
+    pub fn device_type(&self) -> DeviceType {
+        let device = &self.device;
+        if let Some(kind) = device.kind.as_deref().and_then(DeviceType::from_kind) {
+            return kind;
+        }
+
+        let model = device.model.as_deref().unwrap_or("");
+        let has_hint = |hints: &[&str]| {
+            hints
+                .iter()
+                .any(|hint| device.family.contains(hint) || model.contains(hint))
+        };
+
+        if device.family == "Spider" {
+            DeviceType::Bot
+        } else if VoicePlatform::from_device(&device.family).is_some() {
+            DeviceType::SmartSpeaker
+        } else if has_hint(CONSOLE_HINTS) {
+            DeviceType::Console
+        } else if has_hint(TV_HINTS) {
+            DeviceType::Tv
+        } else if has_hint(WEARABLE_HINTS) || self.os.family == "watchOS" {
+            DeviceType::Wearable
+        } else if has_hint(TABLET_HINTS) {
+            DeviceType::Tablet
+        } else if has_hint(FEATURE_PHONE_HINTS)
+            || FEATURE_PHONE_OS.contains(&self.os.family.as_str())
+        {
+            DeviceType::FeaturePhone
+        } else if device.family == "Generic Smartphone"
+            || MOBILE_OS.contains(&self.os.family.as_str())
+        {
+            DeviceType::Smartphone
+        } else if DESKTOP_OS
+            .iter()
+            .any(|family| self.os.family.starts_with(family))
+        {
+            DeviceType::Desktop
+        } else {
+            DeviceType::Unknown
+        }
+    }
+}
+
+impl DeviceType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DeviceType::Desktop => "desktop",
+            DeviceType::Smartphone => "smartphone",
+            DeviceType::Tablet => "tablet",
+            DeviceType::FeaturePhone => "feature phone",
+            DeviceType::Tv => "tv",
+            DeviceType::Console => "console",
+            DeviceType::Wearable => "wearable",
+            DeviceType::SmartSpeaker => "smart speaker",
+            DeviceType::Bot => "bot",
+            DeviceType::Unknown => "unknown",
+        }
+    }
+
+    /// Looks up the type named by a `Device::kind`, the inverse of `as_str`,
+    /// ignoring case and accepting underscores or dashes, like `feature_phone`
+    fn from_kind(kind: &str) -> Option<DeviceType> {
+        let kind = kind.replace(['_', '-'], " ").to_ascii_lowercase();
+        let device_type = match kind.as_str() {
+            "desktop" => DeviceType::Desktop,
+            "smartphone" => DeviceType::Smartphone,
+            "tablet" => DeviceType::Tablet,
+            "feature phone" => DeviceType::FeaturePhone,
+            "tv" => DeviceType::Tv,
+            "console" => DeviceType::Console,
+            "wearable" => DeviceType::Wearable,
+            "smart speaker" => DeviceType::SmartSpeaker,
+            "bot" => DeviceType::Bot,
+            "unknown" => DeviceType::Unknown,
+            _ => return None,
+        };
+        Some(device_type)
+    }
+}
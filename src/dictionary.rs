@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+
+use serde_derive::{Deserialize, Serialize};
+
+use super::{Client, Device, Error, LruCache, Parser, UaCache, UserAgent, OS};
+
+/// Maps complete user agent strings to the `Client` they parse to. Stored as
+/// YAML, or JSON with the `json` feature, with one entry per user agent.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct Dictionary {
+    entries: HashMap<String, Client>,
+}
+
+impl Dictionary {
+    pub fn new() -> Dictionary {
+        Dictionary::default()
+    }
+
+    /// Attempts to load a `Dictionary` from the path to a YAML file
+    #[cfg(feature = "fs")]
+    pub fn from_yaml(path: &str) -> Result<Dictionary, Error> {
+        let file = std::fs::File::open(path)?;
+        Ok(serde_yaml::from_reader(file)?)
+    }
+
+    /// Attempts to load a `Dictionary` from the raw bytes of a YAML file
+    pub fn from_bytes(bytes: &[u8]) -> Result<Dictionary, Error> {
+        Ok(serde_yaml::from_slice(bytes)?)
+    }
+
+    /// Attempts to load a `Dictionary` from a JSON file
+    #[cfg(feature = "json")]
+    pub fn from_json(bytes: &[u8]) -> Result<Dictionary, Error> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    /// Serializes the dictionary to YAML, as read by `from_bytes`
+    pub fn to_yaml(&self) -> Result<String, Error> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+
+    /// Serializes the dictionary to JSON, as read by `from_json`
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<Vec<u8>, Error> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    pub fn get(&self, user_agent: &str) -> Option<&Client> {
+        self.entries.get(user_agent)
+    }
+
+    pub fn insert(&mut self, user_agent: impl Into<String>, client: Client) {
+        self.entries.insert(user_agent.into(), client);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl std::iter::FromIterator<(String, Client)> for Dictionary {
+    fn from_iter<I: IntoIterator<Item = (String, Client)>>(iter: I) -> Dictionary {
+        Dictionary {
+            entries: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<(String, Client)> for Dictionary {
+    fn extend<I: IntoIterator<Item = (String, Client)>>(&mut self, iter: I) {
+        self.entries.extend(iter);
+    }
+}
+
+/// A `Parser` answering user agents found in a `Dictionary` by exact match,
+/// before falling back to the regexes of another parser. Sites with a stable
+/// population of clients can answer most traffic this way. Like with
+/// `CachedParser`, results carry no match info.
+///
+/// Optionally, results of the fallback parser are cached in an `LruCache`,
+/// and can be exported as a dictionary to load on the next start:
+///
+/// ```rust
+/// # use uaparser::*;
+/// let regexes = UserAgentParser::from_yaml("./src/core/regexes.yaml").unwrap();
+/// let parser = DictionaryParser::new(regexes, Dictionary::new()).cache(10_000);
+///
+/// parser.parse("Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Firefox/70.0");
+///
+/// let dictionary = Dictionary::from_bytes(parser.export().to_yaml()?.as_bytes())?;
+/// assert_eq!(dictionary.len(), 1);
+/// # Ok::<_, Error>(())
+/// ```
+#[derive(Debug)]
+pub struct DictionaryParser<P> {
+    parser: P,
+    dictionary: Dictionary,
+    cache: Option<LruCache>,
+}
+
+impl<P: Parser> DictionaryParser<P> {
+    pub fn new(parser: P, dictionary: Dictionary) -> DictionaryParser<P> {
+        DictionaryParser {
+            parser,
+            dictionary,
+            cache: None,
+        }
+    }
+
+    /// Caches the full results of the fallback parser for up to `capacity`
+    /// user agents, which are answered from the cache afterwards and included
+    /// by `export`. Results of single categories aren't cached.
+    pub fn cache(mut self, capacity: usize) -> DictionaryParser<P> {
+        self.cache = Some(LruCache::new(capacity));
+        self
+    }
+
+    /// Returns the dictionary along with all cached results
+    pub fn export(&self) -> Dictionary {
+        let mut dictionary = self.dictionary.clone();
+        if let Some(cache) = &self.cache {
+            dictionary.extend(cache.entries());
+        }
+        dictionary
+    }
+
+    fn lookup(&self, user_agent: &str) -> Option<Client> {
+        if let Some(client) = self.dictionary.get(user_agent) {
+            return Some(client.clone());
+        }
+
+        self.cache.as_ref()?.get(user_agent)
+    }
+}
+
+impl<P: Parser> Parser for DictionaryParser<P> {
+    fn parse(&self, user_agent: &str) -> Client {
+        if let Some(client) = self.lookup(user_agent) {
+            return client;
+        }
+
+        let mut client = self.parser.parse(user_agent);
+        client.match_info = None;
+        if let Some(cache) = &self.cache {
+            cache.put(user_agent, &client, None);
+        }
+        client
+    }
+
+    fn parse_device(&self, user_agent: &str) -> Device {
+        match self.lookup(user_agent) {
+            Some(client) => client.device,
+            None => self.parser.parse_device(user_agent),
+        }
+    }
+
+    fn parse_os(&self, user_agent: &str) -> OS {
+        match self.lookup(user_agent) {
+            Some(client) => client.os,
+            None => self.parser.parse_os(user_agent),
+        }
+    }
+
+    fn parse_user_agent(&self, user_agent: &str) -> UserAgent {
+        match self.lookup(user_agent) {
+            Some(client) => client.user_agent,
+            None => self.parser.parse_user_agent(user_agent),
+        }
+    }
+}
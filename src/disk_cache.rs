@@ -0,0 +1,231 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use serde_derive::{Deserialize, Serialize};
+
+use super::{Client, Error, UaCache};
+
+/// Computes the checksum of the raw bytes of a dataset, which is stable across
+/// versions of the crate and platforms. For `DiskCache::open`, use
+/// `UserAgentParserBuilder::checksum`, which covers the options of the parser
+/// as well.
+pub fn dataset_checksum(bytes: &[u8]) -> u64 {
+    // 64-bit FNV-1a
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[derive(Deserialize, Serialize)]
+struct Header {
+    checksum: u64,
+}
+
+#[derive(Debug)]
+struct Store {
+    entries: HashMap<String, Client>,
+    /// The user agents in `entries`, oldest first
+    order: VecDeque<String>,
+    file: BufWriter<File>,
+    /// The number of entry lines in the file, including evicted ones
+    lines: usize,
+    path: PathBuf,
+    checksum: u64,
+    capacity: usize,
+}
+
+impl Store {
+    /// Rewrites the file with the header followed by the current entries
+    fn rewrite(&mut self) -> Result<(), Error> {
+        self.file = create(&self.path, self.checksum, &self.order, &self.entries)?;
+        self.lines = self.order.len();
+        Ok(())
+    }
+
+    /// Drops the oldest entries until there's room for another one
+    fn evict(&mut self) {
+        while self.order.len() >= self.capacity {
+            match self.order.pop_front() {
+                Some(oldest) => self.entries.remove(&oldest),
+                None => break,
+            };
+        }
+    }
+}
+
+fn create(
+    path: &Path,
+    checksum: u64,
+    order: &VecDeque<String>,
+    entries: &HashMap<String, Client>,
+) -> Result<BufWriter<File>, Error> {
+    let mut file = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(&mut file, &Header { checksum })?;
+    file.write_all(b"\n")?;
+    for user_agent in order {
+        serde_json::to_writer(&mut file, &(user_agent, &entries[user_agent]))?;
+        file.write_all(b"\n")?;
+    }
+    file.flush()?;
+    Ok(file)
+}
+
+/// A `UaCache` in an append-only file, so that batch jobs re-run over similar
+/// logs skip the agents they've seen before, even across restarts. The file
+/// is tied to the checksum of the dataset and the parser options, see
+/// `UserAgentParserBuilder::checksum`, and started over once either changes.
+/// Entries never expire otherwise: the `ttl` of `CachedParser` is ignored.
+///
+/// The cache holds at most `capacity` user agents, and evicts the oldest
+/// stored one for every new one once it's full. Evicted entries remain in
+/// the file until it has grown to twice the capacity, when it's compacted to
+/// the current entries. A capacity of zero stores nothing.
+///
+/// The file holds one JSON document per line: a header with the checksum,
+/// followed by a `[user_agent, client]` pair for every stored user agent.
+/// Written lines are flushed when the cache is dropped or on `flush`.
+///
+/// ```rust,no_run
+/// # use uaparser::*;
+/// let regexes = std::fs::read("./src/core/regexes.yaml")?;
+/// let builder = UserAgentParser::builder().desktop_shortcut(true);
+/// let checksum = builder.checksum(&regexes);
+/// let parser = CachedParser::new(
+///     builder.build_from_bytes(&regexes)?,
+///     DiskCache::open("/var/cache/uaparser.jsonl", checksum, 100_000)?,
+/// );
+///
+/// let client = parser.parse("Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Firefox/70.0");
+/// # Ok::<_, Error>(())
+/// ```
+#[derive(Debug)]
+pub struct DiskCache {
+    store: Mutex<Store>,
+}
+
+impl DiskCache {
+    /// Opens the cache file at `path`, creating it if needed. Entries written
+    /// for a different `checksum` are discarded, as is a line left incomplete
+    /// by a crash, and the oldest ones beyond `capacity`.
+    pub fn open(
+        path: impl AsRef<Path>,
+        checksum: u64,
+        capacity: usize,
+    ) -> Result<DiskCache, Error> {
+        let path = path.as_ref();
+        let mut entries = HashMap::new();
+        let mut order = VecDeque::new();
+        let mut valid = false;
+
+        if let Ok(file) = File::open(path) {
+            let mut lines = BufReader::new(file).lines();
+            if let Some(Ok(line)) = lines.next() {
+                valid = matches!(
+                    serde_json::from_str::<Header>(&line),
+                    Ok(header) if header.checksum == checksum
+                );
+            }
+
+            if valid {
+                for line in lines {
+                    let (user_agent, client) =
+                        match serde_json::from_str::<(String, Client)>(&line?) {
+                            Ok(entry) => entry,
+                            Err(_) => break,
+                        };
+                    if entries.insert(user_agent.clone(), client).is_none() {
+                        order.push_back(user_agent);
+                    }
+                }
+            }
+        }
+
+        // The file is rewritten from the loaded entries, which drops stale
+        // and evicted ones along with an incomplete line at the end
+        while order.len() > capacity {
+            if let Some(oldest) = order.pop_front() {
+                entries.remove(&oldest);
+            }
+        }
+        let file = create(path, checksum, &order, &entries)?;
+
+        Ok(DiskCache {
+            store: Mutex::new(Store {
+                lines: order.len(),
+                entries,
+                order,
+                file,
+                path: path.to_owned(),
+                checksum,
+                capacity,
+            }),
+        })
+    }
+
+    /// Returns the number of user agents in the cache
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Writes buffered entries to the file
+    pub fn flush(&self) -> Result<(), Error> {
+        Ok(self.lock().file.flush()?)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Store> {
+        self.store.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for DiskCache {
+    fn drop(&mut self) {
+        let store = self.store.get_mut().unwrap_or_else(|e| e.into_inner());
+        store.file.flush().ok();
+    }
+}
+
+impl UaCache for DiskCache {
+    fn get(&self, user_agent: &str) -> Option<Client> {
+        self.lock().entries.get(user_agent).cloned()
+    }
+
+    /// Stores `client` for `user_agent`. Entries don't expire, so `ttl` is
+    /// ignored.
+    fn put(&self, user_agent: &str, client: &Client, _ttl: Option<Duration>) {
+        let mut store = self.lock();
+        if store.capacity == 0 {
+            return;
+        }
+        if !store.entries.contains_key(user_agent) {
+            store.evict();
+        }
+        if store.lines >= store.capacity.saturating_mul(2) && store.rewrite().is_err() {
+            return;
+        }
+
+        let written = serde_json::to_writer(&mut store.file, &(user_agent, client))
+            .map_err(Error::from)
+            .and_then(|()| Ok(store.file.write_all(b"\n")?));
+        // A failing disk degrades to parsing, rather than failing the parse
+        if written.is_ok() {
+            store.lines += 1;
+            if store
+                .entries
+                .insert(user_agent.to_owned(), client.clone())
+                .is_none()
+            {
+                store.order.push_back(user_agent.to_owned());
+            }
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::sync::OnceLock;
+
+/// Distribution rules, evaluated in order after the core OS match. The first
+/// group, if any, captures the distribution version. Derivatives come before
+/// the distributions they are based on, since their user agents tend to
+/// mention both. Names only match at the start of a word.
+const DISTRO_RULES: &[(&str, &str)] = &[
+    (r"\bLinux ?Mint(?:[/ ](\d+(?:\.\d+)*))?", "Linux Mint"),
+    (r"\bKubuntu(?:[/ ](\d+(?:\.\d+)*))?", "Kubuntu"),
+    (r"\bXubuntu(?:[/ ](\d+(?:\.\d+)*))?", "Xubuntu"),
+    (r"\belementary ?OS(?:[/ ](\d+(?:\.\d+)*))?", "elementary OS"),
+    (r"\bRaspbian(?:[/ ](\d+(?:\.\d+)*))?", "Raspbian"),
+    (r"\bUbuntu(?:[/ ](\d+(?:\.\d+)*))?", "Ubuntu"),
+    (r"\bDebian(?:[/ ](\d+(?:\.\d+)*))?", "Debian"),
+    (r"\.fc(\d+)\b", "Fedora"),
+    (r"\bFedora(?:[/ ](\d+))?", "Fedora"),
+    (r"\bCentOS(?:[/ ](\d+(?:\.\d+)*))?", "CentOS"),
+    (r"\b(?:Red Hat|RHEL)\b", "Red Hat"),
+    (r"\bopenSUSE(?:[/ ](\d+(?:\.\d+)*))?", "openSUSE"),
+    (r"\bSUSE\b", "SUSE"),
+    (r"\bManjaro", "Manjaro"),
+    (r"\bArch ?Linux", "Arch Linux"),
+    (r"\bGentoo", "Gentoo"),
+    (r"\bMageia(?:[/ ](\d+))?", "Mageia"),
+    (r"\bSlackware(?:[/ ](\d+(?:\.\d+)*))?", "Slackware"),
+];
+
+fn rules() -> &'static [(fancy_regex::Regex, &'static str)] {
+    static RULES: OnceLock<Vec<(fancy_regex::Regex, &'static str)>> = OnceLock::new();
+    RULES.get_or_init(|| {
+        DISTRO_RULES
+            .iter()
+            .map(|&(regex, name)| {
+                let regex =
+                    fancy_regex::Regex::new(regex).expect("distro rules are valid");
+                (regex, name)
+            })
+            .collect()
+    })
+}
+
+/// Finds the Linux distribution and, where present, its version in a user
+/// agent whose OS was identified as Linux or one of its distributions
+pub(crate) fn detect(
+    user_agent: &str,
+    os_family: &str,
+) -> Option<(String, Option<String>)> {
+    let is_linux = user_agent.contains("Linux") || user_agent.contains("X11");
+    if !is_linux || os_family == "Android" || os_family == "Chrome OS" {
+        return None;
+    }
+
+    rules().iter().find_map(|(regex, name)| {
+        let captures = regex.captures(user_agent).ok()??;
+        let version = captures.get(1).map(|m| m.as_str().to_string());
+        Some((name.to_string(), version))
+    })
+}
@@ -0,0 +1,70 @@
+use serde_derive::Serialize;
+
+use super::Client;
+
+/// Elastic Common Schema representation of a `Client`. Serializes to a
+/// top-level `user_agent` object, so it can be merged into an event document
+/// as is.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct EcsFields {
+    pub user_agent: EcsUserAgent,
+}
+
+/// The `user_agent.*` field set
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct EcsUserAgent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original: Option<String>,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub os: EcsOs,
+    pub device: EcsDevice,
+}
+
+/// The `user_agent.os.*` fields
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct EcsOs {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub full: String,
+}
+
+/// The `user_agent.device.*` fields
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct EcsDevice {
+    pub name: String,
+}
+
+impl Client {
+    /// Maps this `Client` onto the ECS `user_agent` field set, including
+    /// `user_agent.original` when the raw string is given
+    pub fn to_ecs(&self, original: Option<&str>) -> EcsFields {
+        let os_version = self.os.version();
+        let os_full = match &os_version {
+            Some(version) => format!("{} {}", self.os.family, version),
+            None => self.os.family.clone(),
+        };
+
+        EcsFields {
+            user_agent: EcsUserAgent {
+                original: original.map(ToString::to_string),
+                name: self.user_agent.family.clone(),
+                version: self.user_agent.version(),
+                os: EcsOs {
+                    name: self.os.family.clone(),
+                    version: os_version,
+                    full: os_full,
+                },
+                device: EcsDevice {
+                    name: self.device.family.clone(),
+                },
+            },
+        }
+    }
+}
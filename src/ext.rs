@@ -0,0 +1,35 @@
+use super::{Client, Device, Parser, UserAgent, OS};
+
+/// Parsing methods on user agent strings, for call sites that read better
+/// with the string first
+///
+/// ```rust
+/// # use uaparser::*;
+/// # let parser = UserAgentParser::from_yaml("./src/core/regexes.yaml").unwrap();
+/// let user_agent = "Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Firefox/70.0";
+/// assert_eq!(user_agent.parse_user_agent(&parser).family, "Firefox");
+/// ```
+pub trait UserAgentExt {
+    fn parse_client<P: Parser + ?Sized>(&self, parser: &P) -> Client;
+    fn parse_device<P: Parser + ?Sized>(&self, parser: &P) -> Device;
+    fn parse_os<P: Parser + ?Sized>(&self, parser: &P) -> OS;
+    fn parse_user_agent<P: Parser + ?Sized>(&self, parser: &P) -> UserAgent;
+}
+
+impl UserAgentExt for str {
+    fn parse_client<P: Parser + ?Sized>(&self, parser: &P) -> Client {
+        parser.parse(self)
+    }
+
+    fn parse_device<P: Parser + ?Sized>(&self, parser: &P) -> Device {
+        parser.parse_device(self)
+    }
+
+    fn parse_os<P: Parser + ?Sized>(&self, parser: &P) -> OS {
+        parser.parse_os(self)
+    }
+
+    fn parse_user_agent<P: Parser + ?Sized>(&self, parser: &P) -> UserAgent {
+        parser.parse_user_agent(self)
+    }
+}
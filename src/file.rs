@@ -1,35 +1,302 @@
+use std::borrow::Cow;
+
+use serde::{Deserialize, Deserializer};
+
 use super::*;
 
-#[derive(Debug, Deserialize)]
-pub struct RegexFile {
-    pub user_agent_parsers: Vec<UserAgentParserEntry>,
-    pub os_parsers: Vec<OSParserEntry>,
-    pub device_parsers: Vec<DeviceParserEntry>,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct UserAgentParserEntry {
-    pub regex: String,
-    pub family_replacement: Option<String>,
-    pub v1_replacement: Option<String>,
-    pub v2_replacement: Option<String>,
-    pub v3_replacement: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct OSParserEntry {
-    pub regex: String,
-    pub os_replacement: Option<String>,
-    pub os_v1_replacement: Option<String>,
-    pub os_v2_replacement: Option<String>,
-    pub os_v3_replacement: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct DeviceParserEntry {
-    pub regex_flag: Option<String>,
-    pub regex: String,
-    pub device_replacement: Option<String>,
-    pub brand_replacement: Option<String>,
-    pub model_replacement: Option<String>,
+/// A dataset in the `regexes.yaml` format of uap-core, for tooling that reads
+/// or writes datasets, like linters and converters. Fields may be added for
+/// new keys of the format, so values are created with `new` or
+/// `Default::default`.
+///
+/// Regexes and replacements are borrowed from the input where the format
+/// allows it, use `into_owned` to detach the dataset from it.
+///
+/// ```rust
+/// # use uaparser::*;
+/// let dataset = "user_agent_parsers: []\nos_parsers: []\ndevice_parsers: []";
+/// let mut regex_file = RegexFile::from_bytes(dataset.as_bytes())?;
+/// let mut rule = UserAgentParserEntry::new("(Firefox)/(\\d+)");
+/// rule.family_replacement = Some("Firefox".into());
+/// regex_file.user_agent_parsers.push(rule);
+///
+/// assert_eq!(regex_file.rules().count(), 1);
+/// let parser = UserAgentParser::builder().build(regex_file)?;
+/// # Ok::<_, Error>(())
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct RegexFile<'a> {
+    #[serde(borrow)]
+    pub user_agent_parsers: Vec<UserAgentParserEntry<'a>>,
+    #[serde(borrow)]
+    pub os_parsers: Vec<OSParserEntry<'a>>,
+    #[serde(borrow)]
+    pub device_parsers: Vec<DeviceParserEntry<'a>>,
+}
+
+impl<'a> RegexFile<'a> {
+    pub fn new() -> RegexFile<'a> {
+        RegexFile::default()
+    }
+
+    /// Attempts to load a `RegexFile` from the path to a YAML file
+    #[cfg(feature = "fs")]
+    pub fn from_yaml(
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<RegexFile<'static>, Error> {
+        let bytes = std::fs::read(path)?;
+        Ok(RegexFile::from_bytes(&bytes)?.into_owned())
+    }
+
+    /// Attempts to load a `RegexFile` from the raw bytes of a YAML file
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<RegexFile<'a>, Error> {
+        let deserializer = serde_yaml::Deserializer::from_slice(bytes);
+        Ok(RegexFile::deserialize(deserializer)?)
+    }
+
+    /// Copies all borrowed strings, so that the dataset outlives its input
+    pub fn into_owned(self) -> RegexFile<'static> {
+        RegexFile {
+            user_agent_parsers: owned_entries(self.user_agent_parsers),
+            os_parsers: owned_entries(self.os_parsers),
+            device_parsers: owned_entries(self.device_parsers),
+        }
+    }
+
+    /// Returns the rules of all categories, in the order user agent, OS and
+    /// device, as they are matched within each category
+    pub fn rules(&self) -> impl Iterator<Item = RuleEntry<'_>> + '_ {
+        let user_agent = self.user_agent_parsers.iter().map(RuleEntry::UserAgent);
+        let os = self.os_parsers.iter().map(RuleEntry::Os);
+        let device = self.device_parsers.iter().map(RuleEntry::Device);
+        user_agent.chain(os).chain(device)
+    }
+
+    /// Serializes the dataset to YAML, in the format of uap-core
+    pub fn to_yaml(&self) -> Result<String, Error> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+}
+
+/// Deserializes an optional string, borrowing it from the input if possible.
+/// Serde only borrows a `Cow` directly, not within an `Option`.
+fn borrow_opt<'de: 'a, 'a, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Cow<'a, str>>, D::Error> {
+    #[derive(Deserialize)]
+    struct Borrowed<'a>(#[serde(borrow)] Cow<'a, str>);
+
+    let value = Option::<Borrowed<'a>>::deserialize(deserializer)?;
+    Ok(value.map(|borrowed| borrowed.0))
+}
+
+fn owned(value: Cow<'_, str>) -> Cow<'static, str> {
+    Cow::Owned(value.into_owned())
+}
+
+fn owned_opt(value: Option<Cow<'_, str>>) -> Option<Cow<'static, str>> {
+    value.map(owned)
+}
+
+fn owned_entries<E: IntoOwned>(entries: Vec<E>) -> Vec<E::Owned> {
+    entries.into_iter().map(IntoOwned::into_owned).collect()
+}
+
+trait IntoOwned {
+    type Owned;
+    fn into_owned(self) -> Self::Owned;
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct UserAgentParserEntry<'a> {
+    #[serde(borrow)]
+    pub regex: Cow<'a, str>,
+    #[serde(default, deserialize_with = "borrow_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub family_replacement: Option<Cow<'a, str>>,
+    #[serde(default, deserialize_with = "borrow_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub v1_replacement: Option<Cow<'a, str>>,
+    #[serde(default, deserialize_with = "borrow_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub v2_replacement: Option<Cow<'a, str>>,
+    #[serde(default, deserialize_with = "borrow_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub v3_replacement: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl<'a> UserAgentParserEntry<'a> {
+    /// Returns a rule matching `regex`, without replacements or tags
+    pub fn new(regex: impl Into<Cow<'a, str>>) -> UserAgentParserEntry<'a> {
+        UserAgentParserEntry {
+            regex: regex.into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl IntoOwned for UserAgentParserEntry<'_> {
+    type Owned = UserAgentParserEntry<'static>;
+
+    fn into_owned(self) -> UserAgentParserEntry<'static> {
+        UserAgentParserEntry {
+            regex: owned(self.regex),
+            family_replacement: owned_opt(self.family_replacement),
+            v1_replacement: owned_opt(self.v1_replacement),
+            v2_replacement: owned_opt(self.v2_replacement),
+            v3_replacement: owned_opt(self.v3_replacement),
+            tags: self.tags,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct OSParserEntry<'a> {
+    #[serde(borrow)]
+    pub regex: Cow<'a, str>,
+    #[serde(default, deserialize_with = "borrow_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os_replacement: Option<Cow<'a, str>>,
+    #[serde(default, deserialize_with = "borrow_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os_v1_replacement: Option<Cow<'a, str>>,
+    #[serde(default, deserialize_with = "borrow_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os_v2_replacement: Option<Cow<'a, str>>,
+    #[serde(default, deserialize_with = "borrow_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os_v3_replacement: Option<Cow<'a, str>>,
+    #[serde(default, deserialize_with = "borrow_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os_v4_replacement: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl<'a> OSParserEntry<'a> {
+    /// Returns a rule matching `regex`, without replacements or tags
+    pub fn new(regex: impl Into<Cow<'a, str>>) -> OSParserEntry<'a> {
+        OSParserEntry {
+            regex: regex.into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl IntoOwned for OSParserEntry<'_> {
+    type Owned = OSParserEntry<'static>;
+
+    fn into_owned(self) -> OSParserEntry<'static> {
+        OSParserEntry {
+            regex: owned(self.regex),
+            os_replacement: owned_opt(self.os_replacement),
+            os_v1_replacement: owned_opt(self.os_v1_replacement),
+            os_v2_replacement: owned_opt(self.os_v2_replacement),
+            os_v3_replacement: owned_opt(self.os_v3_replacement),
+            os_v4_replacement: owned_opt(self.os_v4_replacement),
+            tags: self.tags,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct DeviceParserEntry<'a> {
+    #[serde(default, deserialize_with = "borrow_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regex_flag: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub regex: Cow<'a, str>,
+    #[serde(default, deserialize_with = "borrow_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_replacement: Option<Cow<'a, str>>,
+    #[serde(default, deserialize_with = "borrow_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brand_replacement: Option<Cow<'a, str>>,
+    #[serde(default, deserialize_with = "borrow_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_replacement: Option<Cow<'a, str>>,
+    /// The type of device, found as `type_replacement` or `device_type` in
+    /// extended datasets, see `Device::kind`
+    #[serde(alias = "device_type", default, deserialize_with = "borrow_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_replacement: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl<'a> DeviceParserEntry<'a> {
+    /// Returns a rule matching `regex`, without replacements or tags
+    pub fn new(regex: impl Into<Cow<'a, str>>) -> DeviceParserEntry<'a> {
+        DeviceParserEntry {
+            regex: regex.into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl IntoOwned for DeviceParserEntry<'_> {
+    type Owned = DeviceParserEntry<'static>;
+
+    fn into_owned(self) -> DeviceParserEntry<'static> {
+        DeviceParserEntry {
+            regex_flag: owned_opt(self.regex_flag),
+            regex: owned(self.regex),
+            device_replacement: owned_opt(self.device_replacement),
+            brand_replacement: owned_opt(self.brand_replacement),
+            model_replacement: owned_opt(self.model_replacement),
+            type_replacement: owned_opt(self.type_replacement),
+            tags: self.tags,
+        }
+    }
+}
+
+/// A rule of any category, as passed to `UserAgentParserBuilder::filter_rules`
+#[derive(Clone, Copy, Debug)]
+pub enum RuleEntry<'a> {
+    UserAgent(&'a UserAgentParserEntry<'a>),
+    Os(&'a OSParserEntry<'a>),
+    Device(&'a DeviceParserEntry<'a>),
+}
+
+impl<'a> RuleEntry<'a> {
+    pub fn category(&self) -> Category {
+        match self {
+            RuleEntry::UserAgent(_) => Category::UserAgent,
+            RuleEntry::Os(_) => Category::Os,
+            RuleEntry::Device(_) => Category::Device,
+        }
+    }
+
+    pub fn regex(&self) -> &'a str {
+        match self {
+            RuleEntry::UserAgent(entry) => &entry.regex,
+            RuleEntry::Os(entry) => &entry.regex,
+            RuleEntry::Device(entry) => &entry.regex,
+        }
+    }
+
+    /// Returns the replacement of the family, e.g. `device_replacement` for
+    /// device rules
+    pub fn family_replacement(&self) -> Option<&'a str> {
+        match self {
+            RuleEntry::UserAgent(entry) => entry.family_replacement.as_deref(),
+            RuleEntry::Os(entry) => entry.os_replacement.as_deref(),
+            RuleEntry::Device(entry) => entry.device_replacement.as_deref(),
+        }
+    }
+
+    /// Returns the tags the rule is annotated with in the dataset
+    pub fn tags(&self) -> &'a [String] {
+        match self {
+            RuleEntry::UserAgent(entry) => &entry.tags,
+            RuleEntry::Os(entry) => &entry.tags,
+            RuleEntry::Device(entry) => &entry.tags,
+        }
+    }
 }
@@ -0,0 +1,108 @@
+//! Serializes a `Client` with flat keys like `ua_family`, `os_major` or
+//! `device_brand`, for log schemas and CSV sinks that can't hold nested
+//! objects. Use `Client::flat`, or `#[serde(with = "uaparser::flat")]` on a
+//! `Client` field:
+//!
+//! ```rust
+//! # use uaparser::*;
+//! #[derive(serde_derive::Serialize, serde_derive::Deserialize)]
+//! struct Request {
+//!     path: String,
+//!     #[serde(flatten, with = "uaparser::flat")]
+//!     client: Client,
+//! }
+//! ```
+//!
+//! Fields without a value are written as `null`.
+
+use std::collections::HashMap;
+
+use serde::{ser::SerializeMap, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{Arch, Client, Product};
+
+/// A `Client` serialized with flat keys, see `Client::flat`
+#[derive(Clone, Copy, Debug)]
+pub struct FlatClient<'a>(pub &'a Client);
+
+impl Serialize for FlatClient<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let fields = self.0.flat_fields();
+        let mut map = serializer.serialize_map(Some(fields.len()))?;
+        for (name, value) in fields {
+            map.serialize_entry(name, &value)?;
+        }
+        map.end()
+    }
+}
+
+impl Client {
+    /// Returns a wrapper serializing this `Client` with flat keys
+    pub fn flat(&self) -> FlatClient<'_> {
+        FlatClient(self)
+    }
+
+    /// Builds a `Client` from flat keys, as written by `flat`. Missing keys are
+    /// left at their defaults.
+    pub fn from_flat(mut fields: HashMap<String, Option<String>>) -> Client {
+        let mut field = |name: &str| fields.remove(name).flatten();
+        let mut client = Client::default();
+
+        let ua = &mut client.user_agent;
+        ua.family = field("ua_family").unwrap_or(ua.family.clone());
+        ua.major = field("ua_major");
+        ua.minor = field("ua_minor");
+        ua.patch = field("ua_patch");
+        ua.chromium_version = field("ua_chromium_version");
+        ua.app = product(field("ua_app_name"), field("ua_app_version"));
+        ua.runtime = product(field("ua_runtime_name"), field("ua_runtime_version"));
+        ua.framework = product(field("ua_framework_name"), field("ua_framework_version"));
+
+        let os = &mut client.os;
+        os.family = field("os_family").unwrap_or(os.family.clone());
+        os.major = field("os_major");
+        os.minor = field("os_minor");
+        os.patch = field("os_patch");
+        os.patch_minor = field("os_patch_minor");
+        os.arch = field("os_arch").and_then(|arch| {
+            let arches = [Arch::X86, Arch::X86_64, Arch::Arm, Arch::Arm64];
+            arches.iter().copied().find(|known| known.as_str() == arch)
+        });
+        os.bitness = field("os_bitness").and_then(|bitness| bitness.parse().ok());
+        os.distro = field("os_distro");
+        os.distro_version = field("os_distro_version");
+
+        let device = &mut client.device;
+        device.family = field("device_family").unwrap_or(device.family.clone());
+        device.brand = field("device_brand");
+        device.model = field("device_model");
+        device.kind = field("device_kind");
+
+        client
+    }
+}
+
+/// Restores a `Product` from its flat fields, which requires a name
+fn product(name: Option<String>, version: Option<String>) -> Option<Product> {
+    Some(Product {
+        name: name?,
+        version,
+    })
+}
+
+/// Serializes `client` with flat keys, for `#[serde(with = "uaparser::flat")]`
+pub fn serialize<S: Serializer>(
+    client: &Client,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    client.flat().serialize(serializer)
+}
+
+/// Deserializes a `Client` from flat keys, for
+/// `#[serde(with = "uaparser::flat")]`
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Client, D::Error> {
+    let fields = HashMap::<String, Option<String>>::deserialize(deserializer)?;
+    Ok(Client::from_flat(fields))
+}
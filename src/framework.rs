@@ -0,0 +1,33 @@
+use super::Product;
+
+/// Product tokens of networking stacks and app frameworks, along with the name
+/// they're reported as, in order of precedence. Libraries built on top of
+/// another stack come first, e.g. Alamofire before CFNetwork.
+const FRAMEWORKS: &[(&str, &str)] = &[
+    ("alamofire/", "Alamofire"),
+    ("reactnative/", "React Native"),
+    ("react-native/", "React Native"),
+    ("dart/", "Dart"),
+    ("okhttp/", "okhttp"),
+    ("cfnetwork/", "CFNetwork"),
+];
+
+/// Finds the networking stack or framework that sent a request from a native
+/// app, e.g. `okhttp 4.9.0` in `okhttp/4.9.0`. Tokens are matched
+/// case-insensitively. React Native doesn't add a token of its own by
+/// default, so its apps are usually reported by their platform's stack.
+pub(crate) fn detect(user_agent: &str) -> Option<Product> {
+    FRAMEWORKS.iter().find_map(|&(prefix, name)| {
+        let token = user_agent.split_whitespace().find(|token| {
+            token.len() > prefix.len()
+                && token.is_char_boundary(prefix.len())
+                && token[..prefix.len()].eq_ignore_ascii_case(prefix)
+        })?;
+
+        let product = Product::from_token(token)?;
+        Some(Product {
+            name: name.to_owned(),
+            version: product.version,
+        })
+    })
+}
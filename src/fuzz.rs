@@ -0,0 +1,91 @@
+//! Tokens of real user agents shared by the `Arbitrary` implementation of
+//! `UserAgentString` and the proptest strategies of `strategy`
+
+#[cfg(feature = "arbitrary")]
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+pub(crate) const PLATFORMS: &[&str] = &[
+    "Windows NT 10.0; Win64; x64",
+    "Macintosh; Intel Mac OS X 10_15_7",
+    "X11; Linux x86_64",
+    "Linux; Android 13; Pixel 7",
+    "Linux; Android 12; SM-S901B",
+    "iPhone; CPU iPhone OS 17_1 like Mac OS X",
+    "iPad; CPU OS 16_6 like Mac OS X",
+    "compatible; Googlebot/2.1; +http://www.google.com/bot.html",
+    "Mobile; Nokia_8110_4G; rv:48.0",
+];
+
+pub(crate) const ENGINES: &[&str] =
+    &["AppleWebKit/537.36 (KHTML, like Gecko)", "Gecko/20100101"];
+
+pub(crate) const PRODUCTS: &[&str] = &[
+    "Chrome",
+    "Firefox",
+    "Safari",
+    "Version",
+    "Mobile",
+    "Edg",
+    "OPR",
+    "SamsungBrowser",
+    "CriOS",
+    "Electron",
+    "okhttp",
+    "curl",
+];
+
+/// A user agent string shaped like the ones sent by real clients, built from
+/// common platform and product tokens with arbitrary versions and noise.
+/// Unlike arbitrary strings, these reach deep into the rules of a dataset,
+/// which makes them useful to property test the parser and code consuming
+/// its results.
+#[cfg(feature = "arbitrary")]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct UserAgentString(pub String);
+
+#[cfg(feature = "arbitrary")]
+impl UserAgentString {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl std::fmt::Display for UserAgentString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for UserAgentString {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut user_agent = String::new();
+
+        if u.arbitrary()? {
+            user_agent.push_str("Mozilla/5.0 ");
+        }
+        if u.arbitrary()? {
+            user_agent.push('(');
+            user_agent.push_str(u.choose(PLATFORMS)?);
+            user_agent.push_str(") ");
+        }
+        if u.arbitrary()? {
+            user_agent.push_str(u.choose(ENGINES)?);
+            user_agent.push(' ');
+        }
+
+        for _ in 0..u.int_in_range(1..=3)? {
+            let major: u16 = u.int_in_range(0..=130)?;
+            let minor: u16 = u.int_in_range(0..=9)?;
+            let product = u.choose(PRODUCTS)?;
+            user_agent.push_str(&format!("{}/{}.{} ", product, major, minor));
+        }
+
+        if u.ratio(1, 4)? {
+            user_agent.push_str(u.arbitrary::<&str>()?);
+        }
+
+        Ok(UserAgentString(user_agent.trim_end().to_owned()))
+    }
+}
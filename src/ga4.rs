@@ -0,0 +1,50 @@
+use super::{Client, DeviceType};
+
+/// The device category reported by Google Analytics 4, for parity with its
+/// reports. See `DeviceType::ga4_category` for how types are bucketed.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Ga4DeviceCategory {
+    Desktop,
+    Mobile,
+    Tablet,
+}
+
+impl Ga4DeviceCategory {
+    /// The lowercase name used in GA4 reports and exports, e.g. `"mobile"`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Ga4DeviceCategory::Desktop => "desktop",
+            Ga4DeviceCategory::Mobile => "mobile",
+            Ga4DeviceCategory::Tablet => "tablet",
+        }
+    }
+}
+
+impl DeviceType {
+    /// Buckets this type into the GA4 taxonomy. Tablets stay tablets, and
+    /// smartphones, feature phones and wearables count as mobile. Everything
+    /// else, including TVs, consoles, smart speakers, bots and unknown devices,
+    /// counts as desktop, which is where GA4 files traffic it can't classify.
+    pub fn ga4_category(self) -> Ga4DeviceCategory {
+        match self {
+            DeviceType::Tablet => Ga4DeviceCategory::Tablet,
+            DeviceType::Smartphone | DeviceType::FeaturePhone | DeviceType::Wearable => {
+                Ga4DeviceCategory::Mobile
+            }
+            DeviceType::Desktop
+            | DeviceType::Tv
+            | DeviceType::Console
+            | DeviceType::SmartSpeaker
+            | DeviceType::Bot
+            | DeviceType::Unknown => Ga4DeviceCategory::Desktop,
+        }
+    }
+}
+
+impl Client {
+    /// Returns the GA4 device category of this `Client`, see
+    /// `DeviceType::ga4_category`
+    pub fn ga4_device_category(&self) -> Ga4DeviceCategory {
+        self.device_type().ga4_category()
+    }
+}
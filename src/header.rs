@@ -0,0 +1,143 @@
+use std::borrow::Cow;
+
+/// The request headers that may carry a user agent, in the order they are
+/// consulted by `Parser::parse_headers`. CDNs and proxies that replace the
+/// `User-Agent` header often preserve the original in one of the others.
+pub const USER_AGENT_HEADERS: &[&str] =
+    &["User-Agent", "X-Original-User-Agent", "X-Device-User-Agent"];
+
+/// The request headers in which proxies pass on the user agent of the stock
+/// browser of a device, which `Parser::parse_headers` takes the device and
+/// operating system from. Opera Mini sends the user agent of its proxy, which
+/// otherwise hides the device.
+pub const DEVICE_STOCK_HEADERS: &[&str] = &["X-OperaMini-Phone-UA", "Device-Stock-UA"];
+
+/// The request header in which Android WebViews send the package name of the
+/// app embedding them, e.g. `com.instagram.android`, which
+/// `Parser::parse_headers` reports as the app of the user agent
+pub const REQUESTED_WITH_HEADER: &str = "X-Requested-With";
+
+/// Collects the values of `USER_AGENT_HEADERS` from name and value pairs, in
+/// order, matching names case-insensitively. Every header may occur several
+/// times, and its values are combined according to `strategy`.
+pub(crate) fn candidates<'a>(
+    headers: impl IntoIterator<Item = (&'a str, &'a str)>,
+    strategy: MultiValueStrategy,
+) -> Vec<Cow<'a, str>> {
+    collect(headers, USER_AGENT_HEADERS, strategy)
+}
+
+/// Returns the first of the `DEVICE_STOCK_HEADERS` found in `headers`
+pub(crate) fn stock_user_agent<'a>(
+    headers: impl IntoIterator<Item = (&'a str, &'a str)>,
+    strategy: MultiValueStrategy,
+) -> Option<Cow<'a, str>> {
+    collect(headers, DEVICE_STOCK_HEADERS, strategy)
+        .into_iter()
+        .next()
+}
+
+/// Returns the value of `REQUESTED_WITH_HEADER` found in `headers`
+pub(crate) fn requested_with<'a>(
+    headers: impl IntoIterator<Item = (&'a str, &'a str)>,
+    strategy: MultiValueStrategy,
+) -> Option<Cow<'a, str>> {
+    collect(headers, &[REQUESTED_WITH_HEADER], strategy)
+        .into_iter()
+        .next()
+}
+
+fn collect<'a>(
+    headers: impl IntoIterator<Item = (&'a str, &'a str)>,
+    names: &[&str],
+    strategy: MultiValueStrategy,
+) -> Vec<Cow<'a, str>> {
+    let mut found: Vec<Vec<&str>> = vec![Vec::new(); names.len()];
+
+    for (name, value) in headers {
+        let position =
+            (names.iter()).position(|header| header.eq_ignore_ascii_case(name.trim()));
+        if let Some(position) = position {
+            found[position].push(value);
+        }
+    }
+
+    found
+        .into_iter()
+        .filter_map(|values| strategy.select(&values))
+        .collect()
+}
+
+/// Picks the user agent from requests that carry several `User-Agent` headers
+/// or several comma-joined values in one header, as some proxies produce
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum MultiValueStrategy {
+    /// The first value, which is usually the one sent by the client
+    #[default]
+    First,
+    /// The last value, e.g. when a proxy prepends its own
+    Last,
+    /// The longest value, which tends to be the most descriptive one
+    Longest,
+    /// All values joined with spaces, so that rules can match any of them
+    Concatenated,
+}
+
+impl MultiValueStrategy {
+    /// Selects the user agent from the values of all `User-Agent` headers of a
+    /// request. Values are split where a proxy joined several with commas,
+    /// which is only assumed outside of parentheses, as in `(KHTML, like
+    /// Gecko)`. Returns `None` if there are no non-empty values.
+    ///
+    /// ```rust
+    /// # use uaparser::*;
+    /// let values = ["Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Firefox/70.0, curl/7.64.1"];
+    /// assert_eq!(
+    ///     MultiValueStrategy::Last.select(&values).as_deref(),
+    ///     Some("curl/7.64.1")
+    /// );
+    /// ```
+    pub fn select<'a>(self, values: &[&'a str]) -> Option<Cow<'a, str>> {
+        let mut values = values.iter().flat_map(|value| split_joined(value));
+
+        match self {
+            MultiValueStrategy::First => values.next().map(Cow::Borrowed),
+            MultiValueStrategy::Last => values.last().map(Cow::Borrowed),
+            MultiValueStrategy::Longest => values
+                .rev()
+                .max_by_key(|value| value.len())
+                .map(Cow::Borrowed),
+            MultiValueStrategy::Concatenated => {
+                let first = values.next()?;
+                Some(values.fold(Cow::Borrowed(first), |joined, value| {
+                    Cow::Owned(format!("{} {}", joined, value))
+                }))
+            }
+        }
+    }
+}
+
+/// Splits a header value at commas outside of parentheses, dropping empty parts
+fn split_joined(value: &str) -> impl DoubleEndedIterator<Item = &str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (index, c) in value.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(&value[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&value[start..]);
+
+    parts
+        .into_iter()
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+}
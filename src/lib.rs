@@ -19,35 +19,550 @@
 //! assert_eq!(client.user_agent, user_agent);
 //! ```
 
-use serde_derive::Deserialize;
+use std::sync::Arc;
 
+use serde_derive::{Deserialize, Serialize};
+
+mod ai_crawler;
+mod alias;
+mod anonymize;
+mod app;
+mod arch;
+#[cfg(feature = "browserslist")]
+mod browserslist;
+#[cfg(feature = "bundled")]
+mod bundled;
+mod cache;
+#[cfg(feature = "caniuse")]
+mod caniuse;
+mod category;
+mod chain;
+mod chromium;
 mod client;
+mod client_hints;
+#[cfg(feature = "fs")]
+mod config;
+mod consistency;
+#[cfg(feature = "corpus")]
+pub mod corpus;
+mod desktop;
 mod device;
+mod device_type;
+mod dictionary;
+#[cfg(feature = "disk-cache")]
+mod disk_cache;
+mod distro;
+mod ecs;
+mod ext;
 mod file;
+pub mod flat;
+mod framework;
+#[cfg(any(feature = "arbitrary", feature = "proptest"))]
+mod fuzz;
+mod ga4;
+mod header;
+mod link_preview;
+mod load_report;
+mod match_info;
+mod minimize;
+mod mock;
+mod model;
+mod openrtb;
 mod os;
+mod otel;
 mod parser;
+mod pipeline;
+mod policy;
+pub mod prelude;
+mod product;
+mod profile;
+#[cfg(feature = "protobuf")]
+pub mod proto;
+#[cfg(feature = "redis-cache")]
+mod redis_cache;
+#[cfg(feature = "referer")]
+mod referer;
+mod registry;
+mod self_test;
+mod sentry;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "proptest")]
+pub mod strategy;
+#[cfg(feature = "stream")]
+mod stream;
+#[cfg(feature = "test-support")]
+mod test_support;
+#[cfg(feature = "typed-headers")]
+mod typed_headers;
 mod user_agent;
+mod voice;
+mod webkit;
 
-pub use parser::{Error, UserAgentParser};
+pub use parser::{
+    BackgroundParser, Captures, Error, KnownValues, PostProcess, RawCaptures,
+    RegexConfig, Rewrite, RuleError, RuleTranslation, UserAgentParser,
+    UserAgentParserBuilder,
+};
 
+pub use ai_crawler::{AiCrawler, AiCrawlerParser, AiPurpose};
+pub use alias::FamilyAliases;
+pub use anonymize::anonymize_log;
+pub use arch::Arch;
+#[cfg(feature = "browserslist")]
+pub use browserslist::{Browserslist, QueryError};
+pub use cache::{CachedParser, KeyNormalizer, LruCache, UaCache};
+#[cfg(feature = "caniuse")]
+pub use caniuse::CaniuseData;
+pub use category::Category;
+pub use chain::ChainedParser;
 pub use client::Client;
+pub use client_hints::{AcceptClientHints, ClientHint};
+#[cfg(feature = "fs")]
+pub use config::ParserConfig;
+pub use consistency::{Anomaly, AnomalyKind};
 pub use device::Device;
+pub use device_type::DeviceType;
+pub use dictionary::{Dictionary, DictionaryParser};
+#[cfg(feature = "disk-cache")]
+pub use disk_cache::{dataset_checksum, DiskCache};
+pub use ecs::{EcsDevice, EcsFields, EcsOs, EcsUserAgent};
+pub use ext::UserAgentExt;
+pub use file::{
+    DeviceParserEntry, OSParserEntry, RegexFile, RuleEntry, UserAgentParserEntry,
+};
+pub use flat::FlatClient;
+#[cfg(feature = "arbitrary")]
+pub use fuzz::UserAgentString;
+pub use ga4::Ga4DeviceCategory;
+pub use header::{
+    MultiValueStrategy, DEVICE_STOCK_HEADERS, REQUESTED_WITH_HEADER, USER_AGENT_HEADERS,
+};
+pub use link_preview::{LinkPreview, LinkPreviewParser};
+pub use load_report::{LoadReport, MemoryUsage, RuleReport};
+pub use match_info::{Confidence, FieldSource, MatchInfo, RuleMatch};
+pub use minimize::{minimize_user_agent, RuleHits};
+pub use mock::MockParser;
+pub use model::normalize_model;
+pub use openrtb::{OpenRtbDevice, OpenRtbHints};
 pub use os::OS;
+pub use otel::OtelValue;
+pub use pipeline::Pipeline;
+pub use policy::{Policy, PolicyAction, PolicyEngine};
+pub use product::Product;
+pub use profile::{ProfileReport, RuleProfile};
+#[cfg(feature = "redis-cache")]
+pub use redis_cache::RedisCache;
+#[cfg(feature = "referer")]
+pub use referer::{Medium, Referer, RefererParser};
+pub use registry::{ParserRegistry, TenantParser};
+pub use self_test::{SelfTestCheck, SelfTestReport};
+pub use sentry::{BrowserContext, DeviceContext, OsContext, SentryContexts};
+#[cfg(feature = "sqlite")]
+pub use sqlite::register_sqlite_functions;
+#[cfg(feature = "stream")]
+pub use stream::parse_stream;
+#[cfg(feature = "test-support")]
+pub use test_support::{ClientBuilder, DeviceBuilder, OsBuilder, UserAgentBuilder};
+#[cfg(feature = "typed-headers")]
+pub use typed_headers::ParsedUserAgent;
+#[cfg(feature = "macros")]
+pub use uaparser_macros::{device_family, os_family, ua_family};
 pub use user_agent::UserAgent;
+pub use voice::VoicePlatform;
+pub use webkit::safari_version_from_webkit;
 
+/// Extracts `Client` info from user agent strings. The trait is object safe,
+/// so services can hold any implementation as `Box<dyn Parser + Send + Sync>`.
 pub trait Parser {
     fn parse(&self, user_agent: &str) -> Client;
     fn parse_device(&self, user_agent: &str) -> Device;
     fn parse_os(&self, user_agent: &str) -> OS;
     fn parse_user_agent(&self, user_agent: &str) -> UserAgent;
+
+    /// Writes the full `Client` info into an existing `Client`. Implementations
+    /// can reuse the allocations of its fields, which pays off in tight loops
+    /// over many user agents.
+    fn parse_into(&self, user_agent: &str, client: &mut Client) {
+        *client = self.parse(user_agent);
+    }
+
+    /// Parses a batch of user agents, returning their results in the same
+    /// order. Every distinct user agent is parsed only once, which pays off
+    /// for batches of log lines, where most user agents repeat. See
+    /// `parse_batch_normalized` to also group near-identical user agents.
+    ///
+    /// ```rust
+    /// # use uaparser::*;
+    /// # let parser = UserAgentParser::from_yaml("./src/core/regexes.yaml").unwrap();
+    /// let firefox = "Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Firefox/70.0";
+    /// let clients = parser.parse_batch(vec![firefox, "curl/7.64.1", firefox]);
+    /// assert_eq!(clients[0], clients[2]);
+    /// ```
+    fn parse_batch<'a, I>(&self, user_agents: I) -> Vec<Client>
+    where
+        I: IntoIterator<Item = &'a str>,
+        Self: Sized,
+    {
+        let mut seen = std::collections::HashMap::new();
+        let mut clients = Vec::new();
+        let positions: Vec<usize> = (user_agents.into_iter())
+            .map(|user_agent| {
+                *seen.entry(user_agent).or_insert_with(|| {
+                    clients.push(self.parse(user_agent));
+                    clients.len() - 1
+                })
+            })
+            .collect();
+
+        positions
+            .into_iter()
+            .map(|position| clients[position].clone())
+            .collect()
+    }
+
+    /// Parses a batch of user agents like `parse_batch`, but also parses
+    /// user agents with the same key of `normalizer` only once, e.g. those
+    /// that differ only in a locale or build hash. The normalizer is verified
+    /// against this parser first, see `KeyNormalizer::verify`, and a group
+    /// only shares a result if parsing its key gives the same result as its
+    /// first user agent.
+    ///
+    /// Grouping is opt-in because the other user agents of a group aren't
+    /// parsed themselves: their result is only as exact as the transforms of
+    /// `normalizer`, and carries no match info.
+    ///
+    /// ```rust
+    /// # use uaparser::*;
+    /// # let parser = UserAgentParser::from_yaml("./src/core/regexes.yaml").unwrap();
+    /// let user_agents = vec![
+    ///     "Mozilla/5.0 (Linux; U; Android 4.4; de-DE) Build/KOT49H",
+    ///     "Mozilla/5.0 (Linux; U; Android 4.4; fr-FR) Build/KOT49H",
+    /// ];
+    /// let normalizer = KeyNormalizer::new().locales();
+    /// let clients = parser.parse_batch_normalized(user_agents, normalizer);
+    /// assert_eq!(clients[0], clients[1]);
+    /// ```
+    fn parse_batch_normalized<'a, I>(
+        &self,
+        user_agents: I,
+        normalizer: KeyNormalizer,
+    ) -> Vec<Client>
+    where
+        I: IntoIterator<Item = &'a str>,
+        Self: Sized,
+    {
+        let normalizer = normalizer.verify(self);
+        let mut seen = std::collections::HashMap::new();
+        let mut groups = std::collections::HashMap::new();
+        let mut clients: Vec<Client> = Vec::new();
+        let mut positions = Vec::new();
+
+        for user_agent in user_agents {
+            if let Some(&position) = seen.get(user_agent) {
+                positions.push(position);
+                continue;
+            }
+
+            let key = normalizer.normalize(user_agent);
+            let position = match groups.get(&key) {
+                Some(&shared) => {
+                    // Match info describes the user agent that was parsed
+                    let mut client = Client::clone(&clients[shared]);
+                    client.match_info = None;
+                    clients.push(client);
+                    clients.len() - 1
+                }
+                None => {
+                    let client = self.parse(user_agent);
+                    let shared = key == user_agent || self.parse(&key) == client;
+                    clients.push(client);
+                    if shared {
+                        groups.insert(key, clients.len() - 1);
+                    }
+                    clients.len() - 1
+                }
+            };
+            seen.insert(user_agent, position);
+            positions.push(position);
+        }
+
+        positions
+            .into_iter()
+            .map(|position| clients[position].clone())
+            .collect()
+    }
+
+    /// Parses the user agent selected by `strategy` from the values of all
+    /// `User-Agent` headers of a request, see `MultiValueStrategy::select`.
+    /// Requests without a user agent are parsed like an empty one.
+    fn parse_header(&self, values: &[&str], strategy: MultiValueStrategy) -> Client {
+        self.parse(strategy.select(values).as_deref().unwrap_or(""))
+    }
+
+    /// Parses the first of `candidates` that a rule matches in any category,
+    /// e.g. user agents taken from several headers in order of preference.
+    /// Otherwise returns the result of the last candidate, or of an empty
+    /// user agent if there are none.
+    fn parse_first_of(&self, candidates: &[&str]) -> Client {
+        let mut last = None;
+
+        for candidate in candidates {
+            let (client, matched) = self.parse_matched(candidate);
+            if matched.iter().any(|&matched| matched) {
+                return client;
+            }
+            last = Some(client);
+        }
+
+        last.unwrap_or_else(|| self.parse(""))
+    }
+
+    /// Parses the full `Client` and tells whether a rule matched its device,
+    /// OS and user agent, in that order. Categories left to the fallback are
+    /// unmatched even if a rule could have produced the same family. The
+    /// default takes this from the match info of the result, and otherwise
+    /// considers every category matched that isn't `is_unknown`.
+    fn parse_matched(&self, user_agent: &str) -> (Client, [bool; 3]) {
+        let client = self.parse(user_agent);
+        let matched = client.matched();
+        (client, matched)
+    }
+
+    /// Parses the user agent of a request from its headers, given as name and
+    /// value pairs. The headers in `USER_AGENT_HEADERS` are consulted in order,
+    /// like with `parse_first_of`, and repeated ones are combined according to
+    /// `strategy`. If one of `DEVICE_STOCK_HEADERS` is present, as with Opera
+    /// Mini, the device and operating system are parsed from it instead, as
+    /// far as a rule matches them there. The package an Android WebView sends
+    /// in `REQUESTED_WITH_HEADER` becomes the `app` of the user agent, unless
+    /// the user agent names one itself. It's ignored along with user agents
+    /// of anything but a WebView, which the header is also sent by.
+    ///
+    /// ```rust
+    /// # use uaparser::*;
+    /// # let parser = UserAgentParser::from_yaml("./src/core/regexes.yaml").unwrap();
+    /// let headers = [
+    ///     ("user-agent", "Amazon CloudFront"),
+    ///     ("x-original-user-agent", "Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Firefox/70.0"),
+    /// ];
+    /// let client = parser.parse_headers(headers.iter().copied(), MultiValueStrategy::First);
+    /// assert_eq!(client.user_agent.family, "Firefox");
+    /// ```
+    fn parse_headers<'a, I>(&self, headers: I, strategy: MultiValueStrategy) -> Client
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+        Self: Sized,
+    {
+        let headers: Vec<(&str, &str)> = headers.into_iter().collect();
+        let candidates = header::candidates(headers.iter().copied(), strategy);
+        let candidates: Vec<&str> = candidates.iter().map(|c| c.as_ref()).collect();
+        let mut client = self.parse_first_of(&candidates);
+
+        if let Some(stock) = header::stock_user_agent(headers.iter().copied(), strategy) {
+            let (stock, matched) = self.parse_matched(&stock);
+            let mut stock_info = stock.match_info.unwrap_or_default();
+            if matched[0] {
+                client.device = stock.device;
+                if let Some(info) = &mut client.match_info {
+                    info.device = stock_info.device.take();
+                }
+            }
+            if matched[1] {
+                client.os = stock.os;
+                if let Some(info) = &mut client.match_info {
+                    info.os = stock_info.os.take();
+                }
+            }
+        }
+
+        if client.user_agent.app.is_none() {
+            client.user_agent.app = header::requested_with(
+                headers.iter().copied(),
+                strategy,
+            )
+            .and_then(|package| {
+                (candidates.iter())
+                    .find_map(|user_agent| app::from_package(user_agent, &package))
+            });
+        }
+        client
+    }
+
+    /// Parses the user agent found in `record` and writes the flattened result
+    /// fields, each name starting with `prefix`, back into the record. The
+    /// user agent is looked up as a JSON pointer if `ua_field` starts with a
+    /// `/`, otherwise as a top-level key. Returns whether the record was
+    /// enriched, which requires it to be an object holding a string user agent.
+    ///
+    /// ```rust
+    /// # use uaparser::*;
+    /// # let parser = UserAgentParser::from_yaml("./src/core/regexes.yaml").unwrap();
+    /// let mut record = serde_json::json!({
+    ///     "request": { "user_agent": "Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Firefox/70.0" }
+    /// });
+    /// parser.enrich_json(&mut record, "/request/user_agent", "client_");
+    /// assert_eq!(record["client_ua_family"], "Firefox");
+    /// ```
+    #[cfg(feature = "json")]
+    fn enrich_json(
+        &self,
+        record: &mut serde_json::Value,
+        ua_field: &str,
+        prefix: &str,
+    ) -> bool {
+        let found = if ua_field.starts_with('/') {
+            record.pointer(ua_field)
+        } else {
+            record.get(ua_field)
+        };
+
+        let client = match found.and_then(serde_json::Value::as_str) {
+            Some(user_agent) => self.parse(user_agent),
+            None => return false,
+        };
+
+        let object = match record.as_object_mut() {
+            Some(object) => object,
+            None => return false,
+        };
+
+        for (name, value) in client.flat_fields() {
+            if let Some(value) = value {
+                object.insert(format!("{}{}", prefix, name), value.into());
+            }
+        }
+
+        true
+    }
+}
+
+macro_rules! forward_parser {
+    ($($ty:ty),*) => {$(
+        impl<P: Parser + ?Sized> Parser for $ty {
+            fn parse(&self, user_agent: &str) -> Client {
+                (**self).parse(user_agent)
+            }
+
+            fn parse_device(&self, user_agent: &str) -> Device {
+                (**self).parse_device(user_agent)
+            }
+
+            fn parse_os(&self, user_agent: &str) -> OS {
+                (**self).parse_os(user_agent)
+            }
+
+            fn parse_user_agent(&self, user_agent: &str) -> UserAgent {
+                (**self).parse_user_agent(user_agent)
+            }
+
+            fn parse_into(&self, user_agent: &str, client: &mut Client) {
+                (**self).parse_into(user_agent, client)
+            }
+
+            fn parse_first_of(&self, candidates: &[&str]) -> Client {
+                (**self).parse_first_of(candidates)
+            }
+
+            fn parse_matched(&self, user_agent: &str) -> (Client, [bool; 3]) {
+                (**self).parse_matched(user_agent)
+            }
+        }
+    )*};
 }
 
-pub(crate) trait SubParser {
+forward_parser!(&P, Box<P>, Arc<P>);
+
+/// Extracts a single kind of information, such as a `UserAgent`, from a user
+/// agent string. The matchers behind `UserAgentParser` implement this trait,
+/// and so can external extractors that should compose with this crate's types.
+///
+/// ```rust
+/// # use uaparser::*;
+/// /// Recognizes the `MyApp/1.2` token sent by an internal mobile app
+/// struct AppToken;
+///
+/// impl SubParser for AppToken {
+///     type Item = UserAgent;
+///
+///     fn try_parse(&self, text: &str) -> Option<UserAgent> {
+///         let version = text.split(' ').find_map(|t| t.strip_prefix("MyApp/"))?;
+///         let mut parts = version.split('.').map(ToOwned::to_owned);
+///
+///         Some(UserAgent {
+///             family: "MyApp".to_owned(),
+///             major: parts.next(),
+///             minor: parts.next(),
+///             patch: parts.next(),
+///             ..UserAgent::default()
+///         })
+///     }
+/// }
+///
+/// let app = AppToken.try_parse("Dalvik/2.1.0 MyApp/1.2").unwrap();
+/// assert_eq!(app.version().as_deref(), Some("1.2"));
+/// assert!([AppToken].try_parse("curl/7.64.1").is_none());
+/// ```
+pub trait SubParser {
+    /// The information extracted on a match
     type Item;
+
+    /// Returns the extracted information, or `None` if `text` doesn't match
     fn try_parse(&self, text: &str) -> Option<Self::Item>;
+
+    /// Writes the extracted information into `item` and returns `true`, or
+    /// returns `false` and leaves `item` untouched if `text` doesn't match.
+    /// Implementations can override this to reuse the allocations of `item`.
+    fn try_parse_into(&self, text: &str, item: &mut Self::Item) -> bool {
+        match self.try_parse(text) {
+            Some(parsed) => {
+                *item = parsed;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Tries every `SubParser` in order and returns the first match
+impl<S: SubParser> SubParser for [S] {
+    type Item = S::Item;
+
+    fn try_parse(&self, text: &str) -> Option<Self::Item> {
+        self.iter().find_map(|parser| parser.try_parse(text))
+    }
+
+    fn try_parse_into(&self, text: &str, item: &mut Self::Item) -> bool {
+        self.iter().any(|parser| parser.try_parse_into(text, item))
+    }
+}
+
+/// Tries every `SubParser` in order and returns the first match
+impl<S: SubParser> SubParser for Vec<S> {
+    type Item = S::Item;
+
+    fn try_parse(&self, text: &str) -> Option<Self::Item> {
+        self.as_slice().try_parse(text)
+    }
+
+    fn try_parse_into(&self, text: &str, item: &mut Self::Item) -> bool {
+        self.as_slice().try_parse_into(text, item)
+    }
+}
+
+impl<S: SubParser + ?Sized> SubParser for Box<S> {
+    type Item = S::Item;
+
+    fn try_parse(&self, text: &str) -> Option<Self::Item> {
+        (**self).try_parse(text)
+    }
+
+    fn try_parse_into(&self, text: &str, item: &mut Self::Item) -> bool {
+        (**self).try_parse_into(text, item)
+    }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "fs"))]
 mod tests {
     use super::*;
     use std::fmt::Debug;
@@ -266,6 +781,2706 @@ mod tests {
         }
     }
 
+    const TEST_REGEXES: &str = r"
+user_agent_parsers:
+  - regex: '(Firefox)/(\d+)\.(\d+)'
+os_parsers:
+  - regex: '(Linux)'
+device_parsers:
+  - regex: 'Pixel (\d+)'
+    device_replacement: 'Pixel $1'
+    brand_replacement: 'Google'
+    model_replacement: 'Pixel $1'
+";
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn flat() {
+        #[derive(serde_derive::Serialize, serde_derive::Deserialize)]
+        struct Request {
+            path: String,
+            #[serde(flatten, with = "super::flat")]
+            client: Client,
+        }
+
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let request = Request {
+            path: "/".to_owned(),
+            client: parser.parse("Mozilla/5.0 (X11; Linux x86_64) Firefox/70.1"),
+        };
+
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["path"], "/");
+        assert_eq!(json["ua_family"], "Firefox");
+        assert_eq!(json["os_arch"], "x86_64");
+        assert_eq!(json["device_brand"], serde_json::Value::Null);
+
+        let request: Request = serde_json::from_value(json).unwrap();
+        let mut client = parser.parse("Mozilla/5.0 (X11; Linux x86_64) Firefox/70.1");
+        client.match_info = None;
+        assert_eq!(request.client, client);
+
+        let product = |name: &str, version: Option<&str>| Product {
+            name: name.to_owned(),
+            version: version.map(str::to_owned),
+        };
+        let mut client = Client::default();
+        client.user_agent.app = Some(product("Slack", Some("4.33.90")));
+        client.user_agent.runtime = Some(product("Electron", Some("25.8.4")));
+        client.user_agent.framework = Some(product("okhttp", None));
+        client.os.bitness = Some(16);
+
+        let json = serde_json::to_value(client.flat()).unwrap();
+        assert_eq!(json["ua_app_name"], "Slack");
+        assert_eq!(json["ua_framework_version"], serde_json::Value::Null);
+        assert_eq!(json["os_bitness"], "16");
+        let fields = serde_json::from_value(json).unwrap();
+        assert_eq!(Client::from_flat(fields), client);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn enrich_json() {
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+
+        let mut record = serde_json::json!({
+            "ua": "Mozilla/5.0 (Linux; Android 10; Pixel 4) Firefox/68.0",
+            "status": 200,
+        });
+
+        assert!(parser.enrich_json(&mut record, "ua", "client."));
+        assert_eq!(
+            record,
+            serde_json::json!({
+                "ua": "Mozilla/5.0 (Linux; Android 10; Pixel 4) Firefox/68.0",
+                "status": 200,
+                "client.ua_family": "Firefox",
+                "client.ua_major": "68",
+                "client.ua_minor": "0",
+                "client.os_family": "Linux",
+                "client.device_family": "Pixel 4",
+                "client.device_brand": "Google",
+                "client.device_model": "Pixel 4",
+            })
+        );
+
+        let mut nested = serde_json::json!({ "http": { "ua": "Firefox/70.1" } });
+        assert!(parser.enrich_json(&mut nested, "/http/ua", ""));
+        assert_eq!(nested["ua_major"], "70");
+
+        assert!(!parser.enrich_json(&mut record, "missing", ""));
+        assert!(!parser.enrich_json(&mut record, "status", ""));
+    }
+
+    #[test]
+    fn parse_into() {
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+
+        let user_agents = [
+            "Mozilla/5.0 (Linux; Android 10; Pixel 4) Firefox/68.0",
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) Firefox/70.1",
+            "curl/7.64.1",
+        ];
+
+        let mut client = parser.parse(user_agents[2]);
+        for user_agent in &user_agents {
+            parser.parse_into(user_agent, &mut client);
+            assert_eq!(client, parser.parse(user_agent));
+        }
+    }
+
+    #[test]
+    fn match_info() {
+        let parser = UserAgentParser::builder()
+            .match_info(true)
+            .dataset_id("test")
+            .build_from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+
+        let user_agent = "Mozilla/5.0 (Linux; Android 10; Pixel 4) Firefox/68.0";
+        let client = parser.parse(user_agent);
+
+        // Match info doesn't take part in comparisons
+        let plain = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed")
+            .parse(user_agent);
+        assert!(plain.match_info.is_none());
+        assert_eq!(client, plain);
+
+        let info = client.match_info.expect("match info is enabled");
+        assert_eq!(info.dataset.as_deref(), Some("test"));
+        assert!(info.os.is_some());
+
+        let device = info.device.expect("device rule matched");
+        assert_eq!(device.index, Some(0));
+        assert_eq!(
+            device.fields,
+            vec![
+                ("family", FieldSource::Replacement),
+                ("brand", FieldSource::Replacement),
+                ("model", FieldSource::Replacement),
+            ]
+        );
+
+        assert_eq!(device.confidence, Confidence::Medium);
+        assert_eq!(device.span, 32..39);
+
+        let user_agent = info.user_agent.expect("user agent rule matched");
+        assert_eq!(user_agent.fields[0], ("family", FieldSource::Capture(1)));
+
+        let mut client = Client::default();
+        parser.parse_into("curl/7.64.1", &mut client);
+        let info = client.match_info.expect("match info is enabled");
+        assert_eq!(info.device, None);
+        assert_eq!(info.user_agent, None);
+    }
+
+    #[test]
+    fn load_report() {
+        let parser = UserAgentParser::builder()
+            .diagnostics(true)
+            .build_from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+
+        let report = parser.load_report().expect("diagnostics are enabled");
+        assert_eq!(report.rules.len(), 3);
+        assert_eq!(report.slowest(2).len(), 2);
+
+        let largest = report.largest(1)[0];
+        assert!(largest.compiled_size.is_some());
+        assert!(report.rules.iter().all(|rule| rule.index == Some(0)));
+
+        assert!(UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed")
+            .load_report()
+            .is_none());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn from_json() {
+        let regexes: serde_json::Value =
+            serde_yaml::from_str(TEST_REGEXES).expect("test regexes are valid");
+        let json = serde_json::to_vec(&regexes).unwrap();
+
+        let from_json =
+            UserAgentParser::from_json(&json).expect("Parser creation failed");
+        let from_yaml = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+
+        let user_agent = "Mozilla/5.0 (Linux; Android 10; Pixel 4) Firefox/68.0";
+        assert_eq!(from_json.parse(user_agent), from_yaml.parse(user_agent));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn from_toml() {
+        let regexes: toml::Value =
+            serde_yaml::from_str(TEST_REGEXES).expect("test regexes are valid");
+        let toml = toml::to_string(&regexes).unwrap();
+
+        let from_toml = UserAgentParser::from_toml_bytes(toml.as_bytes())
+            .expect("Parser creation failed");
+        let from_yaml = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+
+        let user_agent = "Mozilla/5.0 (Linux; Android 10; Pixel 4) Firefox/68.0";
+        assert_eq!(from_toml.parse(user_agent), from_yaml.parse(user_agent));
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let user_agent = "MOZILLA/5.0 (LINUX; ANDROID 10; PIXEL 4) FIREFOX/68.0";
+
+        let strict = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        assert_eq!(strict.parse_user_agent(user_agent).family, "Other");
+
+        let parser = UserAgentParser::builder()
+            .case_insensitive(true)
+            .build_from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+
+        let client = parser.parse(user_agent);
+        assert_eq!(client.user_agent.family, "FIREFOX");
+        assert_eq!(client.user_agent.major.as_deref(), Some("68"));
+        assert_eq!(client.os.family, "LINUX");
+        assert_eq!(client.device.family, "Pixel 4");
+    }
+
+    #[test]
+    fn replacements() {
+        let regexes = r"
+user_agent_parsers:
+  - regex: '(Shop)App/(\d+)'
+    family_replacement: 'US$$ $1 $$$1'
+os_parsers:
+  - regex: '(Pay) OS'
+    os_replacement: '$ $1 $'
+    os_v1_replacement: '$$1'
+device_parsers:
+  - regex: 'Kiosk (\w+) (\w+)'
+    device_replacement: ' $2-$1 $9 '
+    brand_replacement: '$$'
+    model_replacement: '$1$$$2'
+";
+        let parser = UserAgentParser::from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+        let client = parser.parse("ShopApp/3 (Pay OS; Kiosk K1 Acme)");
+
+        assert_eq!(client.user_agent.family, "US$ Shop $Shop");
+        assert_eq!(client.user_agent.major.as_deref(), Some("3"));
+        assert_eq!(client.os.family, "$ Pay $");
+        assert_eq!(client.os.major.as_deref(), Some("$1"));
+        assert_eq!(client.device.family, "Acme-K1 $9");
+        assert_eq!(client.device.brand.as_deref(), Some("$"));
+        assert_eq!(client.device.model.as_deref(), Some("K1$Acme"));
+    }
+
+    #[test]
+    fn post_process() {
+        let regexes = r"
+user_agent_parsers:
+  - regex: 'App/\[(.+)\]'
+    family_replacement: ' $1 Browser;'
+os_parsers: []
+device_parsers: []
+";
+        let user_agent = "App/[Tab  Lite ]";
+
+        let parser = UserAgentParser::from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+        assert_eq!(
+            parser.parse_user_agent(user_agent).family,
+            "Tab  Lite  Browser;"
+        );
+
+        let parser = UserAgentParser::builder()
+            .post_process(vec![])
+            .build_from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+        assert_eq!(
+            parser.parse_user_agent(user_agent).family,
+            " Tab  Lite  Browser;"
+        );
+
+        let parser = UserAgentParser::builder()
+            .post_process(vec![
+                PostProcess::CollapseWhitespace,
+                PostProcess::StripTrailingPunctuation,
+                PostProcess::Trim,
+            ])
+            .build_from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+        assert_eq!(
+            parser.parse_user_agent(user_agent).family,
+            "Tab Lite Browser"
+        );
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn normalize_unicode() {
+        let regexes = "
+user_agent_parsers: []
+os_parsers: []
+device_parsers:
+  - regex: 'Model/(\\S+)'
+";
+        let user_agent = "Model/Ｃafe\u{301}Ｘ1";
+
+        let parser = UserAgentParser::from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+        assert_eq!(parser.parse_device(user_agent).family, "Ｃafe\u{301}Ｘ1");
+
+        let parser = UserAgentParser::builder()
+            .normalize_unicode(true)
+            .build_from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+        assert_eq!(parser.parse_device(user_agent).family, "Ｃaf\u{e9}Ｘ1");
+
+        let parser = UserAgentParser::builder()
+            .ascii_fold(true)
+            .build_from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+        assert_eq!(parser.parse_device(user_agent).family, "CafeX1");
+    }
+
+    #[test]
+    #[cfg(feature = "prefilter")]
+    fn prefilter() {
+        let regexes = r"
+user_agent_parsers:
+  - regex: '(Shop)App/(\d+)(?= Pay)'
+  - regex: '(\w+)App/(\d+)'
+  - regex: 'Pay(Kit)'
+os_parsers:
+  - regex: '(Pay) OS'
+device_parsers:
+  - regex: 'Kiosk (\w+)'
+";
+        let parser = UserAgentParser::from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+        let prefiltered = UserAgentParser::builder()
+            .prefilter(true)
+            .build_from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+
+        for user_agent in &["ShopApp/3 Pay OS", "ShopApp/3 Kiosk K1", "PayKit", ""] {
+            assert_eq!(prefiltered.parse(user_agent), parser.parse(user_agent));
+            assert_eq!(
+                prefiltered.parse_device(user_agent),
+                parser.parse_device(user_agent)
+            );
+        }
+        assert_eq!(prefiltered.parse_user_agent("ShopApp/3 Pay").family, "Shop");
+
+        for parser in &[&parser, &prefiltered] {
+            let user_agent = "ShopApp/3 PayKit Pay OS";
+            let indices: Vec<_> = parser.user_agent_match_indices(user_agent).collect();
+            assert_eq!(indices, [0, 1, 2]);
+            assert_eq!(parser.os_match_indices(user_agent).collect::<Vec<_>>(), [0]);
+            assert_eq!(parser.device_match_indices(user_agent).count(), 0);
+        }
+    }
+
+    #[test]
+    fn parse_batch() {
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let user_agents = ["Firefox/68.0", "Pixel 4", "Firefox/68.0", "", "Pixel 4"];
+
+        let clients = parser.parse_batch(user_agents.iter().copied());
+        let expected: Vec<_> = user_agents.iter().map(|ua| parser.parse(ua)).collect();
+        assert_eq!(clients, expected);
+        assert!(parser.parse_batch(Vec::new()).is_empty());
+
+        let regexes = r"
+user_agent_parsers:
+  - regex: '(Firefox)/(\d+)\.(\d+)'
+os_parsers:
+  - regex: '(Linux)'
+device_parsers:
+  - regex: '; ([a-z]{2})-[A-Z]{2}\)'
+    device_replacement: 'Locale $1'
+";
+        let user_agents = [
+            "Mozilla/5.0 (X11; Linux x86_64; en-US) Firefox/68.0",
+            "Mozilla/5.0 (X11; Linux x86_64; de-DE) Firefox/68.0",
+            "Mozilla/5.0 (X11; en-US; Linux x86_64) Firefox/68.0",
+            "Mozilla/5.0 (X11; de-DE; Linux x86_64) Firefox/68.0",
+            "Mozilla/5.0 (X11; Linux x86_64; en-US) Firefox/68.0",
+        ];
+        let batch = |regexes: &str| {
+            let parser = UserAgentParser::builder()
+                .match_info(true)
+                .build_from_bytes(regexes.as_bytes())
+                .expect("Parser creation failed");
+            let normalizer = KeyNormalizer::new().locales();
+            let clients =
+                parser.parse_batch_normalized(user_agents.iter().copied(), normalizer);
+            let expected: Vec<_> =
+                user_agents.iter().map(|ua| parser.parse(ua)).collect();
+            assert_eq!(clients, expected);
+            clients
+        };
+
+        // The device rule looks at the locale, so no user agents are grouped
+        let clients = batch(regexes);
+        assert_eq!(clients[0].device.family, "Locale en");
+        assert_eq!(clients[1].device.family, "Locale de");
+        assert!(clients.iter().all(|client| client.match_info.is_some()));
+
+        // Once it doesn't, all of them share the result of the first, but only
+        // that one and its exact duplicate carry match info
+        let clients = batch(&regexes.replace(r"; ([a-z]{2})-[A-Z]{2}\)", "(Kiosk)"));
+        assert!(clients[0].match_info.is_some());
+        assert!(clients[1].match_info.is_none());
+        assert!(clients[4].match_info.is_some());
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn parse_stream() {
+        use futures_util::{stream, StreamExt};
+
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let parser = Arc::new(parser);
+        let user_agents = ["Firefox/68.0", "Pixel 4", "Firefox/68.0", "Linux"];
+
+        let expected: Vec<_> = user_agents.iter().map(|ua| parser.parse(ua)).collect();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        for &concurrency in &[1, 4] {
+            let upstream = stream::iter(user_agents.iter().map(|ua| ua.to_string()));
+            let clients =
+                super::parse_stream(Arc::clone(&parser), upstream, 1, concurrency, 1);
+            let clients = runtime.block_on(clients.collect::<Vec<_>>());
+            assert_eq!(clients, expected);
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn async_constructors() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        let from_reader = runtime
+            .block_on(UserAgentParser::from_reader_async(TEST_REGEXES.as_bytes()))
+            .expect("Parser creation failed");
+        assert_eq!(from_reader.parse_os("Linux").family, "Linux");
+
+        let missing = runtime.block_on(UserAgentParser::from_path_async("missing.yaml"));
+        assert!(matches!(missing, Err(Error::IO(_))));
+    }
+
+    #[test]
+    fn multiple_header_values() {
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let values = [
+            "Mozilla/5.0 (Linux, like Gecko) Firefox/68.0, ",
+            "Mozilla/5.0 (Pixel 4; Android 10) Firefox/70.1",
+        ];
+
+        let client = parser.parse_header(&values, MultiValueStrategy::First);
+        assert_eq!(client.user_agent.major.as_deref(), Some("68"));
+        assert_eq!(client.device.family, "Other");
+
+        let client = parser.parse_header(&values, MultiValueStrategy::Longest);
+        assert_eq!(client.user_agent.major.as_deref(), Some("70"));
+
+        let client = parser.parse_header(&values, MultiValueStrategy::Concatenated);
+        assert_eq!(client.user_agent.major.as_deref(), Some("68"));
+        assert_eq!(client.device.family, "Pixel 4");
+
+        let client = parser.parse_header(&[" , "], MultiValueStrategy::Last);
+        assert_eq!(client.user_agent.family, "Other");
+    }
+
+    #[test]
+    fn parse_first_of() {
+        let parser = UserAgentParser::builder()
+            .fallback_family("Unknown")
+            .build_from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+
+        let client =
+            parser.parse_first_of(&["Amazon CloudFront", "Linux", "Firefox/70.0"]);
+        assert_eq!(client.os.family, "Linux");
+        assert_eq!(client.user_agent.family, "Unknown");
+
+        let client = parser.parse_first_of(&["curl/7.64.1"]);
+        assert_eq!(client.user_agent.family, "Unknown");
+        assert_eq!(parser.parse_first_of(&[]), parser.parse(""));
+
+        // A rule producing the `Other` family still counts as a match
+        let other = r#"
+user_agent_parsers:
+  - regex: '(Proxy)/(\d+)'
+    family_replacement: 'Other'
+  - regex: '(Firefox)/(\d+)'
+os_parsers: []
+device_parsers: []
+"#;
+        let proxy = UserAgentParser::from_bytes(other.as_bytes()).unwrap();
+        let client = proxy.parse_first_of(&["Proxy/2", "Firefox/70"]);
+        assert_eq!(client.user_agent.family, "Other");
+        assert_eq!(client.user_agent.major.as_deref(), Some("2"));
+
+        let chain = ChainedParser::new()
+            .with(proxy)
+            .with(MockParser::with_fallback(Client {
+                user_agent: UserAgent {
+                    family: "Mock".to_owned(),
+                    ..UserAgent::default()
+                },
+                ..Client::default()
+            }));
+        assert_eq!(
+            chain.parse_user_agent("Proxy/2").major.as_deref(),
+            Some("2")
+        );
+        assert_eq!(chain.parse_user_agent("curl/7").family, "Mock");
+
+        let headers = [
+            ("X-Device-User-Agent", "Firefox/68.0"),
+            ("USER-AGENT", "Amazon CloudFront"),
+            ("X-Original-User-Agent", "Firefox/70.0"),
+        ];
+        let client = parser.parse_headers(headers.iter().copied(), Default::default());
+        assert_eq!(client.user_agent.major.as_deref(), Some("70"));
+
+        let headers = [
+            (
+                "User-Agent",
+                "Opera/9.80 (J2ME/MIDP; Opera Mini; U) Firefox/70.0",
+            ),
+            (
+                "x-operamini-phone-ua",
+                "Mozilla/5.0 (Linux; Android 12; Pixel 6)",
+            ),
+        ];
+        let client = parser.parse_headers(headers.iter().copied(), Default::default());
+        assert_eq!(client.user_agent.major.as_deref(), Some("70"));
+        assert_eq!(client.device.family, "Pixel 6");
+        assert_eq!(client.os.family, "Linux");
+
+        // A stock user agent matching no rule leaves the results in place
+        let headers = [
+            (
+                "User-Agent",
+                "Mozilla/5.0 (Linux; Android 12; Pixel 6) Firefox/70.0",
+            ),
+            ("Device-Stock-UA", "Opera/9.80 (J2ME/MIDP)"),
+        ];
+        let client = parser.parse_headers(headers.iter().copied(), Default::default());
+        assert_eq!(client.device.family, "Pixel 6");
+        assert_eq!(client.os.family, "Linux");
+
+        let webview = "Mozilla/5.0 (Linux; Android 14; wv) Chrome/120.0.0.0 Mobile";
+        let app = |requested_with| {
+            let headers = [
+                ("User-Agent", webview),
+                ("X-Requested-With", requested_with),
+            ];
+            let client =
+                parser.parse_headers(headers.iter().copied(), Default::default());
+            client.user_agent.app.map(|app| app.name)
+        };
+        assert_eq!(app("com.instagram.android").as_deref(), Some("Instagram"));
+        assert_eq!(app("com.example.shop").as_deref(), Some("com.example.shop"));
+        assert_eq!(app("com.android.chrome"), None);
+        assert_eq!(app("XMLHttpRequest"), None);
+        assert_eq!(app("com.1password.android"), None);
+        assert_eq!(app(".hidden.app"), None);
+
+        // Older WebViews have no `wv` token
+        let headers = [
+            (
+                "User-Agent",
+                "Mozilla/5.0 (Linux; Android 4.4.2; Nexus 5 Build/KOT49H) \
+                 AppleWebKit/537.36 (KHTML, like Gecko) Version/4.0 \
+                 Chrome/30.0.0.0 Mobile Safari/537.36",
+            ),
+            ("X-Requested-With", "com.example.shop"),
+        ];
+        let client = parser.parse_headers(headers.iter().copied(), Default::default());
+        assert_eq!(client.user_agent.app.unwrap().name, "com.example.shop");
+
+        // Browsers and libraries send the header for their own requests
+        for user_agent in &[
+            "Mozilla/5.0 (Linux; Android 14) Chrome/120.0.0.0 Mobile",
+            "Mozilla/5.0 (X11; Linux x86_64) Firefox/70.0",
+        ] {
+            let headers = [
+                ("User-Agent", *user_agent),
+                ("X-Requested-With", "com.example.shop"),
+            ];
+            let client =
+                parser.parse_headers(headers.iter().copied(), Default::default());
+            assert_eq!(client.user_agent.app, None);
+        }
+    }
+
+    #[test]
+    fn multiple_documents() {
+        let regexes = "
+user_agent_parsers:
+  - regex: '(Shop)App'
+os_parsers: []
+---
+user_agent_parsers:
+  - regex: '(Pay)App'
+device_parsers: []
+";
+        let parser = UserAgentParser::from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+        assert_eq!(parser.parse_user_agent("ShopApp").family, "Shop");
+        assert_eq!(parser.parse_user_agent("PayApp").family, "Pay");
+
+        let included = "include: [vendor.yaml]\nos_parsers: []\ndevice_parsers: []";
+        assert!(UserAgentParser::from_bytes(included.as_bytes()).is_err());
+        assert!(UserAgentParser::from_bytes(b"os_parsers: []").is_err());
+    }
+
+    #[test]
+    fn rule_errors() {
+        let missing = "user_agent_parsers: []
+device_parsers: []
+os_parsers:
+  - regex: 'Windows'
+  - os_replacement: Linux
+";
+        let error = UserAgentParser::from_bytes(missing.as_bytes()).unwrap_err();
+        match &error {
+            Error::Rule(rule) => {
+                assert_eq!((rule.category, rule.index), (Category::Os, 1));
+                assert_eq!(rule.line, Some(5));
+                assert!(matches!(*rule.error, Error::Yaml(_)));
+            }
+            _ => panic!("unexpected error: {}", error),
+        }
+        assert!(error.to_string().starts_with("os_parsers[1] at line 5"));
+
+        // Lazily compiled rules can't fail on construction
+        if cfg!(feature = "lazy") {
+            return;
+        }
+
+        let invalid = missing.replace("os_replacement: Linux", "regex: '(Linux'");
+        match UserAgentParser::from_bytes(invalid.as_bytes()) {
+            Err(Error::Rule(rule)) => {
+                assert_eq!((rule.category, rule.index), (Category::Os, 1));
+                assert_eq!(rule.line, Some(5));
+                assert!(matches!(*rule.error, Error::OS(_)));
+            }
+            _ => panic!("expected a rule error"),
+        }
+
+        let regex_file = RegexFile::from_bytes(invalid.as_bytes()).unwrap();
+        match UserAgentParser::builder().build(regex_file) {
+            Err(Error::Rule(rule)) => {
+                assert_eq!(
+                    (rule.category, rule.index, rule.line),
+                    (Category::Os, 1, None)
+                )
+            }
+            _ => panic!("expected a rule error"),
+        }
+    }
+
+    #[test]
+    fn filter_rules() {
+        let user_agent = "Mozilla/5.0 (Linux; Android 10; Pixel 4) Firefox/68.0";
+        let parser = UserAgentParser::builder()
+            .filter_rules(|category, _| category != Category::Device)
+            .filter_rules(|_, entry| entry.regex() != "(Linux)")
+            .build_from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+
+        let client = parser.parse(user_agent);
+        assert_eq!(client.user_agent.family, "Firefox");
+        assert_eq!(client.os.family, "Other");
+        assert_eq!(client.device.family, "Other");
+
+        let regex_file = RegexFile::from_bytes(TEST_REGEXES.as_bytes()).unwrap();
+        let parser = UserAgentParser::builder()
+            .filter_rules(|_, entry| entry.family_replacement() != Some("Pixel $1"))
+            .build(regex_file)
+            .expect("Parser creation failed");
+        assert_eq!(parser.parse_device(user_agent).family, "Other");
+        assert_eq!(parser.parse_os(user_agent).family, "Linux");
+    }
+
+    #[test]
+    fn tags() {
+        let regexes = "
+user_agent_parsers:
+  - regex: '(Shop)App'
+    tags: [commerce, apps]
+  - regex: '(Pay)App'
+    tags: [payments]
+  - regex: '(Firefox)'
+os_parsers: []
+device_parsers: []
+";
+        let build = |builder: UserAgentParserBuilder| {
+            let parser = builder
+                .build_from_bytes(regexes.as_bytes())
+                .expect("Parser creation failed");
+            ["ShopApp", "PayApp", "Firefox"]
+                .iter()
+                .map(|ua| parser.parse_user_agent(ua).family)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            build(UserAgentParser::builder().with_tags(vec!["apps", "payments"])),
+            ["Shop", "Pay", "Other"]
+        );
+        assert_eq!(
+            build(UserAgentParser::builder().without_tags(vec!["commerce"])),
+            ["Other", "Pay", "Firefox"]
+        );
+    }
+
+    #[test]
+    fn config() {
+        let dir =
+            std::env::temp_dir().join(format!("uaparser-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("regexes.yaml"), TEST_REGEXES).unwrap();
+        std::fs::write(
+            dir.join("uaparser.yaml"),
+            "dataset: regexes.yaml\ncategories: [user_agent, os]\ncache_size: 10\nfallback_family: Unknown\n",
+        )
+        .unwrap();
+
+        let config = ParserConfig::from_path(dir.join("uaparser.yaml")).unwrap();
+        assert_eq!(
+            config.categories,
+            Some(vec![Category::UserAgent, Category::Os])
+        );
+
+        let parser = config.build().expect("Parser creation failed");
+        let client =
+            parser.parse("Mozilla/5.0 (Linux; Android 10; Pixel 4) Firefox/68.0");
+        assert_eq!(client.user_agent.family, "Firefox");
+        assert_eq!(client.os.family, "Linux");
+        assert_eq!(client.device.family, "Unknown");
+        assert_eq!(parser.export().len(), 1);
+
+        std::fs::write(dir.join("invalid.yaml"), "datset: regexes.yaml").unwrap();
+        assert!(UserAgentParserBuilder::from_config(dir.join("invalid.yaml")).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn pipeline() {
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let pipeline = Pipeline::new(&parser)
+            .input(|ua| *ua = ua.replace("Fennec", "Firefox"))
+            .input(|ua| ua.push_str(" (Linux)"))
+            .output(|client| client.os.major = Some("5".to_owned()))
+            .output(|client| {
+                let major = client.os.major.take();
+                client.os.minor = major;
+            });
+
+        let client = pipeline.parse("Fennec/68.0");
+        assert_eq!(client.user_agent.family, "Firefox");
+        assert_eq!(client.os.family, "Linux");
+        assert_eq!(
+            (client.os.major, client.os.minor.as_deref()),
+            (None, Some("5"))
+        );
+        assert_eq!(pipeline.parse_os("Fennec/68.0").minor.as_deref(), Some("5"));
+
+        let inputs_only = Pipeline::new(&parser).input(|ua| ua.make_ascii_uppercase());
+        assert_eq!(inputs_only.parse_os("Linux").family, "Other");
+    }
+
+    #[test]
+    fn normalize_models() {
+        let regexes = r"
+user_agent_parsers: []
+os_parsers: []
+device_parsers:
+  - regex: 'Android [\d.]+; ([^)]+)\)'
+    brand_replacement: 'Samsung'
+    model_replacement: '$1'
+";
+        let parser = UserAgentParser::builder()
+            .normalize_models(true)
+            .build_from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+
+        for (user_agent, model) in &[
+            (
+                "Android 11; SM-G991B Build/RP1A.200720.012; wv)",
+                Some("SM-G991B"),
+            ),
+            (
+                "Android 4.4; en-us; samsung GT-I9505 Build/KOT49H)",
+                Some("GT-I9505"),
+            ),
+            ("Android 10; Samsung)", Some("Samsung")),
+            ("Android 9; de_DE; Build/PPR1)", None),
+        ] {
+            assert_eq!(parser.parse_device(user_agent).model.as_deref(), *model);
+        }
+    }
+
+    #[test]
+    fn confidence() {
+        let regexes = r"
+user_agent_parsers: []
+os_parsers: []
+device_parsers:
+  - regex: '; (SM-G991B)[;)]'
+    brand_replacement: Samsung
+  - regex: '(Android)'
+";
+        let parser = UserAgentParser::builder()
+            .match_info(true)
+            .build_from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+
+        let confidence = |user_agent| {
+            let info = parser.parse(user_agent).match_info.unwrap();
+            info.device.map(|device| device.confidence)
+        };
+        assert_eq!(confidence("Android 11; SM-G991B)"), Some(Confidence::High));
+        assert_eq!(confidence("Android 11; Pixel 5)"), Some(Confidence::Low));
+        assert_eq!(confidence("iPhone"), None);
+    }
+
+    #[test]
+    fn confidence_of_generic_rules() {
+        // Excerpts of the device rules of uap-core
+        let regexes = r"
+user_agent_parsers:
+  - regex: '(Chrome)/(\d+)\.(\d+)\.(\d+)'
+os_parsers:
+  - regex: '(Android)[ \-/](\d+)(?:\.(\d+)|)'
+device_parsers:
+  - regex: '; *(?:SAMSUNG |Samsung |)(SM-[A-Z0-9]+)(?: Build|\))'
+    device_replacement: 'Samsung $1'
+    brand_replacement: 'Samsung'
+    model_replacement: '$1'
+  - regex: 'Android[\- ][\d]+(?:\.[\d]+)*; (?:[A-Za-z]{2}[_\-][A-Za-z]{0,2}; |)([^;)]+?)(?: Build|\))'
+    device_replacement: '$1'
+    brand_replacement: 'Generic_Android'
+    model_replacement: '$1'
+  - regex: '(?:^|[^A-Za-z0-9])(Android|Mobile)'
+    device_replacement: 'Generic Smartphone'
+    brand_replacement: 'Generic'
+    model_replacement: 'Smartphone'
+";
+        let parser = UserAgentParser::builder()
+            .match_info(true)
+            .build_from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+        let match_info = |user_agent| parser.parse(user_agent).match_info.unwrap();
+
+        let info = match_info(
+            "Mozilla/5.0 (Linux; Android 13; SM-S901B) AppleWebKit/537.36 \
+             (KHTML, like Gecko) Chrome/112.0.0.0 Mobile Safari/537.36",
+        );
+        assert_eq!(info.device.unwrap().confidence, Confidence::High);
+        assert_eq!(info.os.unwrap().confidence, Confidence::Medium);
+
+        // The reduced user agent of Chrome hides the model behind `K`
+        let info = match_info(
+            "Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36 \
+             (KHTML, like Gecko) Chrome/114.0.0.0 Mobile Safari/537.36",
+        );
+        let device = info.device.unwrap();
+        assert_eq!(device.index, Some(1));
+        assert_eq!(device.confidence, Confidence::Low);
+
+        let info =
+            match_info("Mozilla/5.0 (Mobile; rv:48.0) Gecko/48.0 Firefox/48.0 KAIOS/2.5");
+        assert_eq!(info.device.unwrap().confidence, Confidence::Low);
+        assert!(info.user_agent.is_none());
+    }
+
+    #[test]
+    fn family_aliases() {
+        let aliases: FamilyAliases = serde_yaml::from_str(
+            "user_agent:\n  Firefox: Mozilla Firefox\ndevice:\n  Other: Unknown",
+        )
+        .unwrap();
+        let parser = UserAgentParser::builder()
+            .family_aliases(aliases.alias(Category::Os, "Linux", "GNU/Linux"))
+            .build_from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+
+        let client = parser.parse("Mozilla/5.0 (Linux) Firefox/68.0");
+        assert_eq!(client.user_agent.family, "Mozilla Firefox");
+        assert_eq!(client.os.family, "GNU/Linux");
+        assert_eq!(client.device.family, "Unknown");
+
+        let mut family = "Mac OS X".to_owned();
+        FamilyAliases::consolidated().apply(Category::Os, &mut family);
+        assert_eq!(family, "macOS");
+    }
+
+    #[test]
+    fn safari_from_webkit() {
+        let regexes = r"
+user_agent_parsers:
+  - regex: '(Version)/(\d+)\.(\d+).*Safari/'
+    family_replacement: Safari
+  - regex: '(iPhone|iPad).*AppleWebKit/'
+    family_replacement: 'Mobile Safari UI/WKWebView'
+os_parsers: []
+device_parsers: []
+";
+        let parser = UserAgentParser::builder()
+            .safari_from_webkit(true)
+            .build_from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+
+        let web_view = "Mozilla/5.0 (iPhone; CPU iPhone OS 9_3 like Mac OS X) \
+                        AppleWebKit/601.1.46 (KHTML, like Gecko) Mobile/13E233";
+        let ua = parser.parse_user_agent(web_view);
+        assert_eq!(
+            (ua.major.as_deref(), ua.minor.as_deref()),
+            (Some("9"), Some("0"))
+        );
+
+        let frozen = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) \
+                      AppleWebKit/605.1.15 (KHTML, like Gecko) Mobile/15E148";
+        assert_eq!(parser.parse_user_agent(frozen).major, None);
+
+        let stated =
+            "Mozilla/5.0 (Macintosh) AppleWebKit/601.5.17 Version/9.2 Safari/601.5.17";
+        assert_eq!(parser.parse_user_agent(stated).minor.as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn chromium_version() {
+        let parser = UserAgentParser::builder()
+            .detect_chromium_version(true)
+            .build_from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+
+        let samsung = "Mozilla/5.0 (Linux; Android 13; SM-S911B) AppleWebKit/537.36 \
+                       (KHTML, like Gecko) SamsungBrowser/23.0 Chrome/115.0.0.0 Mobile";
+        let ua = parser.parse_user_agent(samsung);
+        let default = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes()).unwrap();
+        assert_eq!(default.parse_user_agent(samsung).chromium_version, None);
+        assert_eq!(ua.chromium_version.as_deref(), Some("115.0.0.0"));
+
+        let firefox = parser.parse_user_agent("Mozilla/5.0 (Linux) Firefox/68.0");
+        assert_eq!(firefox.chromium_version, None);
+    }
+
+    #[test]
+    fn desktop_apps() {
+        let parser = UserAgentParser::builder()
+            .detect_apps(true)
+            .build_from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+
+        let slack = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 \
+                     (KHTML, like Gecko) Slack/4.36.140 Chrome/118.0.5993.159 \
+                     Electron/27.1.3 Safari/537.36 Sonic Slack_SSB/4.36.140";
+        let ua = parser.parse_user_agent(slack);
+        let app = ua.app.expect("app not detected");
+        assert_eq!(app.to_string(), "Slack 4.36.140");
+        let runtime = ua.runtime.expect("runtime not detected");
+        assert_eq!(
+            (runtime.name.as_str(), runtime.version.as_deref()),
+            ("Electron", Some("27.1.3"))
+        );
+
+        let chrome = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 \
+                      (KHTML, like Gecko) Chrome/118.0.0.0 Safari/537.36";
+        let ua = parser.parse_user_agent(chrome);
+        assert_eq!((ua.app, ua.runtime), (None, None));
+
+        let default = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes()).unwrap();
+        assert_eq!(default.parse_user_agent(slack).app, None);
+    }
+
+    #[test]
+    fn frameworks() {
+        let parser = UserAgentParser::builder()
+            .detect_frameworks(true)
+            .build_from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let framework = |user_agent| {
+            let ua = parser.parse_user_agent(user_agent);
+            ua.framework.map(|framework| framework.to_string())
+        };
+
+        assert_eq!(framework("okhttp/4.9.0").as_deref(), Some("okhttp 4.9.0"));
+        assert_eq!(
+            framework("Shop/3 CFNetwork/1404.0.5 Darwin/22.3.0").as_deref(),
+            Some("CFNetwork 1404.0.5")
+        );
+        assert_eq!(
+            framework("Shop/3 (com.shop; iOS 16.3.0) Alamofire/5.4.3").as_deref(),
+            Some("Alamofire 5.4.3")
+        );
+        assert_eq!(
+            framework("Dart/2.19 (dart:io)").as_deref(),
+            Some("Dart 2.19")
+        );
+        assert_eq!(framework("Mozilla/5.0 (Linux) Firefox/68.0"), None);
+
+        let default = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes()).unwrap();
+        assert_eq!(default.parse_user_agent("okhttp/4.9.0").framework, None);
+    }
+
+    #[test]
+    fn voice_assistants() {
+        let parser = UserAgentParser::builder()
+            .detect_voice_assistants(true)
+            .build_from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+
+        let echo = "AlexaMediaPlayer/2.1.4676.0 (Linux;Android 5.1.1) ExoPlayerLib/1.5.9";
+        let client = parser.parse(echo);
+        assert_eq!(client.device.family, "Amazon Echo");
+        assert_eq!(client.device.brand.as_deref(), Some("Amazon"));
+        assert_eq!(client.device_type(), DeviceType::SmartSpeaker);
+        assert_eq!(client.voice_platform(), Some(VoicePlatform::Alexa));
+
+        let homepod =
+            "AppleCoreMedia/1.0.0.17E262 (HomePod; U; CPU OS 13_4 like Mac OS X)";
+        let client = parser.parse(homepod);
+        assert_eq!(client.voice_platform(), Some(VoicePlatform::Siri));
+
+        let client = parser.parse("Mozilla/5.0 (Linux) Firefox/68.0");
+        assert_eq!(client.voice_platform(), None);
+    }
+
+    #[test]
+    fn feature_phones() {
+        let parser = UserAgentParser::builder()
+            .feature_phones(true)
+            .build_from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+
+        let nokia = "Mozilla/5.0 (Mobile; Nokia_8110_4G; rv:48.0) Gecko/48.0 \
+                     Firefox/48.0 KAIOS/2.5";
+        let client = parser.parse(nokia);
+        assert_eq!(client.device.family, "Nokia 8110");
+        assert_eq!(client.os.family, "KaiOS");
+        assert_eq!(client.os.major.as_deref(), Some("2"));
+        assert_eq!(client.device_type(), DeviceType::FeaturePhone);
+
+        let j2me =
+            "Opera/9.80 (J2ME/MIDP; Opera Mini/4.2.14912/870; U; id) Presto/2.4.15";
+        let client = parser.parse(j2me);
+        assert_eq!(client.device.family, "Generic Feature Phone");
+        assert_eq!(client.device_type(), DeviceType::FeaturePhone);
+    }
+
+    #[test]
+    fn anonymize_log() {
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let log: &[u8] = b"ip\tuser_agent\r\n\
+            10.0.0.1\tMozilla/5.0 (X11; Linux x86_64) Firefox/70.1\r\n\
+            \r\n\
+            10.0.0.2\t\xffFirefox/70.1\n\
+            10.0.0.3\t\"curl/7.64.1\"";
+
+        let mut output = Vec::new();
+        let rewritten = super::anonymize_log(&parser, log, &mut output, 1, '\t', 1)
+            .expect("log is readable");
+        assert_eq!(rewritten, 2);
+        assert_eq!(
+            output,
+            &b"ip\tuser_agent\r\n\
+            10.0.0.1\tFirefox 70; Linux; desktop\r\n\
+            \r\n\
+            10.0.0.2\t\xffFirefox/70.1\n\
+            10.0.0.3\t\"Other; Other; unknown\""[..]
+        );
+    }
+
+    #[test]
+    fn link_previews() {
+        let parser = LinkPreviewParser::default();
+
+        let slack = "Slackbot-LinkExpanding 1.0 (+https://api.slack.com/robots)";
+        let preview = parser.parse(slack).expect("link preview not detected");
+        assert_eq!(preview.operator, "Slack");
+        assert_eq!(preview.version.as_deref(), Some("1.0"));
+
+        let telegram = parser.parse("TelegramBot (like TwitterBot)").unwrap();
+        assert_eq!(telegram.name, "TelegramBot");
+
+        let facebook =
+            "facebookexternalhit/1.1 (+http://www.facebook.com/externalhit_uatext.php)";
+        assert_eq!(parser.parse(facebook).unwrap().operator, "Meta");
+        assert!(AiCrawlerParser::default().parse(slack).is_none());
+        assert!(AiCrawlerParser::default().parse(facebook).is_none());
+
+        let gptbot = "Mozilla/5.0 (compatible; GPTBot/1.2; +https://openai.com/gptbot)";
+        assert!(parser.parse(gptbot).is_none());
+    }
+
+    #[test]
+    fn minimize() {
+        let dataset = r"
+user_agent_parsers:
+  - regex: '(Chrome)/(\d+)'
+  - regex: '(Firefox)/(\d+)\.(\d+)'
+  - regex: '(Opera)/(\d+)'
+os_parsers: []
+device_parsers: []
+";
+        let parser = UserAgentParser::from_bytes(dataset.as_bytes())
+            .expect("Parser creation failed");
+        let corpus = [
+            "Mozilla/5.0 (Linux) Firefox/68.0",
+            "Mozilla/5.0 (Linux) Firefox/70.0",
+            "Mozilla/5.0 (X11; Linux x86_64) Chrome/118.0.0.0 Safari/537.36",
+        ];
+        let hits = parser.rule_hits(corpus.iter().copied());
+
+        assert_eq!(hits.get(Category::UserAgent), [1, 2]);
+
+        let regex_file = RegexFile::from_bytes(dataset.as_bytes()).unwrap();
+        let reduced = hits.minimize(regex_file, 0.5);
+        assert_eq!(reduced.user_agent_parsers.len(), 1);
+
+        let yaml = reduced.to_yaml().unwrap();
+        let parser = UserAgentParser::from_bytes(yaml.as_bytes()).unwrap();
+        assert_eq!(parser.parse_user_agent(corpus[0]).family, "Firefox");
+    }
+
+    #[test]
+    fn minimize_user_agent() {
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+
+        let user_agent =
+            "Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Gecko/20100101 Firefox/70.0";
+        let minimal = super::minimize_user_agent(&parser, user_agent);
+        // `x86_64` is kept as a whole token for the architecture
+        assert_eq!(minimal, "Linux x86_64 Firefox/70.0");
+        assert_eq!(super::minimize_user_agent(&parser, ""), "");
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+
+        // A fixed stream of pseudo-random bytes, so failures reproduce
+        let mut state = 0x2545_f491_u32;
+        let bytes: Vec<u8> = (0..64 * 1024)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+
+        let mut u = Unstructured::new(&bytes);
+        for _ in 0..500 {
+            let user_agent = UserAgentString::arbitrary(&mut u).unwrap();
+            let client = parser.parse(user_agent.as_str());
+            assert_eq!(client, parser.parse(user_agent.as_str()));
+            assert!(!client.user_agent.family.is_empty());
+
+            let client = Client::arbitrary(&mut u).unwrap();
+            assert_eq!(client.match_info, None);
+        }
+    }
+
+    #[cfg(feature = "proptest")]
+    #[test]
+    fn strategy() {
+        use proptest::test_runner::{Config, TestRunner};
+
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let mut runner = TestRunner::new(Config::with_cases(200));
+
+        runner
+            .run(&super::strategy::user_agent(), |user_agent| {
+                let client = parser.parse(&user_agent);
+                assert!(!client.user_agent.family.is_empty());
+                Ok(())
+            })
+            .unwrap();
+        runner
+            .run(&super::strategy::client(), |client| {
+                let fields = (client.flat_fields().into_iter())
+                    .map(|(name, value)| (name.to_owned(), value.map(String::from)))
+                    .collect();
+                assert_eq!(Client::from_flat(fields), client);
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "async-graphql")]
+    fn graphql() {
+        use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+
+        struct Query;
+
+        #[Object]
+        impl Query {
+            async fn client(&self) -> Client {
+                Client::default()
+            }
+        }
+
+        let sdl = Schema::new(Query, EmptyMutation, EmptySubscription).sdl();
+        assert!(sdl.contains("type Client {"));
+        assert!(sdl.contains("userAgent: UserAgent!"));
+        assert!(sdl.contains("arch: Arch"));
+        assert!(sdl.contains("runtime: Product"));
+        assert!(!sdl.contains("matchInfo"));
+    }
+
+    #[test]
+    fn registry() {
+        let build = |family: &str| {
+            let dataset = format!(
+                "user_agent_parsers:\n  - regex: 'Firefox'\n    family_replacement: {}\n\
+                 os_parsers: []\ndevice_parsers: []\n",
+                family
+            );
+            UserAgentParser::from_bytes(dataset.as_bytes())
+        };
+        let user_agent = "Mozilla/5.0 (Linux) Firefox/68.0";
+
+        let registry = ParserRegistry::new().cache(10);
+        let shared = std::sync::Arc::new(build("Shared").unwrap());
+        registry.insert("eu", shared.clone());
+        registry.insert("us", shared);
+        registry.reload("apac", || build("Apac")).unwrap();
+
+        let family = |tenant| {
+            registry
+                .tenant(tenant)
+                .unwrap()
+                .parse(user_agent)
+                .user_agent
+                .family
+        };
+        assert_eq!(family("eu"), "Shared");
+        assert_eq!(family("us"), "Shared");
+        assert_eq!(family("apac"), "Apac");
+
+        let before = registry.tenant("eu").unwrap();
+        registry.reload("eu", || build("Reloaded")).unwrap();
+        assert_eq!(family("eu"), "Reloaded");
+        assert_eq!(before.parse(user_agent).user_agent.family, "Shared");
+        assert_eq!(family("us"), "Shared");
+
+        let apac = registry.tenant("apac").unwrap();
+        registry.reload("apac", || build("Apac")).unwrap();
+        let cached = registry.cached();
+        assert_eq!(apac.parse("Firefox/70.0").user_agent.family, "Apac");
+        assert_eq!(registry.cached(), cached);
+
+        let broken = || UserAgentParser::from_bytes(b"user_agent_parsers: [");
+        assert!(registry.reload("us", broken).is_err());
+        assert_eq!(family("us"), "Shared");
+        assert!(registry.remove("apac").is_some());
+        assert!(registry.tenant("apac").is_none());
+
+        let mut tenants = registry.tenants();
+        tenants.sort();
+        assert_eq!(tenants, ["eu", "us"]);
+    }
+
+    #[test]
+    fn cached_parser() {
+        use std::time::Duration;
+
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let firefox = "Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Firefox/70.0";
+        let curl = "curl/7.64.1";
+        let wget = "Wget/1.20.3";
+
+        let parser = CachedParser::new(&parser, LruCache::new(2));
+        let client = parser.parse(firefox);
+        assert_eq!(parser.cache().get(firefox), Some(client.clone()));
+        assert_eq!(parser.parse_os(firefox), client.os);
+
+        parser.parse(curl);
+        parser.cache().get(firefox);
+        parser.parse(wget);
+        assert_eq!(parser.cache().len(), 2);
+        assert!(parser.cache().get(curl).is_none());
+        assert!(parser.cache().get(firefox).is_some());
+
+        let cache = LruCache::new(10);
+        cache.put(curl, &client, Some(Duration::ZERO));
+        assert!(cache.get(curl).is_none());
+        assert!(cache.is_empty());
+
+        let parser = UserAgentParser::builder()
+            .match_info(true)
+            .build_from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let parser = CachedParser::new(parser, LruCache::new(10));
+        let miss = parser.parse(firefox);
+        assert!(miss.match_info.is_none());
+        assert_eq!(parser.parse(firefox), miss);
+    }
+
+    #[test]
+    fn computed_rules() {
+        let parser = UserAgentParser::builder()
+            .match_info(true)
+            .os_rule(r"Windows NT (\d+)\.(\d+)", |captures| OS {
+                family: "Windows".to_owned(),
+                major: captures.get(1).map(|v| format!("{:0>2}", v)),
+                minor: captures.get(2).map(str::to_owned),
+                ..OS::default()
+            })
+            .user_agent_rule(r"Firefox/(\d+)", |captures| UserAgent {
+                family: "Custom Firefox".to_owned(),
+                major: captures.get(1).map(str::to_owned),
+                ..UserAgent::default()
+            })
+            .build_from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+
+        let client = parser
+            .parse("Mozilla/5.0 (Windows NT 6.1; rv:70.0) Gecko/20100101 Firefox/70.0");
+        assert_eq!(client.os.family, "Windows");
+        assert_eq!(client.os.major.as_deref(), Some("06"));
+        assert_eq!(client.user_agent.family, "Custom Firefox");
+        assert_eq!(client.user_agent.major.as_deref(), Some("70"));
+
+        let match_info = client.match_info.unwrap();
+        assert_eq!(match_info.user_agent.unwrap().index, None);
+
+        // Rules of the dataset keep their index after the computed ones
+        let user_agent = "Mozilla/5.0 (X11; Linux x86_64) Firefox/70.0";
+        let rule = parser.parse(user_agent).match_info.unwrap().os.unwrap();
+        assert_eq!(rule.index, Some(0));
+        let hits = parser.rule_hits(vec![user_agent]);
+        assert_eq!((hits.user_agent.len(), hits.os.as_slice()), (0, &[1][..]));
+
+        let invalid = UserAgentParser::builder()
+            .device_rule("(", |_| Device::default())
+            .build_from_bytes(TEST_REGEXES.as_bytes());
+        if cfg!(feature = "lazy") {
+            // Lazily compiled rules that fail to compile never match
+            let parser = invalid.expect("Parser creation failed");
+            assert_eq!(parser.parse_device("Pixel 4").family, "Pixel 4");
+        } else {
+            match invalid {
+                Err(Error::Rule(rule)) => {
+                    assert_eq!((rule.category, rule.index), (Category::Device, 0))
+                }
+                _ => panic!("the invalid rule was accepted"),
+            }
+        }
+    }
+
+    #[test]
+    fn translate_pcre() {
+        let dataset = r#"
+user_agent_parsers:
+  - regex: '(Foo)\!(\d++)(?J)[\/\]]'
+  - regex: '(Bar)/(\d+)\Z'
+  - regex: '(Baz)\h(\d+)'
+  - regex: '(Qux)/[\d.]++5'
+  - regex: '(Qux)'
+    family_replacement: 'Other Qux'
+os_parsers: []
+device_parsers: []
+"#;
+        let parser = UserAgentParser::builder()
+            .translate_pcre(true)
+            .regex_rewrite(r"\h", " ")
+            .build_from_bytes(dataset.as_bytes())
+            .expect("Parser creation failed");
+
+        let translations = parser.translations();
+        assert_eq!(translations.len(), 4);
+        assert_eq!(translations[0].original, r"(Foo)\!(\d++)(?J)[\/\]]");
+        assert_eq!(translations[0].translated, r"(Foo)!((?>\d+))[/\]]");
+        assert_eq!(
+            translations[0].rewrites,
+            [
+                Rewrite::Escape('!'),
+                Rewrite::Possessive,
+                Rewrite::InlineFlag('J'),
+                Rewrite::Escape('/'),
+            ]
+        );
+        assert_eq!(translations[1].rewrites, [Rewrite::EndAnchor]);
+        assert_eq!(translations[2].translated, r"(Baz) (\d+)");
+
+        let user_agent = parser.parse_user_agent("Foo!12/");
+        assert_eq!(user_agent.family, "Foo");
+        assert_eq!(user_agent.major.as_deref(), Some("12"));
+        assert_eq!(parser.parse_user_agent("Baz 3").family, "Baz");
+
+        // Unlike `[\d.]+5`, the possessive quantifier doesn't give back the `5`
+        assert_eq!(translations[3].translated, r"(Qux)/(?>[\d.]+)5");
+        assert_eq!(parser.parse_user_agent("Qux/1.5").family, "Other Qux");
+
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes()).unwrap();
+        assert!(parser.translations().is_empty());
+    }
+
+    #[test]
+    fn cache_key_normalization() {
+        let regexes = r#"
+user_agent_parsers:
+  - regex: '(Firefox)/(\d+)'
+  - regex: '; wv\).*(Chrome)/(\d+)'
+    family_replacement: 'Chrome Mobile WebView'
+  - regex: '(Chrome)/(\d+)'
+os_parsers: []
+device_parsers:
+  - regex: 'Build/(\w+)'
+"#;
+        let parser = UserAgentParser::from_bytes(regexes.as_bytes()).unwrap();
+        let parser = CachedParser::new(&parser, LruCache::new(10))
+            .normalize_keys(KeyNormalizer::new().build_hashes().locales());
+
+        let german = "Mozilla/5.0 (X11; de-DE) Firefox/70.0";
+        let english = "Mozilla/5.0 (X11; en-US) Firefox/70.0";
+        assert_eq!(parser.parse(german).user_agent.family, "Firefox");
+        assert!(parser
+            .cache()
+            .get("Mozilla/5.0 (X11) Firefox/70.0")
+            .is_some());
+        assert_eq!(parser.parse(english), parser.parse(german));
+        assert_eq!(parser.cache().len(), 1);
+
+        // `wv` isn't a locale, but marks a WebView
+        let chrome = "Mozilla/5.0 (Linux; Android 10; K) Chrome/120.0";
+        let webview = "Mozilla/5.0 (Linux; Android 10; K; wv) Chrome/120.0";
+        assert_eq!(parser.parse(chrome).user_agent.family, "Chrome");
+        assert_eq!(
+            parser.parse(webview).user_agent.family,
+            "Chrome Mobile WebView"
+        );
+
+        // Stripping the hash would lose the captured build, so the user agent
+        // is cached as is
+        let build = "Mozilla/5.0 (Linux) Build/3f9a2c1e7b";
+        assert_eq!(parser.parse(build).device.family, "3f9a2c1e7b");
+        assert!(parser.cache().get(build).is_some());
+        assert_eq!(parser.parse(build).device.family, "3f9a2c1e7b");
+        assert_eq!(
+            parser
+                .parse("Mozilla/5.0 (Linux) Build/0123abcdef")
+                .device
+                .family,
+            "0123abcdef"
+        );
+    }
+
+    #[test]
+    fn cache_key_verification() {
+        let regexes = r#"
+user_agent_parsers:
+  - regex: '(Firefox)/(\d+)'
+os_parsers: []
+device_parsers:
+  - regex: '; zh-(TW|HK)\)'
+    device_replacement: 'Traditional Chinese $1'
+"#;
+        let parser = UserAgentParser::from_bytes(regexes.as_bytes()).unwrap();
+        let cached = CachedParser::new(&parser, LruCache::new(10))
+            .normalize_keys(KeyNormalizer::new().build_hashes().locales());
+
+        // Both share a key with locales stripped, but only the second matches
+        // the device rule
+        let english = "Mozilla/5.0 (X11; en-US) Firefox/70.0";
+        let chinese = "Mozilla/5.0 (X11; zh-TW) Firefox/70.0";
+        assert_eq!(cached.parse(english).device.family, "Other");
+        assert_eq!(
+            cached.parse(chinese).device.family,
+            "Traditional Chinese TW"
+        );
+        assert_eq!(cached.cache().len(), 2);
+
+        // Build hashes are still stripped, since no rule looks at them
+        let build = "Mozilla/5.0 (X11; en-US) Firefox/70.0 Build/3f9a2c1e7b";
+        assert_eq!(cached.parse(build), parser.parse(build));
+        assert!(cached
+            .cache()
+            .get("Mozilla/5.0 (X11; en-US) Firefox/70.0 Build/")
+            .is_some());
+    }
+
+    #[test]
+    fn profile() {
+        let parser = UserAgentParser::builder()
+            .profile(1)
+            .build_from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        assert!(UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .unwrap()
+            .profile_report()
+            .is_none());
+
+        parser.parse("Mozilla/5.0 (Linux) Firefox/68.0");
+        parser.parse("Mozilla/5.0 (Linux) Chrome/118.0.0.0");
+
+        let report = parser.profile_report().unwrap();
+        let firefox = (report.rules.iter())
+            .find(|rule| rule.category == Category::UserAgent)
+            .unwrap();
+        assert_eq!(
+            (firefox.index, firefox.evaluations, firefox.hits),
+            (Some(0), 2, 1)
+        );
+        assert_eq!(report.slowest(1).len(), 1);
+    }
+
+    #[cfg(feature = "caniuse")]
+    const CANIUSE_DATA: &str = r#"{"agents": {
+        "firefox": {"version_list": [
+            {"version": "68", "global_usage": 0.2, "release_date": 1},
+            {"version": "69", "global_usage": 0.1, "release_date": 2},
+            {"version": "70", "global_usage": 0.8, "release_date": 3},
+            {"version": "71", "global_usage": 0, "release_date": null}
+        ]},
+        "ie": {"version_list": [
+            {"version": "11", "global_usage": 0.6, "release_date": 1}
+        ]},
+        "ios_saf": {"version_list": [
+            {"version": "16.6-16.7", "global_usage": 1.2, "release_date": 1}
+        ]}
+    }}"#;
+
+    #[cfg(feature = "browserslist")]
+    #[test]
+    fn browserslist() {
+        let data = CaniuseData::from_json(CANIUSE_DATA.as_bytes()).unwrap();
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let firefox = |version: &str| parser.parse(&format!("Firefox/{}.0", version));
+
+        let defaults = Browserslist::new("defaults", &data).unwrap();
+        let browsers: Vec<_> = defaults.browsers().collect();
+        assert_eq!(
+            browsers,
+            [
+                ("firefox", "69"),
+                ("firefox", "70"),
+                ("ios_saf", "16.6-16.7")
+            ]
+        );
+        assert!(defaults.matches(&firefox("70")));
+        assert!(!defaults.matches(&firefox("68")));
+
+        let range = Browserslist::new("Firefox >= 69 and not Firefox 70", &data).unwrap();
+        assert!(range.matches(&firefox("69")));
+        assert!(!range.matches(&firefox("70")));
+
+        assert!(matches!(
+            Browserslist::new("Netscape 4", &data),
+            Err(QueryError::UnknownBrowser(_))
+        ));
+        assert!(matches!(
+            Browserslist::new("> 5% in US", &data),
+            Err(QueryError::Unsupported(_))
+        ));
+
+        let browsers = |query: &str| {
+            let query = Browserslist::new(query, &data).unwrap();
+            let browsers: Vec<_> = (query.browsers())
+                .map(|(agent, release)| format!("{} {}", agent, release))
+                .collect();
+            browsers.join(", ")
+        };
+        assert_eq!(browsers("cover 2%"), "firefox 70, ios_saf 16.6-16.7");
+        assert_eq!(browsers(">0.5%"), "firefox 70, ie 11, ios_saf 16.6-16.7");
+        assert_eq!(browsers("unreleased versions"), "firefox 71");
+        assert_eq!(browsers("Firefox 68-69"), "firefox 68, firefox 69");
+        assert_eq!(
+            browsers("last 2 ff major versions"),
+            "firefox 69, firefox 70"
+        );
+        assert_eq!(browsers("since 1970-01-01 and ie all"), "ie 11");
+        assert_eq!(browsers("since 2020"), "");
+
+        #[cfg(feature = "caniuse-bundled")]
+        {
+            let defaults = Browserslist::bundled("defaults").unwrap();
+            assert!(defaults.matches(&firefox("131")));
+            assert!(defaults.matches(&firefox("128")));
+            assert!(!defaults.matches(&firefox("60")));
+            let webp = Browserslist::bundled("fully supports webp").unwrap();
+            assert!(webp.matches(&firefox("65")));
+            assert!(!webp.matches(&firefox("64")));
+        }
+    }
+
+    #[cfg(feature = "caniuse")]
+    #[test]
+    fn caniuse() {
+        let mut data: serde_json::Value = serde_json::from_str(CANIUSE_DATA).unwrap();
+        data["data"] = serde_json::json!({
+            "webp": {"stats": {
+                "firefox": {"68": "n", "69": "a #1", "70": "y #2"},
+                "chrome": {"100": "y"}
+            }}
+        });
+        let data = CaniuseData::from_json(&serde_json::to_vec(&data).unwrap()).unwrap();
+
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let firefox = |version: &str| parser.parse(&format!("Firefox/{}.0", version));
+        assert_eq!(data.supports(&firefox("68"), "webp"), Some(false));
+        assert_eq!(data.supports(&firefox("69"), "webp"), Some(false));
+        assert_eq!(data.supports(&firefox("70"), "webp"), Some(true));
+        assert_eq!(data.supports(&firefox("70"), "avif"), None);
+        assert_eq!(data.supports(&firefox("99"), "webp"), None);
+        assert_eq!(data.usage(&firefox("70")), Some(0.8));
+
+        assert_eq!(firefox("70").supports("webp", &data), Some(true));
+
+        let electron = parser.parse("Slack/4.0 Chrome/100.0.4896.60 Electron/18.0.1");
+        assert_eq!(data.supports(&electron, "webp"), None);
+    }
+
+    #[test]
+    fn include() {
+        let dir = std::env::temp_dir().join(format!("uaparser-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("vendors")).unwrap();
+        std::fs::write(
+            dir.join("vendors/shop.yaml"),
+            "user_agent_parsers:\n  - regex: 'ShopApp'\n    family_replacement: Shop Vendor\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("regexes.yaml"),
+            "include: [vendors/shop.yaml]\nuser_agent_parsers:\n  - regex: '(\\w+)App'\n\
+             os_parsers: []\ndevice_parsers: []\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("cycle.yaml"), "include: [cycle.yaml]\n").unwrap();
+
+        let parser =
+            UserAgentParser::from_yaml(dir.join("regexes.yaml").to_str().unwrap())
+                .expect("Parser creation failed");
+        let cycle = UserAgentParser::from_yaml(dir.join("cycle.yaml").to_str().unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(parser.parse_user_agent("ShopApp").family, "Shop Vendor");
+        assert_eq!(parser.parse_user_agent("PayApp").family, "Pay");
+        assert!(cycle.is_err());
+    }
+
+    #[test]
+    fn policies() {
+        let engine = PolicyEngine::from_bytes(
+            br#"
+policies:
+  - name: crawlers
+    action: block
+    when: { bot: true, not: { user_agent: [Googlebot, Bingbot] } }
+  - name: modern-firefox
+    action: allow
+    when: { user_agent: Firefox, version_at_least: "68.0.1" }
+"#,
+        )
+        .expect("Policy creation failed");
+
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let mut client = parser.parse("Mozilla/5.0 (X11; Linux x86_64) Firefox/68.1");
+        assert_eq!(engine.action(&client), Some(PolicyAction::Allow));
+
+        client.user_agent.minor = Some("0".to_owned());
+        assert_eq!(engine.action(&client), None);
+
+        client.device.family = "Spider".to_owned();
+        assert_eq!(engine.action(&client), Some(PolicyAction::Block));
+
+        client.user_agent.family = "Googlebot".to_owned();
+        assert!(engine.evaluate(&client).is_empty());
+    }
+
+    #[cfg(feature = "disk-cache")]
+    #[test]
+    fn disk_cache() {
+        let path =
+            std::env::temp_dir().join(format!("uaparser-{}.jsonl", std::process::id()));
+        let open_with = |builder: UserAgentParserBuilder, regexes: &str| {
+            let checksum = builder.checksum(regexes.as_bytes());
+            let parser = builder
+                .build_from_bytes(regexes.as_bytes())
+                .expect("Parser creation failed");
+            let cache =
+                DiskCache::open(&path, checksum, 2).expect("Cache creation failed");
+            CachedParser::new(parser, cache)
+        };
+        let open = |regexes: &str| open_with(UserAgentParser::builder(), regexes);
+        let user_agent = "Mozilla/5.0 (X11; Linux x86_64) Firefox/68.0";
+
+        let parser = open(TEST_REGEXES);
+        assert_eq!(parser.parse(user_agent).user_agent.family, "Firefox");
+        drop(parser);
+
+        let parser = open(TEST_REGEXES);
+        assert_eq!(parser.cache().len(), 1);
+        assert_eq!(parser.parse_os(user_agent).family, "Linux");
+        drop(parser);
+
+        let parser = open_with(
+            UserAgentParser::builder().fallback_family("?"),
+            TEST_REGEXES,
+        );
+        assert!(parser.cache().is_empty());
+        drop(parser);
+
+        // The oldest entries are evicted one by one, also from the file
+        let parser = open(TEST_REGEXES);
+        for user_agent in &["Firefox/1.0", "Firefox/2.0", "Firefox/3.0", "Firefox/4.0"] {
+            parser.parse(user_agent);
+        }
+        assert_eq!(parser.cache().len(), 2);
+        assert!(parser.cache().get("Firefox/2.0").is_none());
+        assert!(parser.cache().get("Firefox/4.0").is_some());
+        drop(parser);
+
+        let parser = open(TEST_REGEXES);
+        assert_eq!(parser.cache().len(), 2);
+        assert!(parser.cache().get("Firefox/3.0").is_some());
+        drop(parser);
+
+        let cache = DiskCache::open(&path, 0, 0).expect("Cache creation failed");
+        cache.put(user_agent, &Client::default(), None);
+        assert!(cache.is_empty());
+        drop(cache);
+
+        let parser = open("user_agent_parsers: []\nos_parsers: []\ndevice_parsers: []");
+        assert!(parser.cache().is_empty());
+        drop(parser);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn self_test() {
+        let dataset = r#"
+user_agent_parsers:
+  - regex: 'Googlebot'
+    family_replacement: 'Googlebot'
+  - regex: 'Android.*Chrome/'
+    family_replacement: 'Chrome Mobile'
+  - regex: '(Chrome)/'
+  - regex: 'iPhone.*Safari/'
+    family_replacement: 'Mobile Safari'
+  - regex: '(Firefox)/'
+  - regex: 'Version/.*(Safari)/'
+os_parsers:
+  - regex: 'Windows NT'
+    os_replacement: 'Windows'
+  - regex: 'iPhone OS'
+    os_replacement: 'iOS'
+  - regex: '(Android) '
+  - regex: 'Intel (Mac OS X)'
+  - regex: '(Ubuntu)'
+device_parsers:
+  - regex: 'Googlebot'
+    device_replacement: 'Spider'
+  - regex: '(iPhone)'
+  - regex: 'Android \d+; (SM-\w+)'
+    device_replacement: 'Samsung $1'
+  - regex: 'Macintosh'
+    device_replacement: 'Mac'
+"#;
+        let parser = UserAgentParser::from_bytes(dataset.as_bytes())
+            .expect("Parser creation failed");
+        let report = parser.self_test();
+        assert!(
+            report.passed(),
+            "{:?}",
+            report.failures().collect::<Vec<_>>()
+        );
+        assert_eq!(report.checks.len(), 15);
+
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let report = parser.self_test();
+        assert!(!report.passed());
+        let failure = report.failures().next().unwrap();
+        assert_ne!(failure.expected, failure.actual);
+    }
+
+    #[test]
+    fn anomalies() {
+        let regexes = r"
+user_agent_parsers:
+  - regex: '(Edge)/(\d+)'
+  - regex: '(Chrome)/(\d+)'
+  - regex: '(Firefox)/(\d+)'
+  - regex: '(MSIE) (\d+)'
+    family_replacement: 'IE'
+os_parsers:
+  - regex: 'Windows NT'
+    os_replacement: 'Windows'
+  - regex: '(Android)'
+  - regex: 'iPhone OS'
+    os_replacement: 'iOS'
+device_parsers:
+  - regex: '(iPhone)'
+";
+        let parser = UserAgentParser::from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+        let kinds = |user_agent: &str| {
+            (parser.parse(user_agent).anomalies(user_agent).into_iter())
+                .map(|anomaly| anomaly.kind)
+                .collect::<Vec<_>>()
+        };
+
+        let chrome = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
+                      (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+        assert_eq!(kinds(chrome), vec![]);
+        assert_eq!(
+            kinds(&chrome.replace("537.36 (KHTML", "605.1.15 (KHTML")),
+            vec![AnomalyKind::EngineMismatch]
+        );
+        assert_eq!(
+            kinds(
+                "Mozilla/5.0 (Windows NT 10.0; rv:120.0) AppleWebKit/537.36 Firefox/120.0"
+            ),
+            vec![AnomalyKind::EngineMismatch]
+        );
+        assert_eq!(
+            kinds("Mozilla/5.0 (compatible; MSIE 13.0; Windows NT 10.0)"),
+            vec![AnomalyKind::ImpossibleVersion]
+        );
+        assert_eq!(
+            kinds("Mozilla/5.0 (iPhone; CPU iPhone OS 16_0 like Mac OS X; Android 12)"),
+            vec![
+                AnomalyKind::ConflictingPlatforms,
+                AnomalyKind::PlatformMismatch
+            ]
+        );
+
+        // Windows Phone claims other platforms for compatibility
+        assert_eq!(
+            kinds(
+                "Mozilla/5.0 (Windows Phone 10.0; Android 6.0.1; Microsoft; Lumia 950) \
+                 AppleWebKit/537.36 (KHTML, like Gecko) Chrome/52.0.2743.116 \
+                 Mobile Safari/537.36 Edge/15.15063"
+            ),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn openrtb_device() {
+        let regexes = r"
+user_agent_parsers: []
+os_parsers:
+  - regex: '(Android) (\d+)'
+  - regex: 'Windows NT 10\.0'
+    os_replacement: 'Windows'
+    os_v1_replacement: '10'
+device_parsers:
+  - regex: '; (SM-[A-Z0-9]+)\)'
+    device_replacement: 'Samsung $1'
+    brand_replacement: 'Samsung'
+    model_replacement: '$1'
+";
+        let parser = UserAgentParser::from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+
+        let user_agent = "Mozilla/5.0 (Linux; Android 13; SM-S901B) AppleWebKit/537.36";
+        let device = parser.parse(user_agent).to_openrtb_device(user_agent, None);
+        assert_eq!(
+            device,
+            OpenRtbDevice {
+                ua: user_agent.to_owned(),
+                devicetype: Some(4),
+                make: Some("Samsung".to_owned()),
+                model: Some("SM-S901B".to_owned()),
+                os: Some("Android".to_owned()),
+                osv: Some("13".to_owned()),
+            }
+        );
+
+        // Client hints take precedence over the reduced user agent
+        let user_agent = "Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36";
+        let hints = OpenRtbHints {
+            model: Some("SM-S918B".to_owned()),
+            platform_version: Some("14.0.0".to_owned()),
+            ..OpenRtbHints::default()
+        };
+        let device = parser
+            .parse(user_agent)
+            .to_openrtb_device(user_agent, Some(&hints));
+        assert_eq!(device.model.as_deref(), Some("SM-S918B"));
+        assert_eq!(device.osv.as_deref(), Some("14.0.0"));
+        assert_eq!(device.make, None);
+
+        let user_agent = "Mozilla/5.0 (Windows NT 10.0; Win64; x64)";
+        let device = parser.parse(user_agent).to_openrtb_device(user_agent, None);
+        assert_eq!(
+            (
+                device.devicetype,
+                device.os.as_deref(),
+                device.osv.as_deref()
+            ),
+            (Some(2), Some("Windows"), Some("10"))
+        );
+
+        let hints = OpenRtbHints {
+            mobile: Some(true),
+            ..OpenRtbHints::default()
+        };
+        let device = parser
+            .parse("curl/8.0")
+            .to_openrtb_device("curl/8.0", Some(&hints));
+        assert_eq!((device.devicetype, device.os.as_deref()), (Some(1), None));
+
+        let parser = UserAgentParser::builder()
+            .fallback_family("Unknown")
+            .match_info(true)
+            .build_from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+        let client = parser.parse("curl/8.0");
+        assert_eq!(client.os.family, "Unknown");
+        assert_eq!(client.to_openrtb_device("curl/8.0", None).os, None);
+        assert!(!client
+            .otel_attributes(None)
+            .iter()
+            .any(|(key, _)| *key == "os.name"));
+
+        #[cfg(feature = "json")]
+        assert_eq!(
+            serde_json::to_value(&device).unwrap(),
+            serde_json::json!({ "ua": "curl/8.0", "devicetype": 1 })
+        );
+    }
+
+    #[cfg(feature = "referer")]
+    #[test]
+    fn referer() {
+        // An excerpt of the Snowplow `referers.yml`
+        let referers = r"
+search:
+  Google:
+    parameters:
+      - q
+    domains:
+      - google.com
+      - google.co.uk
+  Yahoo!:
+    parameters:
+      - p
+    domains:
+      - search.yahoo.com
+social:
+  Facebook:
+    domains:
+      - facebook.com
+  Google+:
+    domains:
+      - plus.google.com
+email:
+  Gmail:
+    domains:
+      - mail.google.com
+";
+        let parser = RefererParser::from_bytes(referers.as_bytes())
+            .unwrap()
+            .with_internal_domains(["example.com"]);
+        let parse = |url| parser.parse(url, Some("https://shop.example.org/cart"));
+
+        assert_eq!(
+            parse("https://www.google.co.uk/search?q=running+shoes&hl=en"),
+            Some(Referer {
+                medium: Medium::Search,
+                source: Some("Google".to_owned()),
+                term: Some("running shoes".to_owned()),
+            })
+        );
+        assert_eq!(
+            parse("https://uk.search.yahoo.com/search?p=").map(|r| r.term),
+            Some(None)
+        );
+
+        // The most specific domain wins
+        let referer = parse("https://mail.google.com/mail/u/0/").unwrap();
+        assert_eq!(
+            (referer.medium, referer.source.as_deref()),
+            (Medium::Email, Some("Gmail"))
+        );
+        let referer = parse("https://m.facebook.com/story.php").unwrap();
+        assert_eq!(referer.medium, Medium::Social);
+
+        assert_eq!(
+            parse("https://blog.example.com/post").unwrap().medium,
+            Medium::Internal
+        );
+        assert_eq!(
+            parse("https://shop.example.org/").unwrap().medium,
+            Medium::Internal
+        );
+        assert_eq!(
+            parse("https://notexample.com/").unwrap().medium,
+            Medium::Unknown
+        );
+        assert_eq!(parse("android-app://com.google.android.gm"), None);
+        assert_eq!(parse("not a url"), None);
+    }
+
+    #[test]
+    fn ga4_device_category() {
+        let regexes = r"
+user_agent_parsers: []
+os_parsers:
+  - regex: 'Windows NT'
+    os_replacement: 'Windows'
+  - regex: '(Android)'
+  - regex: 'CPU (?:iPhone |)OS'
+    os_replacement: 'iOS'
+device_parsers:
+  - regex: '(iPad)'
+  - regex: '(iPhone)'
+  - regex: '(Apple Watch)'
+  - regex: '(PlayStation 5)'
+  - regex: 'Googlebot'
+    device_replacement: 'Spider'
+";
+        let parser = UserAgentParser::from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+        let category = |user_agent| parser.parse(user_agent).ga4_device_category();
+
+        assert_eq!(
+            category("Mozilla/5.0 (Windows NT 10.0; Win64; x64) Chrome/120.0.0.0"),
+            Ga4DeviceCategory::Desktop
+        );
+        assert_eq!(
+            category("Mozilla/5.0 (iPad; CPU OS 17_0 like Mac OS X) Mobile/15E148"),
+            Ga4DeviceCategory::Tablet
+        );
+        assert_eq!(
+            category("Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X)"),
+            Ga4DeviceCategory::Mobile
+        );
+        assert_eq!(
+            category("Mozilla/5.0 (Linux; Android 13; K) Chrome/120.0.0.0 Mobile"),
+            Ga4DeviceCategory::Mobile
+        );
+        assert_eq!(
+            category("atc/1.0 watchOS/10.0 model/Watch6,1 (Apple Watch)"),
+            Ga4DeviceCategory::Mobile
+        );
+
+        // GA4 files consoles, bots and anything unknown under desktop
+        assert_eq!(
+            category("Mozilla/5.0 (PlayStation 5 7.00) AppleWebKit/605.1.15"),
+            Ga4DeviceCategory::Desktop
+        );
+        assert_eq!(
+            category("Mozilla/5.0 (compatible; Googlebot/2.1)"),
+            Ga4DeviceCategory::Desktop
+        );
+        assert_eq!(category("curl/8.0"), Ga4DeviceCategory::Desktop);
+        assert_eq!(Ga4DeviceCategory::Tablet.as_str(), "tablet");
+    }
+
+    #[test]
+    fn chained_parser() {
+        let internal = r"
+user_agent_parsers:
+  - regex: '(MyApp)/(\d+)\.(\d+)'
+os_parsers: []
+device_parsers:
+  - regex: '(Kiosk) (\w+)'
+    device_replacement: '$1 $2'
+    brand_replacement: 'Acme'
+";
+        let build = |regexes: &str, dataset: &str| {
+            UserAgentParser::builder()
+                .match_info(true)
+                .dataset_id(dataset)
+                .fallback_family(dataset)
+                .build_from_bytes(regexes.as_bytes())
+                .expect("Parser creation failed")
+        };
+        let parser = ChainedParser::new()
+            .with(build(internal, "internal"))
+            .with(build(TEST_REGEXES, "core"))
+            .match_info(true);
+
+        let client = parser.parse("MyApp/2.1 (X11; Linux x86_64; Kiosk K7) Firefox/68.0");
+        assert_eq!(client.user_agent.family, "MyApp");
+        assert_eq!(client.user_agent.major.as_deref(), Some("2"));
+        assert_eq!(client.device.family, "Kiosk K7");
+        assert_eq!(client.device.brand.as_deref(), Some("Acme"));
+        assert_eq!(client.os.family, "Linux");
+
+        let info = client.match_info.unwrap();
+        let dataset = |rule: Option<RuleMatch>| rule.unwrap().dataset;
+        assert_eq!(dataset(info.user_agent).as_deref(), Some("internal"));
+        assert_eq!(dataset(info.device).as_deref(), Some("internal"));
+        assert_eq!(dataset(info.os).as_deref(), Some("core"));
+
+        // Later parsers fill in what earlier ones don't match
+        let client =
+            parser.parse("Mozilla/5.0 (Linux; Android 10; Pixel 4) Firefox/68.0");
+        assert_eq!(client.user_agent.family, "Firefox");
+        assert_eq!(client.device.family, "Pixel 4");
+
+        // Categories no parser matches take the result of the last one
+        let (client, matched) = parser.parse_matched("curl/8.0");
+        assert_eq!(matched, [false; 3]);
+        assert_eq!(client.user_agent.family, "core");
+        assert_eq!(client.match_info.unwrap(), MatchInfo::default());
+
+        assert_eq!(parser.parse_device("Kiosk K7").family, "Kiosk K7");
+        assert_eq!(ChainedParser::new().parse("MyApp/2.1"), Client::default());
+    }
+
+    #[test]
+    fn dictionary_parser() {
+        let regexes = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let pinned = "Mozilla/5.0 (X11; Linux x86_64) Firefox/68.0";
+
+        let mut dictionary = Dictionary::new();
+        let mut client = regexes.parse(pinned);
+        client.user_agent.family = "Firefox ESR".to_owned();
+        dictionary.insert(pinned, client);
+        let parser = DictionaryParser::new(regexes, dictionary).cache(2);
+
+        assert_eq!(parser.parse(pinned).user_agent.family, "Firefox ESR");
+        assert_eq!(parser.parse_user_agent(pinned).family, "Firefox ESR");
+        assert_eq!(parser.parse_os(pinned).family, "Linux");
+
+        // Only the exact user agent is answered from the dictionary
+        let other = "Mozilla/5.0 (X11; Linux x86_64) Firefox/70.0";
+        assert_eq!(parser.parse(other).user_agent.family, "Firefox");
+        assert_eq!(
+            parser.parse(&pinned.to_lowercase()).user_agent.family,
+            "Other"
+        );
+
+        let exported = parser.export();
+        assert_eq!(exported.len(), 3);
+        assert_eq!(
+            exported
+                .get(other)
+                .map(|client| client.user_agent.major.as_deref()),
+            Some(Some("70"))
+        );
+
+        let yaml = exported.to_yaml().unwrap();
+        let loaded = Dictionary::from_bytes(yaml.as_bytes()).unwrap();
+        assert_eq!(loaded, exported);
+        assert_eq!(loaded.get(pinned).unwrap().user_agent.family, "Firefox ESR");
+    }
+
+    #[test]
+    fn device_kinds() {
+        let regexes = r"
+user_agent_parsers: []
+os_parsers: []
+device_parsers:
+  - regex: 'Kiosk (\w+)'
+    device_replacement: 'Kiosk'
+    type_replacement: 'tablet'
+  - regex: 'Panel (\w+)'
+    device_type: '$1'
+  - regex: '(Other)'
+";
+        let file = RegexFile::from_bytes(regexes.as_bytes()).unwrap();
+        assert_eq!(
+            file.device_parsers[1].type_replacement.as_deref(),
+            Some("$1")
+        );
+
+        let parser = UserAgentParser::from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+        let client = parser.parse("Mozilla/5.0 (Linux; Android 12; Kiosk K1)");
+        assert_eq!(client.device.family, "Kiosk");
+
+        assert_eq!(client.device.kind.as_deref(), Some("tablet"));
+        assert_eq!(client.device_type(), DeviceType::Tablet);
+
+        let client = parser.parse("Mozilla/5.0 (Panel smart_speaker)");
+        assert_eq!(client.device.kind.as_deref(), Some("smart_speaker"));
+        assert_eq!(client.device_type(), DeviceType::SmartSpeaker);
+
+        let device = parser.parse_device("Other");
+        assert_eq!(device.kind, None);
+    }
+
+    #[test]
+    fn regex_config() {
+        let regexes = r"
+user_agent_parsers:
+  - regex: '(\w+)/(\d+)'
+os_parsers: []
+device_parsers:
+  - regex: 'Kiosk ([\w.]+)'
+";
+        let build = |config: RegexConfig| {
+            UserAgentParser::builder()
+                .regex_config(Category::Device, config)
+                .build_from_bytes(regexes.as_bytes())
+        };
+        let user_agent = "Bäckerei/3 (Kiosk Bäcker.2)";
+
+        let parser = build(RegexConfig::new()).expect("Parser creation failed");
+        assert_eq!(parser.parse_device(user_agent).family, "Bäcker.2");
+
+        let parser = build(RegexConfig::new().unicode(false).dfa_size_limit(1 << 20))
+            .expect("Parser creation failed");
+        assert_eq!(parser.parse_device(user_agent).family, "B");
+        assert_eq!(parser.parse_user_agent(user_agent).family, "Bäckerei");
+
+        let invalid = build(RegexConfig::new().size_limit(10));
+        assert!(invalid.is_err() || cfg!(feature = "lazy"));
+        let parser = UserAgentParser::builder()
+            .size_limit(10)
+            .regex_config(Category::UserAgent, RegexConfig::new().size_limit(1 << 20))
+            .regex_config(Category::Device, RegexConfig::new().size_limit(1 << 20))
+            .build_from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+        assert_eq!(parser.parse_user_agent(user_agent).family, "Bäckerei");
+    }
+
+    #[test]
+    fn known_values() {
+        let regexes = r"
+user_agent_parsers:
+  - regex: '(Edge|Edg)/(\d+)'
+    family_replacement: '$1 Browser'
+  - regex: '(Firefox)/(\d+)'
+  - regex: '(\w+)Bot'
+os_parsers:
+  - regex: '(Mac OS X|iOS) (\d+)'
+  - regex: 'Windows NT'
+    os_replacement: 'Windows'
+device_parsers:
+  - regex: '(Pixel) (\d)'
+    brand_replacement: 'Google'
+  - regex: '(Galaxy|Note) (\w+)'
+    brand_replacement: 'Samsung $1'
+  - regex: 'Kiosk (\w+)'
+    brand_replacement: '$1'
+  - regex: 'iPhone'
+";
+        let parser = UserAgentParser::builder()
+            .family_aliases(FamilyAliases::new().alias(
+                Category::UserAgent,
+                "Edg Browser",
+                "Edge Browser",
+            ))
+            .build_from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+
+        let families = parser.user_agent_families();
+        assert_eq!(
+            families.values.iter().collect::<Vec<_>>(),
+            ["Edge Browser", "Firefox", "Other"]
+        );
+        assert_eq!(families.open_rules, 1);
+        assert!(!families.is_complete());
+
+        let families = parser.os_families();
+        assert_eq!(
+            families.values.iter().collect::<Vec<_>>(),
+            ["Mac OS X", "Other", "Windows", "iOS"]
+        );
+        assert!(families.is_complete());
+
+        let brands = parser.device_brands();
+        assert_eq!(
+            brands.values.iter().collect::<Vec<_>>(),
+            ["Google", "Samsung Galaxy", "Samsung Note"]
+        );
+        assert_eq!(brands.open_rules, 1);
+    }
+
+    #[test]
+    fn os_arch() {
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let arch = |user_agent| {
+            let os = parser.parse_os(user_agent);
+            os.arch.zip(os.bitness)
+        };
+
+        assert_eq!(
+            arch("Mozilla/5.0 (Windows NT 10.0; WOW64) AppleWebKit/537.36"),
+            Some((Arch::X86_64, 64))
+        );
+        assert_eq!(
+            arch("Mozilla/5.0 (X11; Linux armv7l) AppleWebKit/537.36"),
+            Some((Arch::Arm, 32))
+        );
+        assert_eq!(
+            arch("Mozilla/5.0 (X11; CrOS aarch64 15183.69.0) AppleWebKit/537.36"),
+            Some((Arch::Arm64, 64))
+        );
+        assert_eq!(
+            arch("Mozilla/5.0 (Windows Phone 8.1; ARM; Trident/7.0; Touch)"),
+            Some((Arch::Arm, 32))
+        );
+
+        // Only whole tokens count, not words containing them
+        assert_eq!(arch("Mozilla/5.0 (Linux; Android 13) Charm;Alarm"), None);
+        assert_eq!(
+            arch("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7)"),
+            None
+        );
+
+        // Reusing a result doesn't keep the architecture of the previous one
+        let mut client = Client::default();
+        parser.parse_into("Mozilla/5.0 (X11; Linux armv7l) Firefox/68.0", &mut client);
+        parser.parse_into("Mozilla/5.0 (Macintosh) Firefox/68.0", &mut client);
+        assert_eq!(client.os.arch, None);
+        assert_eq!(client.os.bitness, None);
+
+        let mut os = parser.parse_os("Mozilla/5.0 (Windows NT 10.0; Win64; x64)");
+        os.apply_client_hints(None, Some("\"32\""));
+        assert_eq!((os.arch, os.bitness), (Some(Arch::X86), Some(32)));
+        os.apply_client_hints(Some("\"arm\""), Some("\"64\""));
+        assert_eq!((os.arch, os.bitness), (Some(Arch::Arm64), Some(64)));
+    }
+
+    #[test]
+    fn os_distro() {
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let distro = |user_agent| {
+            let os = parser.parse_os(user_agent);
+            (os.distro, os.distro_version)
+        };
+
+        assert_eq!(
+            distro("Mozilla/5.0 (X11; Ubuntu; Linux x86_64; rv:120.0) Firefox/120.0"),
+            (Some("Ubuntu".to_owned()), None)
+        );
+        assert_eq!(
+            distro("Mozilla/5.0 (X11; Linux x86_64) Gecko Ubuntu/22.04 Firefox/120.0"),
+            (Some("Ubuntu".to_owned()), Some("22.04".to_owned()))
+        );
+        assert_eq!(
+            distro("Mozilla/5.0 (X11; Linux i686; rv:10.0) Gecko Fedora/3.6.3-4.fc13"),
+            (Some("Fedora".to_owned()), Some("13".to_owned()))
+        );
+        assert_eq!(
+            distro("Mozilla/5.0 (X11; Linux Mint 21.2; Linux x86_64) Firefox/120.0"),
+            (Some("Linux Mint".to_owned()), Some("21.2".to_owned()))
+        );
+        assert_eq!(
+            distro("Mozilla/5.0 (X11; Linux x86_64; NSUSEX) Firefox/120.0"),
+            (None, None)
+        );
+        assert_eq!(
+            distro("Mozilla/5.0 (Windows NT 10.0; Ubuntu) Firefox/120.0"),
+            (None, None)
+        );
+
+        // Reusing a result doesn't keep the distribution of the previous one
+        let mut client = Client::default();
+        parser.parse_into("Mozilla/5.0 (X11; Debian; Linux) Firefox/68.0", &mut client);
+        assert_eq!(client.os.distro.as_deref(), Some("Debian"));
+        parser.parse_into("Mozilla/5.0 (X11; Linux x86_64) Firefox/68.0", &mut client);
+        assert_eq!(client.os.distro, None);
+    }
+
+    #[test]
+    fn unknown_clients() {
+        let client = Client::unknown();
+        assert!(client.is_unknown());
+        assert!(client.is_empty());
+
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let client = parser.parse("Mozilla/5.0 (X11; Linux x86_64) Firefox/68.0");
+        assert!(!client.is_unknown());
+        assert!(!client.user_agent.is_unknown());
+        assert!(client.device.is_unknown());
+
+        let mut os = OS::unknown();
+        os.arch = Some(Arch::X86_64);
+        assert!(os.is_unknown());
+        assert!(!os.is_empty());
+    }
+
+    #[test]
+    fn raw_captures() {
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let user_agent = "Mozilla/5.0 (X11; Linux x86_64) Firefox/68.0";
+
+        let (parsed, captures) = parser.parse_user_agent_raw(user_agent);
+        assert_eq!(parsed, parser.parse_user_agent(user_agent));
+        let captures = captures.unwrap();
+        assert_eq!(captures.get(1), Some(parsed.family.as_str()));
+        assert_eq!(captures.get(captures.groups.len()), None);
+
+        let (os, captures) = parser.parse_os_raw(user_agent);
+        assert_eq!(os, parser.parse_os(user_agent));
+        assert!(captures.is_some());
+
+        let (device, captures) = parser.parse_device_raw("curl/7.64.1");
+        assert_eq!(device.family, "Other");
+        assert_eq!(captures, None);
+
+        let parser = UserAgentParser::builder()
+            .feature_phones(true)
+            .build_from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let (device, captures) = parser.parse_device_raw("Mozilla/5.0 (Pixel 4)");
+        assert_eq!(device.family, "Pixel 4");
+        assert_eq!(captures.unwrap().index, Some(0));
+
+        let nokia = "Mozilla/5.0 (Mobile; Nokia_8110_4G; rv:48.0) KAIOS/2.5";
+        let (device, captures) = parser.parse_device_raw(nokia);
+        assert_eq!(device.family, "Nokia 8110");
+        assert_eq!(captures.unwrap().index, None);
+    }
+
+    #[test]
+    #[cfg(feature = "typed-headers")]
+    fn typed_headers() {
+        use headers::{HeaderMap, HeaderMapExt};
+
+        let parser = UserAgentParser::from_bytes(TEST_REGEXES.as_bytes())
+            .expect("Parser creation failed");
+        let header: headers::UserAgent = "Mozilla/5.0 (X11; Linux x86_64) Firefox/68.0"
+            .parse()
+            .unwrap();
+        assert_eq!(header.parse_user_agent(&parser).family, "Firefox");
+
+        let parsed = ParsedUserAgent::new(header.clone(), &parser);
+        assert_eq!(parsed.client().os.family, "Linux");
+
+        let mut headers = HeaderMap::new();
+        headers.typed_insert(parsed);
+        assert_eq!(headers["user-agent"], header.as_str());
+        assert!(HeaderMap::new().typed_get::<ParsedUserAgent>().is_none());
+    }
+
+    #[test]
+    fn os_version_replacements() {
+        let regexes = r"
+user_agent_parsers: []
+os_parsers:
+  - regex: 'Fire OS (\d+)\.(\d+)\.(\d+)\.(\d+)'
+    os_replacement: 'Fire OS'
+    os_v1_replacement: '$1'
+    os_v2_replacement: '$2'
+    os_v3_replacement: '$3'
+    os_v4_replacement: '$4'
+  - regex: 'Build/RQ(\d)A\.(\d{2})(\d{2})(\d{2})'
+    os_replacement: 'Android'
+    os_v1_replacement: '1$1'
+    os_v2_replacement: '0'
+    os_v3_replacement: '20$2'
+    os_v4_replacement: '$3$4'
+  - regex: '(Symbian)/(\d+)\.(\d+)\.(\d+)\.(\d+)'
+device_parsers: []
+";
+        let parser = UserAgentParser::from_bytes(regexes.as_bytes())
+            .expect("Parser creation failed");
+        let version = |user_agent| {
+            let os = parser.parse_os(user_agent);
+            let version = os.version();
+            (os.family, version)
+        };
+
+        assert_eq!(
+            version("Mozilla/5.0 (Linux; Fire OS 7.3.2.1)"),
+            ("Fire OS".to_owned(), Some("7.3.2.1".to_owned()))
+        );
+        assert_eq!(
+            version("Mozilla/5.0 (Linux; Android 11; Build/RQ1A.210105)"),
+            ("Android".to_owned(), Some("11.0.2021.0105".to_owned()))
+        );
+        assert_eq!(
+            version("Symbian/3.1.4.8"),
+            ("Symbian".to_owned(), Some("3.1.4.8".to_owned()))
+        );
+
+        let file = RegexFile::from_bytes(regexes.as_bytes()).unwrap();
+        assert_eq!(
+            file.os_parsers[1].os_v4_replacement.as_deref(),
+            Some("$3$4")
+        );
+        assert!(file.to_yaml().unwrap().contains("os_v4_replacement"));
+    }
+
+    #[cfg(feature = "corpus")]
+    #[test]
+    fn corpus() {
+        let user_agents: std::collections::HashSet<_> = corpus::user_agents().collect();
+        assert_eq!(user_agents.len(), corpus::samples().len());
+        assert!(!corpus::version().is_empty());
+
+        for segment in corpus::Segment::ALL.iter() {
+            assert!(corpus::segment(*segment).count() > 0, "{:?}", segment);
+        }
+
+        let parser = UserAgentParser::from_yaml("./src/core/regexes.yaml")
+            .expect("Parser creation failed");
+        let bots = corpus::segment(corpus::Segment::Bot)
+            .filter(|user_agent| parser.parse_device(user_agent).family == "Spider");
+        assert!(bots.count() > 0);
+    }
+
+    #[test]
+    fn borrowed_regex_file() {
+        use std::borrow::Cow;
+
+        let dataset = "user_agent_parsers:\n  - regex: '(Firefox)/'\nos_parsers: []\n\
+                       device_parsers: []"
+            .to_owned();
+        let regex_file = RegexFile::from_bytes(dataset.as_bytes())
+            .unwrap()
+            .into_owned();
+        drop(dataset);
+        assert_eq!(regex_file.user_agent_parsers[0].regex, "(Firefox)/");
+        UserAgentParser::builder().build(regex_file).unwrap();
+
+        #[cfg(feature = "json")]
+        {
+            let json = r#"{
+                "user_agent_parsers": [{"regex": "Fx/", "family_replacement": "Firefox"}],
+                "os_parsers": [],
+                "device_parsers": []
+            }"#;
+            let regex_file: RegexFile = serde_json::from_str(json).unwrap();
+            let entry = &regex_file.user_agent_parsers[0];
+            assert!(matches!(entry.regex, Cow::Borrowed("Fx/")));
+            assert!(matches!(entry.family_replacement, Some(Cow::Borrowed(_))));
+        }
+    }
+
+    #[test]
+    fn desktop_shortcut() {
+        let dataset = r#"
+user_agent_parsers: []
+os_parsers: []
+device_parsers:
+  - regex: 'bingbot'
+    device_replacement: 'Spider'
+  - regex: 'Windows NT.*(Touch)'
+  - regex: '(Windows NT|Linux)'
+    device_replacement: 'Desktop'
+"#;
+        let parser = UserAgentParser::builder()
+            .desktop_shortcut(true)
+            .build_from_bytes(dataset.as_bytes())
+            .expect("Parser creation failed");
+        let device = |user_agent| parser.parse_device(user_agent).family;
+
+        let windows = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
+                       (KHTML, like Gecko) Chrome/140.0.0.0 Safari/537.36";
+        assert_eq!(device(windows), "Other");
+        assert_eq!(
+            device("Mozilla/5.0 (X11; Linux x86_64; rv:143.0) Firefox/143.0"),
+            "Other"
+        );
+        assert_eq!(
+            device("Mozilla/5.0 (Windows NT 6.2; Touch) like Gecko"),
+            "Touch"
+        );
+        assert_eq!(
+            device("Mozilla/5.0 (Windows NT 10.0; bingbot/2.0; +http://www.bing.com)"),
+            "Spider"
+        );
+        assert_eq!(
+            device("Mozilla/5.0 (Linux; Android 14; Pixel 9) Mobile Safari/537.36"),
+            "Desktop"
+        );
+
+        let parser = UserAgentParser::builder()
+            .build_from_bytes(dataset.as_bytes())
+            .expect("Parser creation failed");
+        assert_eq!(parser.parse_device(windows).family, "Desktop");
+    }
+
+    #[test]
+    fn memory_budget() {
+        let dataset = r#"
+user_agent_parsers:
+  - regex: 'Fennec/(\d+)'
+    family_replacement: 'Firefox Mobile'
+  - regex: '(\w+)/(\d+)'
+os_parsers:
+  - regex: '(Windows)'
+  - regex: '(Linux)'
+device_parsers: []
+"#;
+        let build = |bytes, hits: Option<RuleHits>| {
+            let mut builder = UserAgentParser::builder().memory_budget(bytes);
+            if let Some(hits) = hits {
+                builder = builder.budget_hits(hits);
+            }
+            builder.build_from_bytes(dataset.as_bytes()).unwrap()
+        };
+
+        let parser = build(usize::MAX, None);
+        let usage = parser.memory_usage();
+        let total = usage.compiled_bytes;
+        assert_eq!(build(total, None).memory_usage().dropped_rules, 0);
+
+        // The estimate doesn't depend on the budget
+        let plain = UserAgentParser::from_bytes(dataset.as_bytes()).unwrap();
+        assert_eq!(plain.memory_usage().compiled_bytes, total);
+        assert!(total > usage.rules_bytes);
+        assert_eq!((usage.rules, usage.dropped_rules), (4, 0));
+
+        // The generic rule is dropped rather than the specific one before it,
+        // which would let the generic rule report `Fennec`
+        let parser = build(total - 1, None);
+        assert_eq!(parser.memory_usage().dropped_rules, 1);
+        assert_eq!(
+            parser.parse_user_agent("Fennec/68").family,
+            "Firefox Mobile"
+        );
+        assert_eq!(parser.parse_user_agent("Chrome/118").family, "Other");
+        assert_eq!(parser.parse_os("Linux").family, "Linux");
+
+        let mut hits = RuleHits::new();
+        hits.record(Category::UserAgent, 1);
+        let parser = build(total - 1, Some(hits));
+        assert_eq!(parser.parse_user_agent("Chrome/118").family, "Chrome");
+        assert_eq!(parser.parse_os("Linux").family, "Other");
+        assert_eq!(parser.parse_os("Windows").family, "Windows");
+
+        let parser = build(0, None);
+        assert_eq!(parser.memory_usage().dropped_rules, 4);
+        assert_eq!(parser.parse_user_agent("Fennec/68").family, "Other");
+        assert_eq!(
+            UserAgentParser::builder()
+                .build(RegexFile::new())
+                .unwrap()
+                .memory_usage()
+                .compiled_bytes,
+            0
+        );
+
+        // Only the kept rules are compiled
+        let mut hits = RuleHits::new();
+        hits.record(Category::UserAgent, 1);
+        let invalid = dataset.replace("'(Linux)'", "'(Linux'");
+        let parser = UserAgentParser::builder()
+            .memory_budget(total - 1)
+            .budget_hits(hits)
+            .build_from_bytes(invalid.as_bytes())
+            .expect("Parser creation failed");
+        assert_eq!(parser.memory_usage().dropped_rules, 1);
+    }
+
+    #[test]
+    fn background_wait() {
+        let parser = UserAgentParser::builder().build_in_background(TEST_REGEXES);
+
+        std::thread::scope(|scope| {
+            let waits = [
+                scope.spawn(|| parser.wait().is_ok()),
+                scope.spawn(|| parser.wait().is_ok()),
+            ];
+            for wait in waits {
+                assert!(wait.join().unwrap());
+            }
+        });
+
+        assert!(parser.is_ready());
+        assert_eq!(
+            parser
+                .parse_user_agent("Mozilla/5.0 (X11; Linux x86_64) Firefox/68.0")
+                .family,
+            "Firefox"
+        );
+    }
+
     fn print_failure<T: Debug, F: Debug>(got: &T, expected: &F) {
         println!(
             r" --- Failed Test Case ----
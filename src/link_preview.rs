@@ -0,0 +1,109 @@
+use serde_derive::Deserialize;
+
+use super::Error;
+
+/// The rules shipped with the crate, see `LinkPreviewParser::default`
+static LINK_PREVIEWS: &str = include_str!("link_previews.yaml");
+
+/// Describes a fetcher rendering the preview of a link shared in a chat or
+/// social app. These want the metadata of a page, but aren't engagement.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct LinkPreview {
+    pub name: String,
+    pub operator: String,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LinkPreviewFile {
+    link_previews: Vec<LinkPreviewEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LinkPreviewEntry {
+    regex: String,
+    name: String,
+    operator: String,
+}
+
+#[derive(Debug)]
+struct Rule {
+    regex: fancy_regex::Regex,
+    preview: LinkPreview,
+}
+
+/// Identifies the link preview fetchers of chat and social apps, e.g. to
+/// serve them Open Graph metadata while excluding them from engagement
+/// metrics. Besides the rules shipped with the crate, they can be loaded from
+/// a file in the format of `src/link_previews.yaml`.
+///
+/// ```rust
+/// # use uaparser::*;
+/// let parser = LinkPreviewParser::default();
+/// let preview = parser
+///     .parse("Slackbot-LinkExpanding 1.0 (+https://api.slack.com/robots)")
+///     .unwrap();
+///
+/// assert_eq!(preview.operator, "Slack");
+/// assert_eq!(preview.version.as_deref(), Some("1.0"));
+/// ```
+#[derive(Debug)]
+pub struct LinkPreviewParser {
+    rules: Vec<Rule>,
+}
+
+impl Default for LinkPreviewParser {
+    fn default() -> LinkPreviewParser {
+        LinkPreviewParser::from_bytes(LINK_PREVIEWS.as_bytes())
+            .expect("bundled link preview rules are valid")
+    }
+}
+
+impl LinkPreviewParser {
+    /// Attempts to construct a `LinkPreviewParser` from the path to a rule
+    /// file
+    #[cfg(feature = "fs")]
+    pub fn from_yaml(path: &str) -> Result<LinkPreviewParser, Error> {
+        let file = std::fs::File::open(path)?;
+        let preview_file: LinkPreviewFile = serde_yaml::from_reader(file)?;
+        LinkPreviewParser::from_preview_file(preview_file)
+    }
+
+    /// Attempts to construct a `LinkPreviewParser` from the raw bytes of a
+    /// rule file
+    pub fn from_bytes(bytes: &[u8]) -> Result<LinkPreviewParser, Error> {
+        let preview_file: LinkPreviewFile = serde_yaml::from_slice(bytes)?;
+        LinkPreviewParser::from_preview_file(preview_file)
+    }
+
+    fn from_preview_file(
+        preview_file: LinkPreviewFile,
+    ) -> Result<LinkPreviewParser, Error> {
+        let mut rules = Vec::new();
+
+        for entry in preview_file.link_previews {
+            rules.push(Rule {
+                regex: fancy_regex::Regex::new(&entry.regex)?,
+                preview: LinkPreview {
+                    name: entry.name,
+                    operator: entry.operator,
+                    version: None,
+                },
+            });
+        }
+
+        Ok(LinkPreviewParser { rules })
+    }
+
+    /// Returns the link preview fetcher `user_agent` belongs to, if any
+    pub fn parse(&self, user_agent: &str) -> Option<LinkPreview> {
+        self.rules.iter().find_map(|rule| {
+            let captures = rule.regex.captures(user_agent).ok()??;
+            let version = captures.get(1).map(|m| m.as_str().to_string());
+            Some(LinkPreview {
+                version,
+                ..rule.preview.clone()
+            })
+        })
+    }
+}
@@ -0,0 +1,62 @@
+use std::{cmp::Reverse, time::Duration};
+
+use super::Category;
+
+/// The cost of compiling a single rule
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RuleReport {
+    pub category: Category,
+    /// The position of the rule within its category of the dataset, or
+    /// `None` for rules the parser adds itself
+    pub index: Option<usize>,
+    pub pattern: String,
+    pub compile_time: Duration,
+    /// The approximate size in bytes of the compiled regex, rounded up to the
+    /// next KiB, or `None` if it doesn't compile
+    pub compiled_size: Option<usize>,
+}
+
+/// Describes what it took to construct a `UserAgentParser`. Only recorded if
+/// enabled with `UserAgentParserBuilder::diagnostics`.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct LoadReport {
+    pub rules: Vec<RuleReport>,
+    /// The time it took to construct the parser, not counting the extra
+    /// compilations that measure the `rules`
+    pub total_time: Duration,
+}
+
+impl LoadReport {
+    /// Returns the `n` rules that took longest to compile, slowest first
+    pub fn slowest(&self, n: usize) -> Vec<&RuleReport> {
+        let mut rules: Vec<&RuleReport> = self.rules.iter().collect();
+        rules.sort_by_key(|rule| Reverse(rule.compile_time));
+        rules.truncate(n);
+        rules
+    }
+
+    /// Returns the `n` rules with the largest compiled regexes, largest first
+    pub fn largest(&self, n: usize) -> Vec<&RuleReport> {
+        let mut rules: Vec<&RuleReport> = self.rules.iter().collect();
+        rules.sort_by_key(|rule| Reverse(rule.compiled_size));
+        rules.truncate(n);
+        rules
+    }
+}
+
+/// The memory held by a constructed `UserAgentParser`, see
+/// `UserAgentParser::memory_usage`
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct MemoryUsage {
+    /// The number of rules across all categories
+    pub rules: usize,
+    /// Bytes taken up by the rules themselves, i.e. their replacements and,
+    /// with the `lazy` feature, their regex sources
+    pub rules_bytes: usize,
+    /// The memory of the rules including their compiled regexes, as estimated
+    /// from their sources like for `UserAgentParserBuilder::memory_budget`.
+    /// With the `lazy` feature, rules yet to be compiled are counted as well.
+    pub compiled_bytes: usize,
+    /// The number of rules dropped to stay within the memory budget
+    pub dropped_rules: usize,
+}
@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+/// Where the value of a single result field came from
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum FieldSource {
+    /// The given capture group of the rule's regex
+    Capture(usize),
+    /// The replacement configured on the rule, which may itself reference
+    /// capture groups
+    Replacement,
+    /// The rule doesn't provide a value for the field
+    Unset,
+}
+
+/// How much a result can be relied on, judged by how specific the matched
+/// rule is, whether it fills in every field and whether it reports a generic
+/// value, like the fallback family or a `Generic_Android` device
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Confidence {
+    /// A generic rule, like one matching any `Android` user agent, or a rule
+    /// reporting a generic value
+    Low,
+    Medium,
+    /// A rule matching a long literal, like a specific device model, which
+    /// fills in every field
+    High,
+}
+
+impl Confidence {
+    /// Rates a rule by the literal text its regex requires and the fields it
+    /// leaves unset. A rule is rated low regardless if the result is
+    /// `generic`, see `is_generic`.
+    pub(crate) fn rate(
+        source: &str,
+        fields: &[(&'static str, FieldSource)],
+        generic: bool,
+    ) -> Confidence {
+        if generic {
+            return Confidence::Low;
+        }
+
+        let specificity = match literal_len(source) {
+            0..=4 => 0,
+            5..=9 => 1,
+            _ => 2,
+        };
+        let unset = fields
+            .iter()
+            .any(|(_, source)| *source == FieldSource::Unset);
+
+        match specificity - i32::from(unset) {
+            i32::MIN..=0 => Confidence::Low,
+            1 => Confidence::Medium,
+            _ => Confidence::High,
+        }
+    }
+}
+
+/// Tells whether a device family or brand is a placeholder of the catch-all
+/// rules of uap-core, like `Generic Smartphone` or `Generic_Android`. These
+/// rules require long literals, but tell nothing about the device.
+pub(crate) fn is_generic(value: &str) -> bool {
+    value.starts_with("Generic")
+}
+
+/// Counts the characters a regex matches literally, skipping classes,
+/// quantifiers, group syntax and escapes other than escaped punctuation
+fn literal_len(source: &str) -> usize {
+    let mut chars = source.chars().peekable();
+    let mut count = 0;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if chars.next().is_some_and(|c| !c.is_alphanumeric()) {
+                    count += 1;
+                }
+            }
+            '[' => {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        ']' => break,
+                        _ => (),
+                    }
+                }
+            }
+            '{' => while chars.next().is_some_and(|c| c != '}') {},
+            '(' if chars.peek() == Some(&'?') => {
+                while chars.next_if(|&c| c != ':' && c != ')').is_some() {}
+                chars.next_if_eq(&':');
+            }
+            '.' | '^' | '$' | '*' | '+' | '?' | '(' | ')' | '|' => (),
+            _ => count += 1,
+        }
+    }
+
+    count
+}
+
+/// Identifies the rule that produced one category of a result
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct RuleMatch {
+    /// The position of the rule within its category of the dataset, or
+    /// `None` for rules the parser adds itself, like those of
+    /// `UserAgentParserBuilder::feature_phones`
+    pub index: Option<usize>,
+    /// The source of every field the rule fills in, by field name
+    pub fields: Vec<(&'static str, FieldSource)>,
+    pub confidence: Confidence,
+    /// The byte range of the user agent the rule matched, e.g. to highlight
+    /// it in an investigation UI
+    pub span: std::ops::Range<usize>,
+    /// The identifier of the dataset the rule belongs to. Results combined
+    /// from several parsers, see `ChainedParser::match_info`, can take each
+    /// category from a different one. Shared by all results of a parser.
+    pub dataset: Option<Arc<str>>,
+}
+
+/// Attributes the fields of a `Client` to the rules they came from. A
+/// category is `None` if no rule matched and the fallback was used.
+///
+/// Only attached to results if enabled with
+/// `UserAgentParserBuilder::match_info`.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct MatchInfo {
+    /// The identifier given to the dataset the parser was built from
+    pub dataset: Option<Arc<str>>,
+    pub device: Option<RuleMatch>,
+    pub os: Option<RuleMatch>,
+    pub user_agent: Option<RuleMatch>,
+}
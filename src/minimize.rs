@@ -0,0 +1,157 @@
+use super::{Category, Client, Deserialize, Parser, RegexFile, Serialize};
+
+/// How often every rule of a dataset was the first to match, indexed by the
+/// position of the rule in its category of the dataset. Collected from a corpus with
+/// `UserAgentParser::rule_hits`, or recorded from production traffic, and
+/// stored to build reduced datasets with `minimize`.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub struct RuleHits {
+    pub user_agent: Vec<u64>,
+    pub os: Vec<u64>,
+    pub device: Vec<u64>,
+}
+
+impl RuleHits {
+    pub fn new() -> RuleHits {
+        RuleHits::default()
+    }
+
+    /// Counts a match of the rule at `index` of `category`
+    pub fn record(&mut self, category: Category, index: usize) {
+        let hits = self.category_mut(category);
+        if hits.len() <= index {
+            hits.resize(index + 1, 0);
+        }
+        hits[index] += 1;
+    }
+
+    /// Returns the hits of the rules of `category`
+    pub fn get(&self, category: Category) -> &[u64] {
+        match category {
+            Category::UserAgent => &self.user_agent,
+            Category::Os => &self.os,
+            Category::Device => &self.device,
+        }
+    }
+
+    fn category_mut(&mut self, category: Category) -> &mut Vec<u64> {
+        match category {
+            Category::UserAgent => &mut self.user_agent,
+            Category::Os => &mut self.os,
+            Category::Device => &mut self.device,
+        }
+    }
+
+    /// Reduces `regex_file` to the most frequently hit rules of every
+    /// category that together cover at least `coverage` (between 0 and 1) of
+    /// the recorded matches. Rules keep their order, so the recorded user
+    /// agents of kept rules parse as before.
+    ///
+    /// Other user agents may not: a dropped rule, even one without any hits,
+    /// may have shadowed a later rule that is kept, which now claims the user
+    /// agents the dropped rule would have matched. Those get the result of
+    /// the later rule instead of the one of the full dataset, and only user
+    /// agents that no kept rule matches get the fallback family.
+    ///
+    /// The hits have to be recorded against the same dataset `regex_file` was
+    /// loaded from. Parsers report rules by their position in the dataset, so
+    /// hits recorded with `filter_rules` or a `memory_budget` in place still
+    /// line up with it.
+    pub fn minimize<'a>(
+        &self,
+        regex_file: RegexFile<'a>,
+        coverage: f64,
+    ) -> RegexFile<'a> {
+        RegexFile {
+            user_agent_parsers: retain(
+                regex_file.user_agent_parsers,
+                &self.user_agent,
+                coverage,
+            ),
+            os_parsers: retain(regex_file.os_parsers, &self.os, coverage),
+            device_parsers: retain(regex_file.device_parsers, &self.device, coverage),
+        }
+    }
+}
+
+/// Keeps the rules with the most hits until they cover `coverage` of all hits
+fn retain<T>(entries: Vec<T>, hits: &[u64], coverage: f64) -> Vec<T> {
+    let count = |index: usize| hits.get(index).copied().unwrap_or(0);
+    let total: u64 = hits.iter().sum();
+    let target = (total as f64 * coverage.clamp(0.0, 1.0)).ceil() as u64;
+
+    let mut ranked: Vec<usize> = (0..entries.len()).filter(|&i| count(i) > 0).collect();
+    ranked.sort_by_key(|&index| std::cmp::Reverse(count(index)));
+
+    let mut keep = vec![false; entries.len()];
+    let mut covered = 0;
+    for index in ranked {
+        if covered >= target {
+            break;
+        }
+        keep[index] = true;
+        covered += count(index);
+    }
+
+    (entries.into_iter().zip(keep))
+        .filter(|&(_, keep)| keep)
+        .map(|(entry, _)| entry)
+        .collect()
+}
+
+/// Shrinks `user_agent` to a shorter string that `parser` parses to the same
+/// `Client`, to craft regression tests and report dataset bugs. Words are
+/// removed first, then single characters, with delta debugging. The result
+/// is minimal in that removing any single word or character changes it.
+///
+/// ```rust
+/// # use uaparser::*;
+/// # let parser = UserAgentParser::from_yaml("./src/core/regexes.yaml").unwrap();
+/// let user_agent = "Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Gecko/20100101 Firefox/70.0";
+/// let minimal = minimize_user_agent(&parser, user_agent);
+/// assert!(minimal.len() < user_agent.len());
+/// assert_eq!(parser.parse(&minimal), parser.parse(user_agent));
+/// ```
+pub fn minimize_user_agent<P: Parser + ?Sized>(parser: &P, user_agent: &str) -> String {
+    let parse = |user_agent: &str| Client {
+        match_info: None,
+        ..parser.parse(user_agent)
+    };
+    let expected = parse(user_agent);
+
+    let words: Vec<&str> = user_agent.split(' ').collect();
+    let words = ddmin(words, |words| parse(&words.join(" ")) == expected);
+
+    let chars: Vec<char> = words.join(" ").chars().collect();
+    let chars = ddmin(chars, |chars| {
+        parse(&chars.iter().collect::<String>()) == expected
+    });
+    chars.into_iter().collect()
+}
+
+/// Removes chunks of `items` as long as `test` holds for the rest, halving
+/// the chunks down to single items
+fn ddmin<T: Clone>(mut items: Vec<T>, test: impl Fn(&[T]) -> bool) -> Vec<T> {
+    let mut chunks = 2;
+
+    while !items.is_empty() {
+        let size = (items.len() + chunks - 1) / chunks;
+        let reduced = (0..items.len()).step_by(size).find_map(|start| {
+            let end = (start + size).min(items.len());
+            let rest = [&items[..start], &items[end..]].concat();
+            Some(rest).filter(|rest| test(rest))
+        });
+
+        match reduced {
+            Some(rest) => {
+                items = rest;
+                chunks = (chunks - 1).max(2);
+            }
+            None if size == 1 => break,
+            None => chunks = (chunks * 2).min(items.len()),
+        }
+    }
+
+    items
+}
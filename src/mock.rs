@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use super::{Client, Device, Parser, UserAgent, OS};
+
+/// A `Parser` returning canned results, meant for unit tests of code that
+/// consumes parsed user agents without loading the full dataset.
+///
+/// ```rust
+/// # use uaparser::*;
+/// let mut firefox = Client::default();
+/// firefox.user_agent.family = "Firefox".to_owned();
+///
+/// let parser: Box<dyn Parser + Send + Sync> =
+///     Box::new(MockParser::new().with("Mozilla/5.0 Firefox/70.0", firefox));
+///
+/// assert_eq!(parser.parse_user_agent("Mozilla/5.0 Firefox/70.0").family, "Firefox");
+/// assert_eq!(parser.parse_user_agent("curl/7.64.1").family, "Other");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MockParser {
+    clients: HashMap<String, Client>,
+    fallback: Client,
+}
+
+impl MockParser {
+    /// Creates a `MockParser` that returns `Other` for every user agent
+    pub fn new() -> MockParser {
+        MockParser::default()
+    }
+
+    /// Creates a `MockParser` that returns `client` for every user agent
+    pub fn with_fallback(client: Client) -> MockParser {
+        MockParser {
+            clients: HashMap::new(),
+            fallback: client,
+        }
+    }
+
+    /// Returns `client` whenever exactly `user_agent` is parsed
+    pub fn with(mut self, user_agent: impl Into<String>, client: Client) -> MockParser {
+        self.clients.insert(user_agent.into(), client);
+        self
+    }
+
+    fn lookup(&self, user_agent: &str) -> &Client {
+        self.clients.get(user_agent).unwrap_or(&self.fallback)
+    }
+}
+
+impl Parser for MockParser {
+    fn parse(&self, user_agent: &str) -> Client {
+        self.lookup(user_agent).clone()
+    }
+
+    fn parse_device(&self, user_agent: &str) -> Device {
+        self.lookup(user_agent).device.clone()
+    }
+
+    fn parse_os(&self, user_agent: &str) -> OS {
+        self.lookup(user_agent).os.clone()
+    }
+
+    fn parse_user_agent(&self, user_agent: &str) -> UserAgent {
+        self.lookup(user_agent).user_agent.clone()
+    }
+}
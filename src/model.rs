@@ -0,0 +1,59 @@
+/// Strips noise that device rules capture along with the model, so that the
+/// same device isn't counted under many different models:
+///
+/// - everything from a `Build/` marker on, like `Build/QP1A.190711.020`
+/// - locale tokens, like `en-us`
+/// - the `wv` marker of Android WebViews
+/// - the brand repeated in front of the model, like `Samsung SM-G991B`
+///
+/// Returns `None` if nothing is left of the model.
+///
+/// ```rust
+/// # use uaparser::*;
+/// assert_eq!(
+///     normalize_model("SAMSUNG SM-G991B; wv Build/RP1A", Some("Samsung")).as_deref(),
+///     Some("SM-G991B")
+/// );
+/// assert_eq!(normalize_model("en-us; Build/KOT49H", None), None);
+/// ```
+pub fn normalize_model(model: &str, brand: Option<&str>) -> Option<String> {
+    let model = match find_ignore_case(model, "build/") {
+        Some(index) => &model[..index],
+        None => model,
+    };
+
+    let mut tokens: Vec<&str> = model
+        .split(|c: char| c.is_whitespace() || c == ';' || c == ',')
+        .filter(|token| !token.is_empty() && *token != "wv" && !is_locale(token))
+        .collect();
+
+    if let Some(brand) = brand {
+        let brand: Vec<&str> = brand.split_whitespace().collect();
+        while !brand.is_empty()
+            && tokens.len() > brand.len()
+            && (tokens.iter().zip(&brand)).all(|(t, b)| t.eq_ignore_ascii_case(b))
+        {
+            tokens.drain(..brand.len());
+        }
+    }
+
+    if tokens.is_empty() {
+        None
+    } else {
+        Some(tokens.join(" "))
+    }
+}
+
+/// Matches locale tokens like `en-us`, `en-GB` or `zh_CN`
+fn is_locale(token: &str) -> bool {
+    let bytes = token.as_bytes();
+    bytes.len() == 5
+        && bytes[..2].iter().all(u8::is_ascii_lowercase)
+        && (bytes[2] == b'-' || bytes[2] == b'_')
+        && bytes[3..].iter().all(u8::is_ascii_alphabetic)
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    (haystack.as_bytes().windows(needle.len()))
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
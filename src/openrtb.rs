@@ -0,0 +1,80 @@
+use serde_derive::Serialize;
+
+use super::{Client, DeviceType};
+
+/// Values taken from request signals other than the user agent string, most
+/// commonly the `Sec-CH-UA-*` client hints, which are more precise than what
+/// can be parsed from a reduced user agent
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OpenRtbHints {
+    pub model: Option<String>,
+    pub platform: Option<String>,
+    pub platform_version: Option<String>,
+    pub mobile: Option<bool>,
+}
+
+/// The subset of the OpenRTB 2.x `Device` object that can be derived from a
+/// user agent. Serializes to the field names defined by the specification.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct OpenRtbDevice {
+    pub ua: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub devicetype: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub make: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub osv: Option<String>,
+}
+
+impl DeviceType {
+    /// Returns the code of this device type in OpenRTB 2.x list 5.21, if the
+    /// taxonomy has one
+    pub fn openrtb_code(self) -> Option<u8> {
+        match self {
+            DeviceType::Desktop => Some(2),
+            DeviceType::Tv => Some(3),
+            DeviceType::Smartphone | DeviceType::FeaturePhone => Some(4),
+            DeviceType::Tablet => Some(5),
+            DeviceType::Console | DeviceType::Wearable | DeviceType::SmartSpeaker => {
+                Some(6)
+            }
+            DeviceType::Bot | DeviceType::Unknown => None,
+        }
+    }
+}
+
+impl Client {
+    /// Converts this `Client`, parsed from `user_agent`, into an OpenRTB
+    /// `Device` object. Values present in `hints` take precedence over the
+    /// parsed ones.
+    pub fn to_openrtb_device(
+        &self,
+        user_agent: &str,
+        hints: Option<&OpenRtbHints>,
+    ) -> OpenRtbDevice {
+        let hints = hints.cloned().unwrap_or_default();
+
+        let mut devicetype = self.device_type().openrtb_code();
+        if devicetype.is_none() && hints.mobile == Some(true) {
+            // "Mobile/Tablet - General" when only the mobile hint is known
+            devicetype = Some(1);
+        }
+
+        let [_, os_matched, _] = self.matched();
+        let os = Some(self.os.family.clone()).filter(|_| os_matched);
+
+        OpenRtbDevice {
+            ua: user_agent.to_string(),
+            devicetype,
+            make: self.device.brand.clone(),
+            model: hints.model.or_else(|| self.device.model.clone()),
+            os: hints.platform.or(os),
+            osv: hints.platform_version.or_else(|| self.os.version()),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::Deserialize;
+use super::{Arch, Deserialize, Serialize};
 
 pub type Family = String;
 pub type Major = String;
@@ -7,14 +7,22 @@ pub type Patch = String;
 pub type PatchMinor = String;
 
 /// Describes the `Family` as well as the `Major`, `Minor`, `Patch`, and
-/// `PatchMinor` versions of an `OS`
-#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+/// `PatchMinor` versions of an `OS`, along with the `Arch` and bitness of the
+/// machine and the Linux distribution where the user agent reveals them
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "async-graphql", derive(async_graphql::SimpleObject))]
 pub struct OS {
     pub family: Family,
     pub major: Option<Major>,
     pub minor: Option<Minor>,
     pub patch: Option<Patch>,
     pub patch_minor: Option<PatchMinor>,
+    pub arch: Option<Arch>,
+    pub bitness: Option<u8>,
+    pub distro: Option<String>,
+    pub distro_version: Option<String>,
 }
 
 impl Default for OS {
@@ -25,6 +33,42 @@ impl Default for OS {
             minor: None,
             patch: None,
             patch_minor: None,
+            arch: None,
+            bitness: None,
+            distro: None,
+            distro_version: None,
         }
     }
 }
+
+impl OS {
+    /// Returns the operating system of user agents no rule matches
+    pub fn unknown() -> OS {
+        OS::default()
+    }
+
+    /// Returns whether the family is `Other`, as when no rule matched
+    pub fn is_unknown(&self) -> bool {
+        self.family == "Other"
+    }
+
+    /// Returns whether the family is unknown and no other field is set
+    pub fn is_empty(&self) -> bool {
+        *self == OS::default()
+    }
+
+    /// Joins the known version components with dots, e.g. `"10.15.7"`
+    pub fn version(&self) -> Option<String> {
+        join_version(&[&self.major, &self.minor, &self.patch, &self.patch_minor])
+    }
+}
+
+pub(crate) fn join_version(parts: &[&Option<String>]) -> Option<String> {
+    let parts: Vec<&str> = parts.iter().map_while(|part| part.as_deref()).collect();
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("."))
+    }
+}
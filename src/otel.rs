@@ -0,0 +1,70 @@
+use super::{Client, DeviceType};
+
+/// Value of an OpenTelemetry attribute produced by `Client::otel_attributes`
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum OtelValue {
+    String(String),
+    Bool(bool),
+}
+
+impl From<String> for OtelValue {
+    fn from(value: String) -> OtelValue {
+        OtelValue::String(value)
+    }
+}
+
+impl From<bool> for OtelValue {
+    fn from(value: bool) -> OtelValue {
+        OtelValue::Bool(value)
+    }
+}
+
+impl Client {
+    /// Converts this `Client` into attributes named after the OpenTelemetry
+    /// semantic conventions (`user_agent.*`, `browser.*`, `os.*` and
+    /// `device.*`). Attributes without a known value are left out, including
+    /// the families of categories no rule matched, and `user_agent.original`
+    /// is only emitted when the raw string is given.
+    pub fn otel_attributes(
+        &self,
+        original: Option<&str>,
+    ) -> Vec<(&'static str, OtelValue)> {
+        let mut attributes: Vec<(&'static str, OtelValue)> = Vec::new();
+        let mut push = |key: &'static str, value: Option<String>| {
+            if let Some(value) = value {
+                attributes.push((key, value.into()));
+            }
+        };
+
+        let [_, os_matched, user_agent_matched] = self.matched();
+        let user_agent_name =
+            Some(self.user_agent.family.clone()).filter(|_| user_agent_matched);
+        let os_name = Some(self.os.family.clone()).filter(|_| os_matched);
+
+        push("user_agent.original", original.map(ToString::to_string));
+        push("user_agent.name", user_agent_name);
+        push("user_agent.version", self.user_agent.version());
+        push("user_agent.os.name", os_name.clone());
+        push("user_agent.os.version", self.os.version());
+        push("os.name", os_name.clone());
+        push("os.version", self.os.version());
+        push("browser.platform", os_name);
+        push("device.manufacturer", self.device.brand.clone());
+        push("device.model.name", self.device.model.clone());
+
+        match self.device_type() {
+            DeviceType::Unknown | DeviceType::Bot => {}
+            device_type => {
+                let mobile = matches!(
+                    device_type,
+                    DeviceType::Smartphone
+                        | DeviceType::FeaturePhone
+                        | DeviceType::Tablet
+                );
+                attributes.push(("browser.mobile", mobile.into()));
+            }
+        }
+
+        attributes
+    }
+}
@@ -0,0 +1,144 @@
+use std::{
+    sync::{Arc, Mutex, OnceLock},
+    thread::{self, JoinHandle},
+};
+
+use super::{
+    Client, Device, Error, Fallbacks, Parser, UserAgent, UserAgentParser,
+    UserAgentParserBuilder, OS,
+};
+
+/// A parser that is usable right away while its regexes are still compiled on
+/// a background thread. Until then, every user agent is reported with the
+/// fallback families. The compiled parser is swapped in atomically once it's
+/// ready, so request paths never wait for the compilation.
+///
+/// ```rust
+/// # use uaparser::*;
+/// let regexes = std::fs::read("./src/core/regexes.yaml").unwrap();
+/// let parser = UserAgentParser::builder().build_in_background(regexes);
+///
+/// // Answers with fallbacks until the regexes are compiled
+/// let _client = parser.parse("Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Firefox/70.0");
+///
+/// parser.wait().expect("Parser creation failed");
+/// assert!(parser.is_ready());
+/// ```
+#[derive(Debug)]
+pub struct BackgroundParser {
+    parser: Arc<OnceLock<Result<UserAgentParser, Error>>>,
+    handle: Mutex<Option<JoinHandle<()>>>,
+    fallbacks: Fallbacks,
+}
+
+impl BackgroundParser {
+    pub(super) fn spawn<B>(builder: UserAgentParserBuilder, bytes: B) -> BackgroundParser
+    where
+        B: AsRef<[u8]> + Send + 'static,
+    {
+        let parser = Arc::new(OnceLock::new());
+        let fallbacks = builder.fallbacks.clone();
+
+        let handle = {
+            let parser = parser.clone();
+            thread::spawn(move || {
+                let _ = parser.set(builder.build_from_bytes(bytes.as_ref()));
+            })
+        };
+
+        BackgroundParser {
+            parser,
+            handle: Mutex::new(Some(handle)),
+            fallbacks,
+        }
+    }
+
+    /// Returns whether the regexes are compiled and used for parsing
+    pub fn is_ready(&self) -> bool {
+        matches!(self.parser.get(), Some(Ok(_)))
+    }
+
+    /// Returns the error of the background construction, if it failed
+    pub fn error(&self) -> Option<&Error> {
+        self.parser.get()?.as_ref().err()
+    }
+
+    /// Blocks until the background construction has finished and returns its
+    /// result
+    pub fn wait(&self) -> Result<&UserAgentParser, &Error> {
+        // The lock is held until the thread is joined, so that concurrent
+        // callers wait for it as well
+        let mut handle = self.handle.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(handle) = handle.take() {
+            handle.join().expect("parser construction panicked");
+        }
+        drop(handle);
+
+        self.parser
+            .get()
+            .expect("parser construction finished")
+            .as_ref()
+    }
+
+    fn ready(&self) -> Option<&UserAgentParser> {
+        self.parser.get()?.as_ref().ok()
+    }
+}
+
+impl Parser for BackgroundParser {
+    fn parse(&self, user_agent: &str) -> Client {
+        match self.ready() {
+            Some(parser) => parser.parse(user_agent),
+            None => Client {
+                device: self.parse_device(user_agent),
+                os: self.parse_os(user_agent),
+                user_agent: self.parse_user_agent(user_agent),
+                match_info: None,
+            },
+        }
+    }
+
+    fn parse_matched(&self, user_agent: &str) -> (Client, [bool; 3]) {
+        match self.ready() {
+            Some(parser) => parser.parse_matched(user_agent),
+            None => (self.parse(user_agent), [false; 3]),
+        }
+    }
+
+    fn parse_device(&self, user_agent: &str) -> Device {
+        match self.ready() {
+            Some(parser) => parser.parse_device(user_agent),
+            None => Device {
+                family: self.fallbacks.device.clone(),
+                ..Device::default()
+            },
+        }
+    }
+
+    fn parse_os(&self, user_agent: &str) -> OS {
+        match self.ready() {
+            Some(parser) => parser.parse_os(user_agent),
+            None => OS {
+                family: self.fallbacks.os.clone(),
+                ..OS::default()
+            },
+        }
+    }
+
+    fn parse_user_agent(&self, user_agent: &str) -> UserAgent {
+        match self.ready() {
+            Some(parser) => parser.parse_user_agent(user_agent),
+            None => UserAgent {
+                family: self.fallbacks.user_agent.clone(),
+                ..UserAgent::default()
+            },
+        }
+    }
+
+    fn parse_into(&self, user_agent: &str, client: &mut Client) {
+        match self.ready() {
+            Some(parser) => parser.parse_into(user_agent, client),
+            None => *client = self.parse(user_agent),
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::cmp::Reverse;
+
+use regex_syntax::hir::{Class, Hir, HirKind};
+
+use super::{
+    device, os, stream::Matchers, user_agent, Category, Error, HeapSize, Pattern,
+    RuleError, RuleHits,
+};
+
+/// The estimated memory of a state of a compiled regex
+const STATE_BYTES: usize = 16;
+
+/// The estimated memory of a compiled regex besides its states
+const REGEX_BYTES: usize = 1 << 10;
+
+/// The rules kept within a memory budget, see
+/// `UserAgentParserBuilder::memory_budget`
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct Fitted {
+    /// The estimated memory of the kept rules
+    pub bytes: usize,
+    pub dropped: usize,
+}
+
+/// Drops the last rules of the categories until the estimated memory of the
+/// rest fits into `budget`, and compiles the rules that are kept. Of the last
+/// rules of all categories, the one with the fewest `hits`, or else the one
+/// furthest down its category, is dropped first. Dropping only the last
+/// rules ensures that no later rule claims the user agents of a dropped one,
+/// so they get the fallback instead.
+pub(super) fn fit(
+    matchers: &mut Matchers,
+    budget: usize,
+    hits: Option<&RuleHits>,
+) -> Result<Fitted, Error> {
+    let costs = [
+        costs_of(&matchers.user_agent, user_agent::Matcher::pattern),
+        costs_of(&matchers.os, os::Matcher::pattern),
+        costs_of(&matchers.device, device::Matcher::pattern),
+    ];
+
+    // Hits are recorded by the position of a rule in the dataset
+    let indices = &matchers.indices;
+    let hits_of = |category: Category, position: usize| {
+        let index = indices[category as usize][position];
+        (hits.zip(index))
+            .and_then(|(hits, index)| hits.get(category).get(index).copied())
+            .unwrap_or(0)
+    };
+
+    let mut kept = [costs[0].len(), costs[1].len(), costs[2].len()];
+    let mut fitted = Fitted {
+        bytes: costs.iter().flatten().sum(),
+        dropped: 0,
+    };
+    while fitted.bytes > budget {
+        let last = [Category::UserAgent, Category::Os, Category::Device]
+            .iter()
+            .filter(|&&category| kept[category as usize] > 0)
+            .map(|&category| (category, kept[category as usize] - 1))
+            .min_by_key(|&(category, position)| {
+                (hits_of(category, position), Reverse(position))
+            });
+
+        let (category, position) = match last {
+            Some(last) => last,
+            None => break,
+        };
+        kept[category as usize] = position;
+        fitted.bytes -= costs[category as usize][position];
+        fitted.dropped += 1;
+    }
+
+    let [user_agent, os, device] = &mut matchers.indices;
+    user_agent.truncate(kept[Category::UserAgent as usize]);
+    os.truncate(kept[Category::Os as usize]);
+    device.truncate(kept[Category::Device as usize]);
+
+    matchers.user_agent.truncate(user_agent.len());
+    compile(
+        Category::UserAgent,
+        &mut matchers.user_agent,
+        user_agent,
+        |m| m.pattern_mut(),
+    )?;
+    matchers.os.truncate(os.len());
+    compile(Category::Os, &mut matchers.os, os, |m| m.pattern_mut())?;
+    matchers.device.truncate(device.len());
+    compile(Category::Device, &mut matchers.device, device, |m| {
+        m.pattern_mut()
+    })?;
+
+    Ok(fitted)
+}
+
+/// Estimates the memory of all of `matchers`, including their compiled
+/// regexes, see `UserAgentParser::memory_usage`
+pub(super) fn cost<M: HeapSize>(
+    matchers: &[M],
+    pattern: impl Fn(&M) -> &Pattern,
+) -> usize {
+    costs_of(matchers, pattern).iter().sum()
+}
+
+/// Estimates the memory of every matcher, including its compiled regex
+fn costs_of<M: HeapSize>(matchers: &[M], pattern: impl Fn(&M) -> &Pattern) -> Vec<usize> {
+    (matchers.iter())
+        .map(|matcher| {
+            // A deferred source is replaced by the compiled regex
+            let pattern = pattern(matcher);
+            let regex = estimate(pattern.source());
+            std::mem::size_of::<M>() + matcher.heap_size() - pattern.deferred_size()
+                + regex
+        })
+        .collect()
+}
+
+/// Compiles the deferred patterns of `matchers`, attributing errors to the
+/// position of the rule in the dataset, if it has one
+fn compile<M>(
+    category: Category,
+    matchers: &mut [M],
+    indices: &[Option<usize>],
+    pattern: impl Fn(&mut M) -> &mut Pattern,
+) -> Result<(), Error> {
+    for (position, matcher) in matchers.iter_mut().enumerate() {
+        let index = indices[position].unwrap_or(position);
+        RuleError::wrap(pattern(matcher).compile(), category, index)?;
+    }
+    Ok(())
+}
+
+/// Estimates the memory of `regex` once compiled from the size of its syntax
+/// tree, without compiling it. Regexes using syntax beyond the `regex` crate,
+/// like look-around, are estimated from their length.
+fn estimate(regex: &str) -> usize {
+    let states = match regex_syntax::Parser::new().parse(regex) {
+        Ok(hir) => states(&hir),
+        Err(_) => regex.len() * 8,
+    };
+    REGEX_BYTES + states * STATE_BYTES
+}
+
+/// Approximates the number of states `hir` compiles to. Unicode classes take
+/// several states per range for their UTF-8 encodings, and bounded
+/// repetitions repeat their states.
+fn states(hir: &Hir) -> usize {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => 1,
+        HirKind::Literal(literal) => literal.0.len(),
+        HirKind::Class(Class::Unicode(class)) => class.ranges().len() * 4,
+        HirKind::Class(Class::Bytes(class)) => class.ranges().len(),
+        HirKind::Repetition(repetition) => {
+            let copies = repetition.max.unwrap_or(repetition.min + 1).clamp(1, 1000);
+            states(&repetition.sub) * copies as usize
+        }
+        HirKind::Capture(capture) => states(&capture.sub) + 2,
+        HirKind::Concat(hirs) | HirKind::Alternation(hirs) => {
+            hirs.iter().map(states).sum::<usize>() + 1
+        }
+    }
+}
@@ -0,0 +1,1035 @@
+use std::{borrow::Cow, sync::Arc, time::Instant};
+
+use serde_derive::Deserialize;
+
+#[cfg(any(feature = "json", feature = "toml"))]
+use super::stream::{streamed, DatasetSeed, Failure};
+#[cfg(feature = "unicode")]
+use super::UnicodeOptions;
+use super::{
+    background::BackgroundParser,
+    budget, device, os,
+    stream::{compile_all, read_documents, Fragment, Matchers},
+    template::{PostProcess, Template},
+    translate::ascii_classes,
+    user_agent, Captures, Category, Compute, ComputedRules, Device, Error, FamilyAliases,
+    Limits, LoadReport, Pattern, Prefilter, Profiler, RegexFile, RuleEntry, RuleError,
+    RuleHits, RuleReport, Translator, UserAgent, UserAgentParser, OS,
+};
+
+/// Rules for KaiOS and feature phones, see `feature_phones`
+static FEATURE_PHONES: &str = include_str!("../feature_phones.yaml");
+
+/// The families reported for each category when no rule matches
+#[derive(Clone, Debug)]
+pub(super) struct Fallbacks {
+    pub device: String,
+    pub os: String,
+    pub user_agent: String,
+}
+
+impl Default for Fallbacks {
+    fn default() -> Fallbacks {
+        Fallbacks {
+            device: "Other".to_owned(),
+            os: "Other".to_owned(),
+            user_agent: "Other".to_owned(),
+        }
+    }
+}
+
+/// Decides whether a rule is kept, see `UserAgentParserBuilder::filter_rules`
+#[derive(Clone)]
+pub(super) struct RuleFilter(Arc<FilterFn>);
+
+type FilterFn = dyn Fn(Category, &RuleEntry) -> bool + Send + Sync;
+
+impl std::fmt::Debug for RuleFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("RuleFilter")
+    }
+}
+
+/// The size limit of device and user agent rules, unless configured otherwise
+const DEFAULT_SIZE_LIMIT: usize = 20 * (1 << 20);
+
+/// How the regexes of the rules of one category are compiled, see
+/// `UserAgentParserBuilder::regex_config`. Unset limits fall back to those
+/// configured for all categories.
+///
+/// ```rust
+/// # use uaparser::*;
+/// let parser = UserAgentParser::builder()
+///     .regex_config(
+///         Category::Device,
+///         RegexConfig::new().size_limit(2 << 20).unicode(false),
+///     )
+///     .build_from_yaml("./src/core/regexes.yaml")?;
+/// # Ok::<_, Error>(())
+/// ```
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RegexConfig {
+    size_limit: Option<usize>,
+    dfa_size_limit: Option<usize>,
+    unicode: bool,
+}
+
+impl Default for RegexConfig {
+    fn default() -> RegexConfig {
+        RegexConfig {
+            size_limit: None,
+            dfa_size_limit: None,
+            unicode: true,
+        }
+    }
+}
+
+impl RegexConfig {
+    pub fn new() -> RegexConfig {
+        RegexConfig::default()
+    }
+
+    /// Limits the size of every compiled rule, in bytes, like
+    /// `UserAgentParserBuilder::size_limit`
+    pub fn size_limit(mut self, bytes: usize) -> RegexConfig {
+        self.size_limit = Some(bytes);
+        self
+    }
+
+    /// Limits the cache of the lazy DFA of every rule, in bytes. Matching
+    /// still succeeds once it's full, though more slowly.
+    pub fn dfa_size_limit(mut self, bytes: usize) -> RegexConfig {
+        self.dfa_size_limit = Some(bytes);
+        self
+    }
+
+    /// Whether `\d`, `\w` and `\s` match Unicode characters, as they do by
+    /// default. Restricting them to ASCII makes rules considerably smaller,
+    /// and user agents rarely contain other characters.
+    pub fn unicode(mut self, enabled: bool) -> RegexConfig {
+        self.unicode = enabled;
+        self
+    }
+}
+
+/// Settings applied to every rule as it's compiled
+#[derive(Clone, Debug)]
+pub(super) struct RuleOptions {
+    pub case_insensitive: bool,
+    pub post_process: Arc<[PostProcess]>,
+    pub filters: Vec<RuleFilter>,
+    pub size_limit: Option<usize>,
+    /// The configuration of each category, in the order device, OS and user
+    /// agent
+    pub regex: [RegexConfig; 3],
+    pub translator: Translator,
+    /// Whether regexes are only compiled once the rules within the memory
+    /// budget are known
+    pub defer: bool,
+}
+
+impl Default for RuleOptions {
+    fn default() -> RuleOptions {
+        RuleOptions {
+            case_insensitive: false,
+            post_process: Arc::new([PostProcess::Trim]),
+            filters: Vec::new(),
+            size_limit: None,
+            regex: [RegexConfig::default(); 3],
+            translator: Translator::default(),
+            defer: false,
+        }
+    }
+}
+
+impl RuleOptions {
+    /// Returns whether `entry` passes every filter
+    pub fn keep(&self, category: Category, entry: RuleEntry) -> bool {
+        self.filters
+            .iter()
+            .all(|filter| (filter.0)(category, &entry))
+    }
+
+    pub fn template(&self, replacement: &str) -> Template {
+        Template::parse(replacement, &self.post_process)
+    }
+
+    /// Compiles the `regex` of a rule of `category` after translating it,
+    /// along with its inline `flags`
+    pub fn pattern(
+        &self,
+        category: Category,
+        regex: Cow<'_, str>,
+        flags: Option<&str>,
+    ) -> Result<Pattern, fancy_regex::Error> {
+        let config = self.regex_config(category);
+        let (mut regex, translation) = self.translator.translate(regex);
+        if !config.unicode {
+            regex = Cow::Owned(ascii_classes(&regex));
+        }
+
+        // OS rules are only limited by the regex engine's default
+        let default_size_limit = match category {
+            Category::Os => None,
+            Category::Device | Category::UserAgent => Some(DEFAULT_SIZE_LIMIT),
+        };
+        let limits = Limits {
+            size: config.size_limit.or(self.size_limit).or(default_size_limit),
+            dfa_size: config.dfa_size_limit,
+        };
+
+        let regex = self.regex(regex, flags);
+        let pattern = if self.defer {
+            Pattern::deferred(regex, limits)
+        } else {
+            Pattern::new(regex, limits)?
+        };
+        Ok(pattern.translated(translation))
+    }
+
+    fn regex_config(&self, category: Category) -> &RegexConfig {
+        &self.regex[config_index(category)]
+    }
+
+    /// Prepends the inline `flags` of a rule, along with any implied by the
+    /// options, to its `regex`
+    fn regex<'a>(&self, regex: Cow<'a, str>, flags: Option<&str>) -> Cow<'a, str> {
+        let mut flags = flags.unwrap_or_default().to_owned();
+        if self.case_insensitive && !flags.contains('i') {
+            flags.push('i');
+        }
+
+        if flags.is_empty() {
+            regex
+        } else {
+            Cow::Owned(format!("(?{}){}", flags, regex))
+        }
+    }
+}
+
+fn config_index(category: Category) -> usize {
+    match category {
+        Category::Device => 0,
+        Category::Os => 1,
+        Category::UserAgent => 2,
+    }
+}
+
+/// Configures a `UserAgentParser` before loading the regexes into it
+///
+/// ```rust
+/// # use uaparser::*;
+/// let parser = UserAgentParser::builder()
+///     .fallback_family("Unknown")
+///     .device_fallback("")
+///     .build_from_yaml("./src/core/regexes.yaml")
+///     .expect("Parser creation failed");
+///
+/// assert_eq!(parser.parse_user_agent("").family, "Unknown");
+/// assert_eq!(parser.parse_device("").family, "");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct UserAgentParserBuilder {
+    pub(super) fallbacks: Fallbacks,
+    match_info: bool,
+    dataset: Option<String>,
+    diagnostics: bool,
+    #[cfg(feature = "prefilter")]
+    prefilter: bool,
+    normalize_models: bool,
+    aliases: FamilyAliases,
+    safari_from_webkit: bool,
+    chromium_version: bool,
+    apps: bool,
+    frameworks: bool,
+    voice_assistants: bool,
+    desktop_shortcut: bool,
+    feature_phones: bool,
+    memory_budget: Option<usize>,
+    budget_hits: Option<RuleHits>,
+    profile: Option<u32>,
+    rules: RuleOptions,
+    computed: ComputedRules,
+    #[cfg(feature = "unicode")]
+    unicode: UnicodeOptions,
+}
+
+impl UserAgentParserBuilder {
+    /// Sets the family reported in every category when no rule matches,
+    /// instead of `"Other"`
+    pub fn fallback_family(mut self, family: impl Into<String>) -> Self {
+        let family = family.into();
+        self.fallbacks.device = family.clone();
+        self.fallbacks.os = family.clone();
+        self.fallbacks.user_agent = family;
+        self
+    }
+
+    /// Sets the `Device` family reported when no rule matches
+    pub fn device_fallback(mut self, family: impl Into<String>) -> Self {
+        self.fallbacks.device = family.into();
+        self
+    }
+
+    /// Sets the `OS` family reported when no rule matches
+    pub fn os_fallback(mut self, family: impl Into<String>) -> Self {
+        self.fallbacks.os = family.into();
+        self
+    }
+
+    /// Sets the `UserAgent` family reported when no rule matches
+    pub fn user_agent_fallback(mut self, family: impl Into<String>) -> Self {
+        self.fallbacks.user_agent = family.into();
+        self
+    }
+
+    /// Attaches a `MatchInfo` to every parsed `Client`, attributing its
+    /// fields to the rules they came from. Off by default, as it costs an
+    /// allocation per parse.
+    pub fn match_info(mut self, enabled: bool) -> Self {
+        self.match_info = enabled;
+        self
+    }
+
+    /// Computes the checksum of `dataset` along with the options of this
+    /// builder and the version of the crate, for `DiskCache::open`. Rules
+    /// added with `device_rule` and the like, and filters of `filter_rules`,
+    /// only contribute their regexes and number, not what their closures do.
+    #[cfg(feature = "disk-cache")]
+    pub fn checksum(&self, dataset: &[u8]) -> u64 {
+        // The aliases are kept in hash maps, whose order changes between runs
+        let aliases = [
+            &self.aliases.user_agent,
+            &self.aliases.os,
+            &self.aliases.device,
+        ]
+        .map(|aliases| aliases.iter().collect::<std::collections::BTreeMap<_, _>>());
+        let options = UserAgentParserBuilder {
+            aliases: FamilyAliases::default(),
+            ..self.clone()
+        };
+
+        let description =
+            format!("{} {:?} {:?}", env!("CARGO_PKG_VERSION"), options, aliases);
+        crate::dataset_checksum(&[dataset, description.as_bytes()].concat())
+    }
+
+    /// Sets the identifier of the dataset reported in `MatchInfo`, e.g. the
+    /// uap-core release the regexes were taken from
+    pub fn dataset_id(mut self, id: impl Into<String>) -> Self {
+        self.dataset = Some(id.into());
+        self
+    }
+
+    /// Compiles every rule case-insensitively. This trades strictness for
+    /// robustness against user agents with unusual casing, as sent by some
+    /// SDKs and embedded devices. Captured values keep their original casing.
+    pub fn case_insensitive(mut self, enabled: bool) -> Self {
+        self.rules.case_insensitive = enabled;
+        self
+    }
+
+    /// Limits the size of every compiled rule, in bytes. Rules exceeding it
+    /// fail to compile. By default, device and user agent rules are limited
+    /// to 20 MiB, and OS rules only by the regex engine's own default.
+    pub fn size_limit(mut self, bytes: usize) -> Self {
+        self.rules.size_limit = Some(bytes);
+        self
+    }
+
+    /// Configures how the rules of `category` are compiled, taking
+    /// precedence over `size_limit`. The device rules outnumber the others by
+    /// far, so they often warrant tighter limits.
+    pub fn regex_config(mut self, category: Category, config: RegexConfig) -> Self {
+        self.rules.regex[config_index(category)] = config;
+        self
+    }
+
+    /// Sets the steps applied, in order, to values produced by replacements
+    /// that refer to capture groups, such as `$1 Mobile`. Defaults to
+    /// `[PostProcess::Trim]`; an empty chain preserves whitespace exactly.
+    pub fn post_process(mut self, steps: impl IntoIterator<Item = PostProcess>) -> Self {
+        self.rules.post_process = steps.into_iter().collect();
+        self
+    }
+
+    /// Strips noise like `Build/` suffixes, locale tokens and repeated brands
+    /// from device models, see `normalize_model`
+    pub fn normalize_models(mut self, enabled: bool) -> Self {
+        self.normalize_models = enabled;
+        self
+    }
+
+    /// Renames families in results according to `aliases`, e.g.
+    /// `FamilyAliases::consolidated()`. Aliases apply after the fallback
+    /// families, so those can be renamed too.
+    pub fn family_aliases(mut self, aliases: FamilyAliases) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// Fills in the version of Safari user agents that don't state one, such
+    /// as those of older web views, from their `AppleWebKit` build where the
+    /// build is known to belong to a single Safari release. See
+    /// `safari_version_from_webkit`.
+    pub fn safari_from_webkit(mut self, enabled: bool) -> Self {
+        self.safari_from_webkit = enabled;
+        self
+    }
+
+    /// Fills in `UserAgent::chromium_version` with the version of Chromium
+    /// that browsers based on it, like Samsung Internet or Opera, state
+    /// alongside their own
+    pub fn detect_chromium_version(mut self, enabled: bool) -> Self {
+        self.chromium_version = enabled;
+        self
+    }
+
+    /// Fills in `UserAgent::app` and `UserAgent::runtime` for desktop apps
+    /// built with Electron, Tauri or CEF, e.g. `Slack 4.36` on `Electron 27`,
+    /// which the dataset reports as the browser they embed
+    pub fn detect_apps(mut self, enabled: bool) -> Self {
+        self.apps = enabled;
+        self
+    }
+
+    /// Fills in `UserAgent::framework` with the networking stack or framework
+    /// of native apps, like `okhttp`, `CFNetwork`, `Alamofire` or `Dart`
+    pub fn detect_frameworks(mut self, enabled: bool) -> Self {
+        self.frameworks = enabled;
+        self
+    }
+
+    /// Reports requests of voice assistants and their content fetchers as the
+    /// smart speaker of the platform, e.g. `Amazon Echo`, which are classified
+    /// as `DeviceType::SmartSpeaker`. See `Client::voice_platform`.
+    pub fn detect_voice_assistants(mut self, enabled: bool) -> Self {
+        self.voice_assistants = enabled;
+        self
+    }
+
+    /// Skips the device rules for user agents of desktop browsers on Windows
+    /// and Linux that carry no token of a mobile device, console, TV or bot,
+    /// and reports the fallback device right away. Most device rules are
+    /// tried in vain for these, so this saves a large share of the parsing
+    /// time of typical traffic, at the cost of missing the odd device rule
+    /// that would have matched anyway.
+    pub fn desktop_shortcut(mut self, enabled: bool) -> Self {
+        self.desktop_shortcut = enabled;
+        self
+    }
+
+    /// Adds rules for KaiOS and common feature phones, like J2ME handsets and
+    /// the JioPhone, ahead of the rules of the dataset. These are classified
+    /// as `DeviceType::FeaturePhone`. The added rules have no index in
+    /// `MatchInfo`, so indices of the dataset's rules are unaffected.
+    pub fn feature_phones(mut self, enabled: bool) -> Self {
+        self.feature_phones = enabled;
+        self
+    }
+
+    /// NFC-normalizes every output field, so that values captured with
+    /// decomposed characters group together with their composed equivalents
+    #[cfg(feature = "unicode")]
+    pub fn normalize_unicode(mut self, enabled: bool) -> Self {
+        self.unicode.nfc = enabled;
+        self
+    }
+
+    /// Folds output fields towards ASCII by replacing compatibility characters,
+    /// such as fullwidth letters, and stripping diacritics. Characters without
+    /// an ASCII counterpart are kept. Implies `normalize_unicode`.
+    #[cfg(feature = "unicode")]
+    pub fn ascii_fold(mut self, enabled: bool) -> Self {
+        self.unicode.ascii_fold = enabled;
+        self
+    }
+
+    /// Records the compile time and size of every rule in a `LoadReport`,
+    /// available from `UserAgentParser::load_report`. Every rule is compiled
+    /// several more times to measure it, even with the `lazy` feature, so
+    /// this is meant for tuning datasets rather than for production use.
+    pub fn diagnostics(mut self, enabled: bool) -> Self {
+        self.diagnostics = enabled;
+        self
+    }
+
+    /// Times every rule evaluated in one of every `every` category parses, and
+    /// counts its matches, available from `UserAgentParser::profile_report`.
+    /// This finds the rules dominating the latency of production traffic at
+    /// the cost of a clock read per evaluated rule in sampled parses.
+    pub fn profile(mut self, every: u32) -> Self {
+        self.profile = Some(every);
+        self
+    }
+
+    /// Drops every rule for which `filter` returns `false` while the dataset
+    /// is loaded, so that specialized workloads don't pay for rules they never
+    /// need. Repeated calls add filters that must all pass. The kept rules
+    /// are still identified by their position in the dataset, e.g. in
+    /// `MatchInfo` and `RuleHits`.
+    ///
+    /// ```rust
+    /// # use uaparser::*;
+    /// let parser = UserAgentParser::builder()
+    ///     .filter_rules(|category, entry| {
+    ///         category != Category::Device || !entry.regex().contains("SmartTV")
+    ///     })
+    ///     .build_from_yaml("./src/core/regexes.yaml")
+    ///     .expect("Parser creation failed");
+    /// ```
+    pub fn filter_rules<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(Category, &RuleEntry) -> bool + Send + Sync + 'static,
+    {
+        self.rules.filters.push(RuleFilter(Arc::new(filter)));
+        self
+    }
+
+    /// Keeps only the rules tagged with at least one of `tags`. Rules are
+    /// tagged with an optional `tags` list in the dataset, so that a single
+    /// annotated dataset can serve differently scoped parsers:
+    ///
+    /// ```yaml
+    /// device_parsers:
+    ///   - regex: 'SMART-TV'
+    ///     device_replacement: 'Samsung SmartTV'
+    ///     tags: [tv, samsung]
+    /// ```
+    pub fn with_tags<I, S>(self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let tags: Vec<String> = tags.into_iter().map(Into::into).collect();
+        self.filter_rules(move |_, entry| entry.tags().iter().any(|t| tags.contains(t)))
+    }
+
+    /// Drops the rules tagged with any of `tags`, see `with_tags`
+    pub fn without_tags<I, S>(self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let tags: Vec<String> = tags.into_iter().map(Into::into).collect();
+        self.filter_rules(move |_, entry| !entry.tags().iter().any(|t| tags.contains(t)))
+    }
+
+    /// Rewrites the regexes of the dataset that were written for PCRE, as
+    /// those of uap-core are, where the `regex` crate would reject or
+    /// misinterpret them. Superfluous escapes like `\!` and `\/` are dropped,
+    /// possessive quantifiers like `a++` become atomic groups like `(?>a+)`,
+    /// unknown inline flags are dropped and `\Z` becomes `\z`. The rewritten
+    /// rules are listed by `UserAgentParser::translations`.
+    pub fn translate_pcre(mut self, enabled: bool) -> Self {
+        self.rules.translator.pcre = enabled;
+        self
+    }
+
+    /// Replaces every occurrence of `from` in the regexes of the dataset with
+    /// `to`, before they're translated, for constructs the translation
+    /// doesn't cover
+    ///
+    /// ```rust
+    /// # use uaparser::*;
+    /// let parser = UserAgentParser::builder()
+    ///     .regex_rewrite(r"\h", r"[ \t]")
+    ///     .build_from_yaml("./src/core/regexes.yaml")
+    ///     .expect("Parser creation failed");
+    /// ```
+    pub fn regex_rewrite(
+        mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+    ) -> Self {
+        let mut custom = self.rules.translator.custom.to_vec();
+        custom.push((from.into(), to.into()));
+        self.rules.translator.custom = custom.into();
+        self
+    }
+
+    /// Adds a device rule whose result is computed by `compute` from the
+    /// captures of `regex`, for logic templates can't express, like deriving
+    /// the brand from the model. Added rules of every category are evaluated
+    /// before the rules of the dataset, in the order they were added.
+    ///
+    /// ```rust
+    /// # use uaparser::*;
+    /// let parser = UserAgentParser::builder()
+    ///     .device_rule(r"; (SM-[A-Z]\d+[A-Z]?)", |captures| Device {
+    ///         family: "Samsung Galaxy".to_owned(),
+    ///         brand: Some("Samsung".to_owned()),
+    ///         model: captures.get(1).map(str::to_owned),
+    ///         ..Device::default()
+    ///     })
+    ///     .build_from_yaml("./src/core/regexes.yaml")?;
+    ///
+    /// let device = parser.parse_device("Mozilla/5.0 (Linux; Android 11; SM-G991B)");
+    /// assert_eq!(device.model.as_deref(), Some("SM-G991B"));
+    /// # Ok::<_, Error>(())
+    /// ```
+    pub fn device_rule<F>(mut self, regex: impl Into<String>, compute: F) -> Self
+    where
+        F: Fn(&Captures) -> Device + Send + Sync + 'static,
+    {
+        (self.computed.device).push((regex.into(), Compute::new(compute)));
+        self
+    }
+
+    /// Adds an operating system rule whose result is computed by `compute`,
+    /// see `device_rule`
+    pub fn os_rule<F>(mut self, regex: impl Into<String>, compute: F) -> Self
+    where
+        F: Fn(&Captures) -> OS + Send + Sync + 'static,
+    {
+        (self.computed.os).push((regex.into(), Compute::new(compute)));
+        self
+    }
+
+    /// Adds a user agent rule whose result is computed by `compute`, see
+    /// `device_rule`
+    pub fn user_agent_rule<F>(mut self, regex: impl Into<String>, compute: F) -> Self
+    where
+        F: Fn(&Captures) -> UserAgent + Send + Sync + 'static,
+    {
+        (self.computed.user_agent).push((regex.into(), Compute::new(compute)));
+        self
+    }
+
+    /// Scans every user agent once with a single `RegexSet` over the rules of
+    /// all categories, and only evaluates the rules it matched, instead of
+    /// trying every rule of every category in turn. This speeds up parsing
+    /// considerably at the cost of a slower build and more memory. Rules the
+    /// set can't express, such as those using look-around, are always tried.
+    /// If the set exceeds the size limits of the `regex` crate, the parser is
+    /// built without it.
+    #[cfg(feature = "prefilter")]
+    pub fn prefilter(mut self, enabled: bool) -> Self {
+        self.prefilter = enabled;
+        self
+    }
+
+    /// Caps the estimated memory of the compiled rules at `bytes`. Instead of
+    /// failing, the build drops the last rules of the categories until the
+    /// rest fit, so user agents of dropped rules get the fallback rather than
+    /// the result of a later, more generic rule. The memory of every rule is
+    /// estimated from its regex before anything is compiled, and only the
+    /// kept rules are compiled. The prefilter is built over the kept rules.
+    /// See `UserAgentParser::memory_usage` for the outcome.
+    pub fn memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = Some(bytes);
+        self.rules.defer = true;
+        self
+    }
+
+    /// Decides which category gives up its last rule first when the
+    /// `memory_budget` forces rules to be dropped: the one whose last rule
+    /// matched least often, e.g. as recorded from production traffic with
+    /// `UserAgentParser::rule_hits`
+    pub fn budget_hits(mut self, hits: RuleHits) -> Self {
+        self.budget_hits = Some(hits);
+        self
+    }
+
+    /// Builds the parser from the path to a `regexes.yaml` file. The dataset
+    /// may consist of several YAML documents and list further files under a
+    /// top-level `include` key, resolved relative to the including file.
+    /// Rules of included files come before the rules of the including
+    /// document, and each category must appear in at least one document.
+    ///
+    /// ```yaml
+    /// include:
+    ///   - vendors/apple.yaml
+    ///   - vendors/samsung.yaml
+    /// user_agent_parsers:
+    ///   - regex: 'MyApp/(\d+)'
+    /// ```
+    #[cfg(feature = "fs")]
+    pub fn build_from_yaml(self, path: &str) -> Result<UserAgentParser, Error> {
+        self.build_from_path(std::path::Path::new(path))
+    }
+
+    #[cfg(feature = "fs")]
+    pub(crate) fn build_from_path(
+        self,
+        path: &std::path::Path,
+    ) -> Result<UserAgentParser, Error> {
+        let start = Instant::now();
+        let dataset = self.read_file(path, &mut Vec::new())?;
+        self.finish(start, dataset.finish::<serde_yaml::Error>()?)
+    }
+
+    /// Builds the parser from an open `regexes.yaml` file. Every rule is
+    /// compiled as soon as it's deserialized, so the entries of the dataset
+    /// aren't collected next to the compiled rules. The YAML parser still
+    /// reads the whole file into memory before the first rule is compiled.
+    /// Since the location of the file is unknown, it can't include others,
+    /// see `build_from_yaml`.
+    #[cfg(feature = "fs")]
+    pub fn build_from_file(self, file: std::fs::File) -> Result<UserAgentParser, Error> {
+        let start = Instant::now();
+        let deserializer = serde_yaml::Deserializer::from_reader(file);
+        let dataset = read_documents(deserializer, &self.rules, &mut no_include)?;
+        self.finish(start, dataset.finish::<serde_yaml::Error>()?)
+    }
+
+    /// Builds the parser from the raw bytes of a `regexes.yaml` file. Every
+    /// rule is compiled as soon as it's deserialized, see `build_from_file`.
+    pub fn build_from_bytes(self, bytes: &[u8]) -> Result<UserAgentParser, Error> {
+        let start = Instant::now();
+        let deserializer = serde_yaml::Deserializer::from_slice(bytes);
+        let dataset = read_documents(deserializer, &self.rules, &mut no_include)?;
+        self.finish(start, dataset.finish::<serde_yaml::Error>()?)
+    }
+
+    /// Builds the parser from a `regexes.yaml` dataset converted to JSON,
+    /// which deserializes several times faster than the YAML original. The
+    /// conversion can happen at build time, e.g. with
+    /// `yq -o=json regexes.yaml > regexes.json`.
+    #[cfg(feature = "json")]
+    pub fn build_from_json(self, bytes: &[u8]) -> Result<UserAgentParser, Error> {
+        use serde::de::DeserializeSeed;
+
+        let start = Instant::now();
+        let failure = Failure::default();
+        let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+        let fragment = DatasetSeed {
+            failure: &failure,
+            options: &self.rules,
+        }
+        .deserialize(&mut deserializer)
+        .and_then(|fragment| deserializer.end().map(|()| fragment));
+
+        self.finish_document(start, fragment, failure)
+    }
+
+    /// Builds the parser from a `regexes.yaml` dataset written as TOML, with
+    /// an array of tables for every category:
+    ///
+    /// ```toml
+    /// [[user_agent_parsers]]
+    /// regex = 'MyApp/(\d+)'
+    /// family_replacement = 'My App'
+    /// ```
+    #[cfg(feature = "toml")]
+    pub fn build_from_toml_bytes(self, bytes: &[u8]) -> Result<UserAgentParser, Error> {
+        use serde::de::{DeserializeSeed, Error as _};
+
+        let start = Instant::now();
+        let text = std::str::from_utf8(bytes).map_err(toml::de::Error::custom)?;
+        let failure = Failure::default();
+        let fragment = DatasetSeed {
+            failure: &failure,
+            options: &self.rules,
+        }
+        .deserialize(&mut toml::Deserializer::new(text));
+
+        self.finish_document(start, fragment, failure)
+    }
+
+    /// Builds the parser from a reader over a TOML dataset, see
+    /// `build_from_toml_bytes`
+    #[cfg(feature = "toml")]
+    pub fn build_from_toml_reader<R: std::io::Read>(
+        self,
+        mut reader: R,
+    ) -> Result<UserAgentParser, Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        self.build_from_toml_bytes(&bytes)
+    }
+
+    /// Builds the parser from the path to a `regexes.yaml` file without
+    /// blocking the async runtime, by reading and compiling the dataset on
+    /// tokio's blocking thread pool, see `build_from_yaml`. Must be called
+    /// from within a tokio runtime.
+    #[cfg(all(feature = "async", feature = "fs"))]
+    pub async fn build_from_path_async(
+        self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<UserAgentParser, Error> {
+        let path = path.as_ref().to_owned();
+        blocking(move || self.build_from_path(&path)).await
+    }
+
+    /// Builds the parser from an async reader over a `regexes.yaml` file,
+    /// compiling the dataset on tokio's blocking thread pool. Must be called
+    /// from within a tokio runtime.
+    #[cfg(feature = "async")]
+    pub async fn build_from_reader_async<R>(
+        self,
+        mut reader: R,
+    ) -> Result<UserAgentParser, Error>
+    where
+        R: tokio::io::AsyncRead + Unpin,
+    {
+        use tokio::io::AsyncReadExt;
+
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        blocking(move || self.build_from_bytes(&bytes)).await
+    }
+
+    /// Returns a parser that can be used right away, while the raw bytes of a
+    /// `regexes.yaml` file are compiled on a background thread
+    pub fn build_in_background<B>(self, bytes: B) -> BackgroundParser
+    where
+        B: AsRef<[u8]> + Send + 'static,
+    {
+        BackgroundParser::spawn(self, bytes)
+    }
+
+    /// Builds the parser from a zlib compressed `regexes.yaml` file
+    #[cfg(feature = "compressed")]
+    pub fn build_from_compressed_bytes(
+        self,
+        bytes: &[u8],
+    ) -> Result<UserAgentParser, Error> {
+        let bytes = miniz_oxide::inflate::decompress_to_vec_zlib(bytes)?;
+        self.build_from_bytes(&bytes)
+    }
+
+    /// Builds the parser from an already deserialized `RegexFile`
+    pub fn build(self, regex_file: RegexFile) -> Result<UserAgentParser, Error> {
+        let start = Instant::now();
+        let fragment = Fragment {
+            device: Some(compile_all(
+                regex_file.device_parsers,
+                Category::Device,
+                &self.rules,
+            )?),
+            os: Some(compile_all(
+                regex_file.os_parsers,
+                Category::Os,
+                &self.rules,
+            )?),
+            user_agent: Some(compile_all(
+                regex_file.user_agent_parsers,
+                Category::UserAgent,
+                &self.rules,
+            )?),
+            include: Vec::new(),
+        };
+        self.finish(start, fragment.finish::<serde_yaml::Error>()?)
+    }
+
+    /// Reads the dataset at `path` along with the files it includes.
+    /// `stack` holds the files currently being read, to detect cycles.
+    #[cfg(feature = "fs")]
+    fn read_file(
+        &self,
+        path: &std::path::Path,
+        stack: &mut Vec<std::path::PathBuf>,
+    ) -> Result<Fragment, Error> {
+        let path = path.canonicalize()?;
+        if stack.contains(&path) {
+            return Err(Error::IO(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("`{}` includes itself", path.display()),
+            )));
+        }
+
+        let file = std::fs::File::open(&path)?;
+        let base = path.parent().map(|p| p.to_owned()).unwrap_or_default();
+        stack.push(path);
+
+        let deserializer = serde_yaml::Deserializer::from_reader(file);
+        let dataset = read_documents(deserializer, &self.rules, &mut |include| {
+            self.read_file(&base.join(include), stack)
+        });
+
+        stack.pop();
+        dataset
+    }
+
+    /// Finishes a dataset read as a single document in a format other than
+    /// YAML, where includes aren't supported
+    #[cfg(any(feature = "json", feature = "toml"))]
+    fn finish_document<E: Into<Error> + serde::de::Error>(
+        self,
+        start: Instant,
+        fragment: Result<Fragment, E>,
+        failure: Failure,
+    ) -> Result<UserAgentParser, Error> {
+        let fragment = streamed(fragment, failure)?;
+        if let Some(path) = fragment.include.first() {
+            no_include(path)?;
+        }
+        self.finish(start, fragment.finish::<E>().map_err(Into::into)?)
+    }
+
+    fn finish(
+        self,
+        start: Instant,
+        mut matchers: Matchers,
+    ) -> Result<UserAgentParser, Error> {
+        // Rules the parser adds itself come first and aren't part of the
+        // dataset, so they have no index in it
+        let [user_agent_indices, os_indices, device_indices] = &mut matchers.indices;
+        if self.feature_phones {
+            let deserializer = serde_yaml::Deserializer::from_str(FEATURE_PHONES);
+            let rules = read_documents(deserializer, &self.rules, &mut no_include)?;
+            let rules = rules.finish::<serde_yaml::Error>()?;
+            prepend(&mut matchers.device, device_indices, rules.device);
+            prepend(&mut matchers.os, os_indices, rules.os);
+            prepend(
+                &mut matchers.user_agent,
+                user_agent_indices,
+                rules.user_agent,
+            );
+        }
+
+        let computed = self.computed;
+        let options = &self.rules;
+        let rules = (computed.device.into_iter().enumerate())
+            .map(|(index, (regex, compute))| {
+                let matcher = device::Matcher::computed(regex, compute, options);
+                RuleError::wrap(matcher, Category::Device, index)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        prepend(&mut matchers.device, device_indices, rules);
+        let rules = (computed.os.into_iter().enumerate())
+            .map(|(index, (regex, compute))| {
+                let matcher = os::Matcher::computed(regex, compute, options);
+                RuleError::wrap(matcher, Category::Os, index)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        prepend(&mut matchers.os, os_indices, rules);
+        let rules = (computed.user_agent.into_iter().enumerate())
+            .map(|(index, (regex, compute))| {
+                let matcher = user_agent::Matcher::computed(regex, compute, options);
+                RuleError::wrap(matcher, Category::UserAgent, index)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        prepend(&mut matchers.user_agent, user_agent_indices, rules);
+
+        let fitted = match self.memory_budget {
+            Some(bytes) => Some(budget::fit(
+                &mut matchers,
+                bytes,
+                self.budget_hits.as_ref(),
+            )?),
+            None => None,
+        };
+
+        let patterns = || {
+            (matchers.user_agent.iter())
+                .map(|m| (Category::UserAgent, m.pattern()))
+                .chain(matchers.os.iter().map(|m| (Category::Os, m.pattern())))
+                .chain(
+                    matchers
+                        .device
+                        .iter()
+                        .map(|m| (Category::Device, m.pattern())),
+                )
+        };
+
+        #[cfg(feature = "prefilter")]
+        let prefilter = if self.prefilter {
+            Prefilter::new(patterns())
+        } else {
+            None
+        };
+        #[cfg(not(feature = "prefilter"))]
+        let prefilter: Option<Prefilter> = None;
+
+        // Taken before the rules are compiled again to measure them
+        let total_time = start.elapsed();
+        let load_report = if self.diagnostics {
+            Some(LoadReport {
+                rules: measure(patterns(), &matchers.indices),
+                total_time,
+            })
+        } else {
+            None
+        };
+
+        let profiler = self.profile.map(|every| {
+            let lens = [
+                matchers.user_agent.len(),
+                matchers.os.len(),
+                matchers.device.len(),
+            ];
+            Profiler::new(every, lens)
+        });
+
+        let [user_agent_indices, os_indices, device_indices] = matchers.indices;
+        Ok(UserAgentParser {
+            rule_indices: [
+                user_agent_indices.into_boxed_slice(),
+                os_indices.into_boxed_slice(),
+                device_indices.into_boxed_slice(),
+            ],
+            device_matchers: matchers.device.into_boxed_slice(),
+            os_matchers: matchers.os.into_boxed_slice(),
+            user_agent_matchers: matchers.user_agent.into_boxed_slice(),
+            fallbacks: self.fallbacks,
+            match_info: self.match_info,
+            dataset: self.dataset.map(Arc::from),
+            load_report,
+            prefilter,
+            normalize_models: self.normalize_models,
+            aliases: self.aliases,
+            safari_from_webkit: self.safari_from_webkit,
+            chromium_version: self.chromium_version,
+            apps: self.apps,
+            frameworks: self.frameworks,
+            voice_assistants: self.voice_assistants,
+            desktop_shortcut: self.desktop_shortcut,
+            dropped_rules: fitted.map_or(0, |fitted| fitted.dropped),
+            profiler,
+            #[cfg(feature = "unicode")]
+            unicode: self.unicode,
+        })
+    }
+}
+
+/// Runs `build` on tokio's blocking thread pool, resuming a panic within it
+#[cfg(feature = "async")]
+async fn blocking<F>(build: F) -> Result<UserAgentParser, Error>
+where
+    F: FnOnce() -> Result<UserAgentParser, Error> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(build).await {
+        Ok(result) => result,
+        Err(error) => std::panic::resume_unwind(error.into_panic()),
+    }
+}
+
+/// Rejects includes in datasets that weren't loaded from a path
+fn no_include(path: &str) -> Result<Fragment, Error> {
+    Err(Error::IO(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("cannot include `{}` without the path of the dataset", path),
+    )))
+}
+
+fn measure<'a>(
+    patterns: impl Iterator<Item = (Category, &'a Pattern)>,
+    indices: &[Vec<Option<usize>>; 3],
+) -> Vec<RuleReport> {
+    let mut positions = [0; 3];
+
+    patterns
+        .map(|(category, pattern)| {
+            let position = &mut positions[category as usize];
+            let (compile_time, compiled_size) = pattern.measure();
+            *position += 1;
+
+            RuleReport {
+                category,
+                index: indices[category as usize][*position - 1],
+                pattern: pattern.source().to_owned(),
+                compile_time,
+                compiled_size,
+            }
+        })
+        .collect()
+}
+
+/// Places `rules` before `matchers`, without an index in the dataset
+fn prepend<M>(matchers: &mut Vec<M>, indices: &mut Vec<Option<usize>>, rules: Vec<M>) {
+    indices.splice(0..0, rules.iter().map(|_| None));
+    matchers.splice(0..0, rules);
+}
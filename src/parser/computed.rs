@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use super::{group, Device, UserAgent, OS};
+
+/// The capture groups of a rule's regex, as passed to the rules added with
+/// `UserAgentParserBuilder::device_rule` and its siblings
+#[derive(Debug)]
+pub struct Captures<'a> {
+    captures: &'a fancy_regex::Captures<'a>,
+}
+
+impl<'a> Captures<'a> {
+    /// Returns the text of the group at `index`, where `0` is the whole
+    /// match. Groups that didn't participate or matched nothing are `None`,
+    /// as with templates.
+    pub fn get(&self, index: usize) -> Option<&'a str> {
+        group(self.captures, index)
+    }
+
+    /// Returns the number of groups, including the whole match
+    pub fn len(&self) -> usize {
+        self.captures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Computes the result of a rule from its captures
+pub(super) struct Compute<T>(Arc<ComputeFn<T>>);
+
+type ComputeFn<T> = dyn Fn(&Captures) -> T + Send + Sync;
+
+impl<T> Compute<T> {
+    pub fn new<F>(compute: F) -> Compute<T>
+    where
+        F: Fn(&Captures) -> T + Send + Sync + 'static,
+    {
+        Compute(Arc::new(compute))
+    }
+
+    pub fn apply(&self, captures: &fancy_regex::Captures) -> T {
+        (self.0)(&Captures { captures })
+    }
+}
+
+impl<T> Clone for Compute<T> {
+    fn clone(&self) -> Compute<T> {
+        Compute(self.0.clone())
+    }
+}
+
+impl<T> std::fmt::Debug for Compute<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("Compute")
+    }
+}
+
+/// The rules added to a builder, as regexes along with their computation
+#[derive(Clone, Debug, Default)]
+pub(super) struct ComputedRules {
+    pub device: Vec<(String, Compute<Device>)>,
+    pub os: Vec<(String, Compute<OS>)>,
+    pub user_agent: Vec<(String, Compute<UserAgent>)>,
+}
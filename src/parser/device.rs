@@ -7,74 +7,165 @@ pub enum Error {
 
 #[derive(Debug)]
 pub struct Matcher {
-    regex: fancy_regex::Regex,
-    device_replacement: Option<String>,
-    brand_replacement: Option<String>,
-    model_replacement: Option<String>,
+    regex: Pattern,
+    device_replacement: Option<Template>,
+    brand_replacement: Option<Template>,
+    model_replacement: Option<Template>,
+    type_replacement: Option<Template>,
+    compute: Option<Compute<Device>>,
 }
 
 impl SubParser for Matcher {
     type Item = Device;
 
     fn try_parse(&self, text: &str) -> Option<Self::Item> {
-        if let Ok(Some(captures)) = self.regex.captures(text) {
-            let family: String =
-                if let Some(device_replacement) = &self.device_replacement {
-                    replace(&device_replacement, &captures)
-                } else {
-                    captures
-                        .get(1)
-                        .map(|x| x.as_str())
-                        .and_then(none_if_empty)
-                        .map(ToString::to_string)?
-                };
-
-            let brand: Option<String> =
-                if let Some(brand_replacement) = &self.brand_replacement {
-                    none_if_empty(replace(&brand_replacement, &captures))
-                } else {
-                    None
-                };
-
-            let model: Option<String> =
-                if let Some(model_replacement) = &self.model_replacement {
-                    none_if_empty(replace(&model_replacement, &captures))
-                } else {
-                    captures
-                        .get(1)
-                        .map(|x| x.as_str())
-                        .and_then(none_if_empty)
-                        .map(ToString::to_string)
-                };
-
-            Some(Device {
-                family,
-                brand,
-                model,
-            })
+        let mut device = Device {
+            family: String::new(),
+            ..Device::default()
+        };
+
+        if self.try_parse_into(text, &mut device) {
+            Some(device)
         } else {
             None
         }
     }
+
+    fn try_parse_into(&self, text: &str, device: &mut Device) -> bool {
+        let captures = match self.regex.captures(text) {
+            Some(captures) => captures,
+            None => return false,
+        };
+
+        if let Some(compute) = &self.compute {
+            *device = compute.apply(&captures);
+            return true;
+        }
+
+        if let Some(device_replacement) = &self.device_replacement {
+            device.family.clear();
+            device_replacement.expand_into(&captures, &mut device.family);
+        } else {
+            match group(&captures, 1) {
+                Some(family) => set(&mut device.family, family),
+                None => return false,
+            }
+        }
+
+        if let Some(brand_replacement) = &self.brand_replacement {
+            replace_opt(&mut device.brand, brand_replacement, &captures);
+        } else {
+            device.brand = None;
+        }
+
+        if let Some(model_replacement) = &self.model_replacement {
+            replace_opt(&mut device.model, model_replacement, &captures);
+        } else {
+            set_opt(&mut device.model, group(&captures, 1));
+        }
+
+        if let Some(type_replacement) = &self.type_replacement {
+            replace_opt(&mut device.kind, type_replacement, &captures);
+        } else {
+            device.kind = None;
+        }
+
+        true
+    }
 }
 
 impl Matcher {
-    pub fn try_from(entry: DeviceParserEntry) -> Result<Matcher, Error> {
-        let regex_with_flags =
-            if !entry.regex_flag.as_ref().map_or(true, String::is_empty) {
-                format!("(?{}){}", entry.regex_flag.unwrap_or_default(), entry.regex)
-            } else {
-                entry.regex.to_owned()
-            };
-        let regex = fancy_regex::RegexBuilder::new(&regex_with_flags)
-            .delegate_size_limit(20 * (1 << 20))
-            .build();
+    pub fn pattern(&self) -> &Pattern {
+        &self.regex
+    }
+
+    pub fn pattern_mut(&mut self) -> &mut Pattern {
+        &mut self.regex
+    }
+
+    /// Returns every brand the rule can produce, if they can be told
+    pub fn brands(&self) -> Option<Vec<String>> {
+        match (&self.compute, &self.brand_replacement) {
+            (Some(_), _) => None,
+            (None, Some(brand)) => known::values(&self.regex, Some(brand), 0),
+            (None, None) => Some(Vec::new()),
+        }
+    }
+
+    pub fn field_sources(&self) -> Vec<(&'static str, FieldSource)> {
+        let mut sources = vec![
+            ("family", source(&self.device_replacement, 1)),
+            (
+                "brand",
+                match self.brand_replacement {
+                    Some(_) => FieldSource::Replacement,
+                    None => FieldSource::Unset,
+                },
+            ),
+            ("model", source(&self.model_replacement, 1)),
+        ];
+        // Only extended datasets know the kind, so it isn't reported as unset
+        if self.type_replacement.is_some() {
+            sources.push(("kind", FieldSource::Replacement));
+        }
+
+        computed_sources(&self.compute, sources)
+    }
+
+    pub fn try_from(
+        entry: DeviceParserEntry<'_>,
+        options: &RuleOptions,
+    ) -> Result<Matcher, Error> {
+        let regex =
+            options.pattern(Category::Device, entry.regex, entry.regex_flag.as_deref());
 
         Ok(Matcher {
             regex: regex?,
-            device_replacement: entry.device_replacement,
-            brand_replacement: entry.brand_replacement,
-            model_replacement: entry.model_replacement,
+            device_replacement: entry
+                .device_replacement
+                .as_deref()
+                .map(|r| options.template(r)),
+            brand_replacement: entry
+                .brand_replacement
+                .as_deref()
+                .map(|r| options.template(r)),
+            model_replacement: entry
+                .model_replacement
+                .as_deref()
+                .map(|r| options.template(r)),
+            type_replacement: entry
+                .type_replacement
+                .as_deref()
+                .map(|r| options.template(r)),
+            compute: None,
         })
     }
+
+    /// Returns a rule computing its result from the captures of `regex`
+    pub fn computed(
+        regex: String,
+        compute: Compute<Device>,
+        options: &RuleOptions,
+    ) -> Result<Matcher, Error> {
+        let regex = options.pattern(Category::Device, regex.into(), None);
+
+        Ok(Matcher {
+            regex: regex?,
+            device_replacement: None,
+            brand_replacement: None,
+            model_replacement: None,
+            type_replacement: None,
+            compute: Some(compute),
+        })
+    }
+}
+
+impl HeapSize for Matcher {
+    fn heap_size(&self) -> usize {
+        self.regex.heap_size()
+            + self.device_replacement.heap_size()
+            + self.brand_replacement.heap_size()
+            + self.model_replacement.heap_size()
+            + self.type_replacement.heap_size()
+    }
 }
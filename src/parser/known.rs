@@ -0,0 +1,145 @@
+use std::collections::BTreeSet;
+
+use regex_syntax::hir::{literal::Extractor, Hir, HirKind};
+
+use super::{device, os, user_agent, Category, Pattern, Template, UserAgentParser};
+
+/// The most values derived from a single rule, beyond which it's considered
+/// open
+const MAX_VALUES: usize = 256;
+
+/// The values a parser can produce for one field, see
+/// `UserAgentParser::user_agent_families` and its siblings
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct KnownValues {
+    pub values: BTreeSet<String>,
+    /// The number of rules whose values can't be told from the dataset, as
+    /// they capture text that isn't limited to a few literals
+    pub open_rules: usize,
+}
+
+impl KnownValues {
+    /// Returns whether `values` holds every value the parser can produce
+    pub fn is_complete(&self) -> bool {
+        self.open_rules == 0
+    }
+
+    fn collect(rules: impl Iterator<Item = Option<Vec<String>>>) -> KnownValues {
+        let mut known = KnownValues::default();
+        for values in rules {
+            match values {
+                Some(values) => known.values.extend(values),
+                None => known.open_rules += 1,
+            }
+        }
+        known
+    }
+}
+
+impl UserAgentParser {
+    /// Returns the browser families the rules can produce, including the
+    /// fallback family and with aliases applied, e.g. to offer them as
+    /// filters on a dashboard. Families are derived from replacements and
+    /// from groups that capture one of a list of literals, like `(Edge|Edg)`.
+    ///
+    /// ```rust
+    /// # use uaparser::*;
+    /// # let parser = UserAgentParser::from_yaml("./src/core/regexes.yaml").unwrap();
+    /// let families = parser.user_agent_families();
+    /// assert!(families.values.contains("Firefox"));
+    /// ```
+    pub fn user_agent_families(&self) -> KnownValues {
+        let rules = self.user_agent_matchers.iter();
+        let mut known = KnownValues::collect(rules.map(user_agent::Matcher::families));
+        known.values.insert(self.fallbacks.user_agent.clone());
+        self.aliased(Category::UserAgent, known)
+    }
+
+    /// Returns the operating system families the rules can produce, see
+    /// `user_agent_families`
+    pub fn os_families(&self) -> KnownValues {
+        let rules = self.os_matchers.iter();
+        let mut known = KnownValues::collect(rules.map(os::Matcher::families));
+        known.values.insert(self.fallbacks.os.clone());
+        self.aliased(Category::Os, known)
+    }
+
+    /// Returns the device brands the rules can produce, see
+    /// `user_agent_families`
+    pub fn device_brands(&self) -> KnownValues {
+        let rules = self.device_matchers.iter();
+        KnownValues::collect(rules.map(device::Matcher::brands))
+    }
+
+    fn aliased(&self, category: Category, known: KnownValues) -> KnownValues {
+        let values = (known.values.into_iter())
+            .map(|mut value| {
+                self.aliases.apply(category, &mut value);
+                value
+            })
+            .collect();
+
+        KnownValues { values, ..known }
+    }
+}
+
+/// Returns every value of a field that is either expanded from `replacement`
+/// or taken from `group`, unless they can't be told
+pub(super) fn values(
+    pattern: &Pattern,
+    replacement: Option<&Template>,
+    group: usize,
+) -> Option<Vec<String>> {
+    let hir = regex_syntax::Parser::new().parse(pattern.source()).ok()?;
+    let group_values = |index| {
+        let values = literals(find_group(&hir, index)?)?;
+        // Empty groups don't count as captured
+        Some(
+            values
+                .into_iter()
+                .filter(|value| !value.is_empty())
+                .collect(),
+        )
+    };
+
+    let values = match replacement {
+        Some(template) => template.expansions(group_values, MAX_VALUES)?,
+        None => group_values(group)?,
+    };
+    Some(
+        values
+            .into_iter()
+            .filter(|value| !value.is_empty())
+            .collect(),
+    )
+}
+
+/// Finds the capture group with the given `index` within `hir`
+fn find_group(hir: &Hir, index: usize) -> Option<&Hir> {
+    match hir.kind() {
+        HirKind::Capture(capture) if capture.index as usize == index => {
+            Some(&capture.sub)
+        }
+        HirKind::Capture(capture) => find_group(&capture.sub, index),
+        HirKind::Repetition(repetition) => find_group(&repetition.sub, index),
+        HirKind::Concat(hirs) | HirKind::Alternation(hirs) => {
+            hirs.iter().find_map(|hir| find_group(hir, index))
+        }
+        HirKind::Empty | HirKind::Literal(_) | HirKind::Class(_) | HirKind::Look(_) => {
+            None
+        }
+    }
+}
+
+/// Returns the literals `hir` matches exactly, if there are few of them
+fn literals(hir: &Hir) -> Option<Vec<String>> {
+    let seq = Extractor::new().extract(hir);
+    let literals = seq.literals()?;
+    if literals.len() > MAX_VALUES || !literals.iter().all(|literal| literal.is_exact()) {
+        return None;
+    }
+
+    (literals.iter())
+        .map(|literal| String::from_utf8(literal.as_bytes().to_vec()).ok())
+        .collect()
+}
@@ -1,91 +1,709 @@
+use std::{sync::Arc, time::Instant};
+
 use derive_more::{Display, From};
 use serde_yaml;
 
 use super::{
+    alias::FamilyAliases,
+    app, arch,
+    category::Category,
+    chromium,
     client::Client,
+    desktop,
     device::Device,
-    file::{DeviceParserEntry, OSParserEntry, RegexFile, UserAgentParserEntry},
+    distro,
+    file::{
+        DeviceParserEntry, OSParserEntry, RegexFile, RuleEntry, UserAgentParserEntry,
+    },
+    framework,
+    load_report::{LoadReport, MemoryUsage, RuleReport},
+    match_info::{is_generic, Confidence, FieldSource, MatchInfo, RuleMatch},
+    minimize::RuleHits,
+    model::normalize_model,
     os::OS,
     parser::{
         device::Error as DeviceError, os::Error as OSError,
         user_agent::Error as UserAgentError,
     },
+    profile::{ProfileReport, Profiler},
     user_agent::UserAgent,
-    Parser, SubParser,
+    voice, webkit, Parser, SubParser,
 };
 
+mod background;
+mod budget;
+mod builder;
+mod computed;
 mod device;
+mod known;
 mod os;
+mod pattern;
+mod prefilter;
+mod raw;
+mod stream;
+mod template;
+mod translate;
+#[cfg(feature = "unicode")]
+mod unicode;
 mod user_agent;
 
+pub use background::BackgroundParser;
+pub use builder::RegexConfig;
+pub use builder::UserAgentParserBuilder;
+use builder::{Fallbacks, RuleOptions};
+pub use computed::Captures;
+use computed::{Compute, ComputedRules};
+pub use known::KnownValues;
+use pattern::{Limits, Pattern};
+use prefilter::{Prefilter, Scan};
+pub use raw::RawCaptures;
+pub use template::PostProcess;
+use template::Template;
+pub use translate::{Rewrite, RuleTranslation};
+use translate::{Translation, Translator};
+#[cfg(feature = "unicode")]
+use unicode::UnicodeOptions;
+
+/// The errors of constructing a parser. Variants are added along with new
+/// sources of rules and depend on the enabled features, so matches need a
+/// wildcard arm.
 #[derive(Debug, Display, From)]
+#[non_exhaustive]
 pub enum Error {
     IO(std::io::Error),
     Yaml(serde_yaml::Error),
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+    #[cfg(feature = "toml")]
+    Toml(toml::de::Error),
+    Regex(fancy_regex::Error),
+    #[cfg(feature = "compressed")]
+    Decompress(miniz_oxide::inflate::DecompressError),
+    #[cfg(feature = "redis-cache")]
+    Redis(redis::RedisError),
     Device(DeviceError),
     OS(OSError),
     UserAgent(UserAgentError),
+    Rule(RuleError),
+}
+
+/// An error in a single rule of a dataset, along with the position of the rule
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct RuleError {
+    /// The category of the rule
+    pub category: Category,
+    /// The zero-based index of the rule within its category
+    pub index: usize,
+    /// The one-based line of the rule in the dataset, if known
+    pub line: Option<usize>,
+    /// The one-based column of the rule in the dataset, if known
+    pub column: Option<usize>,
+    pub error: Box<Error>,
+}
+
+impl RuleError {
+    fn wrap<T, E: Into<Error>>(
+        result: Result<T, E>,
+        category: Category,
+        index: usize,
+    ) -> Result<T, Error> {
+        result.map_err(|error| {
+            Error::Rule(RuleError {
+                category,
+                index,
+                line: None,
+                column: None,
+                error: Box::new(error.into()),
+            })
+        })
+    }
+}
+
+impl std::fmt::Display for RuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}[{}]", self.category.key(), self.index)?;
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, " at line {}, column {}", line, column)?;
+        }
+        write!(f, ": {}", self.error)
+    }
 }
 
 /// Handles the actual parsing of a user agent string by delegating to
 /// the respective `SubParser`
 #[derive(Debug)]
 pub struct UserAgentParser {
-    device_matchers: Vec<device::Matcher>,
-    os_matchers: Vec<os::Matcher>,
-    user_agent_matchers: Vec<user_agent::Matcher>,
+    /// The position in the dataset of every rule by category, see `Matchers`
+    rule_indices: [Box<[Option<usize>]>; 3],
+    device_matchers: Box<[device::Matcher]>,
+    os_matchers: Box<[os::Matcher]>,
+    user_agent_matchers: Box<[user_agent::Matcher]>,
+    fallbacks: Fallbacks,
+    match_info: bool,
+    dataset: Option<Arc<str>>,
+    load_report: Option<LoadReport>,
+    prefilter: Option<Prefilter>,
+    normalize_models: bool,
+    aliases: FamilyAliases,
+    safari_from_webkit: bool,
+    chromium_version: bool,
+    apps: bool,
+    frameworks: bool,
+    voice_assistants: bool,
+    desktop_shortcut: bool,
+    dropped_rules: usize,
+    profiler: Option<Profiler>,
+    #[cfg(feature = "unicode")]
+    unicode: UnicodeOptions,
 }
 
 impl Parser for UserAgentParser {
     /// Returns the full `Client` info when given a user agent string
     fn parse(&self, user_agent: &str) -> Client {
-        let device = self.parse_device(&user_agent);
-        let os = self.parse_os(&user_agent);
-        let user_agent = self.parse_user_agent(&user_agent);
+        self.parse_matched(user_agent).0
+    }
+
+    /// Returns the full `Client` info along with the categories that a rule
+    /// matched, which fall back to the configured families otherwise
+    fn parse_matched(&self, user_agent: &str) -> (Client, [bool; 3]) {
+        let scan = self.scan(user_agent);
+        let (device_rule, device) = self.match_device(user_agent, scan.as_ref());
+        let (os_rule, os) = self.match_os(user_agent, scan.as_ref());
+        let (user_agent_rule, parsed) = self.match_user_agent(user_agent, scan.as_ref());
 
-        Client {
+        let matched = [
+            device_rule.is_some(),
+            os_rule.is_some(),
+            user_agent_rule.is_some(),
+        ];
+        let mut client = Client {
             device,
             os,
-            user_agent,
-        }
+            user_agent: parsed,
+            match_info: None,
+        };
+        client.match_info =
+            self.match_info(user_agent, &client, device_rule, os_rule, user_agent_rule);
+        (client, matched)
+    }
+
+    /// Writes the full `Client` info into `client`, reusing the allocations of
+    /// its fields
+    fn parse_into(&self, user_agent: &str, client: &mut Client) {
+        let scan = self.scan(user_agent);
+        let device_rule =
+            self.parse_device_into(user_agent, &mut client.device, scan.as_ref());
+        let os_rule = self.parse_os_into(user_agent, &mut client.os, scan.as_ref());
+        let user_agent_rule =
+            self.parse_user_agent_into(user_agent, &mut client.user_agent, scan.as_ref());
+
+        client.match_info =
+            self.match_info(user_agent, client, device_rule, os_rule, user_agent_rule);
     }
 
     /// Returns just the `Device` info when given a user agent string
     fn parse_device(&self, user_agent: &str) -> Device {
-        self.device_matchers
-            .iter()
-            .filter_map(|matcher| matcher.try_parse(&user_agent))
-            .take(1)
-            .next()
-            .unwrap_or_default()
+        self.match_device(user_agent, self.scan(user_agent).as_ref())
+            .1
     }
 
     /// Returns just the `OS` info when given a user agent string
     fn parse_os(&self, user_agent: &str) -> OS {
-        self.os_matchers
-            .iter()
-            .filter_map(|matcher| matcher.try_parse(&user_agent))
-            .take(1)
-            .next()
-            .unwrap_or_default()
+        self.match_os(user_agent, self.scan(user_agent).as_ref()).1
     }
 
     /// Returns just the `UserAgent` info when given a user agent string
     fn parse_user_agent(&self, user_agent: &str) -> UserAgent {
-        self.user_agent_matchers
-            .iter()
-            .filter_map(|matcher| matcher.try_parse(&user_agent))
-            .take(1)
-            .next()
-            .unwrap_or_default()
+        self.match_user_agent(user_agent, self.scan(user_agent).as_ref())
+            .1
     }
 }
 
 impl UserAgentParser {
+    /// Describes the matched rules, given the index of each, if enabled
+    fn match_info(
+        &self,
+        input: &str,
+        client: &Client,
+        device: Option<usize>,
+        os: Option<usize>,
+        user_agent: Option<usize>,
+    ) -> Option<MatchInfo> {
+        if !self.match_info {
+            return None;
+        }
+
+        // A rule may still report the fallback family, e.g. from a capture
+        let generic_device = client.device.family == self.fallbacks.device
+            || is_generic(&client.device.family)
+            || client.device.brand.as_deref().is_some_and(is_generic);
+        let generic_os = client.os.family == self.fallbacks.os;
+        let generic_user_agent = client.user_agent.family == self.fallbacks.user_agent;
+
+        Some(MatchInfo {
+            dataset: self.dataset.clone(),
+            device: device.map(|position| {
+                let matcher = &self.device_matchers[position];
+                let index = self.dataset_index(Category::Device, position);
+                let fields = matcher.field_sources();
+                self.rule_match(index, matcher.pattern(), fields, generic_device, input)
+            }),
+            os: os.map(|position| {
+                let matcher = &self.os_matchers[position];
+                let index = self.dataset_index(Category::Os, position);
+                let fields = matcher.field_sources();
+                self.rule_match(index, matcher.pattern(), fields, generic_os, input)
+            }),
+            user_agent: user_agent.map(|position| {
+                let matcher = &self.user_agent_matchers[position];
+                let index = self.dataset_index(Category::UserAgent, position);
+                let fields = matcher.field_sources();
+                self.rule_match(
+                    index,
+                    matcher.pattern(),
+                    fields,
+                    generic_user_agent,
+                    input,
+                )
+            }),
+        })
+    }
+
+    /// Returns the position in the dataset of the rule at `position` among
+    /// the rules of `category`, or `None` for rules the parser adds itself
+    fn dataset_index(&self, category: Category, position: usize) -> Option<usize> {
+        self.rule_indices[category as usize][position]
+    }
+
+    fn rule_match(
+        &self,
+        index: Option<usize>,
+        pattern: &Pattern,
+        fields: Vec<(&'static str, FieldSource)>,
+        generic: bool,
+        input: &str,
+    ) -> RuleMatch {
+        RuleMatch {
+            index,
+            confidence: Confidence::rate(pattern.source(), &fields, generic),
+            fields,
+            span: pattern.find(input).unwrap_or_default(),
+            dataset: self.dataset.clone(),
+        }
+    }
+
+    /// Scans `user_agent` with the prefilter, if enabled
+    fn scan(&self, user_agent: &str) -> Option<Scan> {
+        Some(self.prefilter.as_ref()?.scan(user_agent))
+    }
+
+    /// Returns the index of the first of `matchers` that parses `user_agent`
+    /// into `item`, evaluating only the candidates of the prefilter if given.
+    /// Every evaluation is timed if this one is sampled by the profiler.
+    fn first_match<M: SubParser>(
+        &self,
+        category: Category,
+        matchers: &[M],
+        scan: Option<&Scan>,
+        user_agent: &str,
+        item: &mut M::Item,
+    ) -> Option<usize> {
+        let profiler = self.profiler.as_ref().filter(|profiler| profiler.sample());
+        let mut try_parse = |index: usize| match profiler {
+            Some(profiler) => {
+                let start = Instant::now();
+                let hit = matchers[index].try_parse_into(user_agent, item);
+                profiler.record(category, index, start.elapsed(), hit);
+                hit
+            }
+            None => matchers[index].try_parse_into(user_agent, item),
+        };
+
+        match (&self.prefilter, scan) {
+            (Some(prefilter), Some(scan)) => prefilter
+                .candidates(category, scan)
+                .find(|&index| try_parse(index)),
+            _ => (0..matchers.len()).find(|&index| try_parse(index)),
+        }
+    }
+
+    fn match_device(
+        &self,
+        user_agent: &str,
+        scan: Option<&Scan>,
+    ) -> (Option<usize>, Device) {
+        let mut device = Device {
+            family: String::new(),
+            ..Device::default()
+        };
+        let index = self.parse_device_into(user_agent, &mut device, scan);
+        (index, device)
+    }
+
+    fn match_os(&self, user_agent: &str, scan: Option<&Scan>) -> (Option<usize>, OS) {
+        let mut os = OS {
+            family: String::new(),
+            ..OS::default()
+        };
+        let index = self.parse_os_into(user_agent, &mut os, scan);
+        (index, os)
+    }
+
+    fn match_user_agent(
+        &self,
+        user_agent: &str,
+        scan: Option<&Scan>,
+    ) -> (Option<usize>, UserAgent) {
+        let mut ua = UserAgent {
+            family: String::new(),
+            ..UserAgent::default()
+        };
+        let index = self.parse_user_agent_into(user_agent, &mut ua, scan);
+        (index, ua)
+    }
+
+    fn parse_device_into(
+        &self,
+        user_agent: &str,
+        device: &mut Device,
+        scan: Option<&Scan>,
+    ) -> Option<usize> {
+        let index = if self.desktop_shortcut && desktop::is_plain_desktop(user_agent) {
+            None
+        } else {
+            self.first_match(
+                Category::Device,
+                &self.device_matchers,
+                scan,
+                user_agent,
+                device,
+            )
+        };
+
+        if index.is_none() {
+            set(&mut device.family, &self.fallbacks.device);
+            device.brand = None;
+            device.model = None;
+        }
+
+        if self.voice_assistants {
+            voice::apply(user_agent, device);
+        }
+        if self.normalize_models {
+            if let Some(model) = &device.model {
+                device.model = normalize_model(model, device.brand.as_deref());
+            }
+        }
+        self.aliases.apply(Category::Device, &mut device.family);
+
+        #[cfg(feature = "unicode")]
+        self.unicode.apply(
+            std::iter::once(&mut device.family)
+                .chain(&mut device.brand)
+                .chain(&mut device.model),
+        );
+
+        index
+    }
+
+    fn parse_os_into(
+        &self,
+        user_agent: &str,
+        os: &mut OS,
+        scan: Option<&Scan>,
+    ) -> Option<usize> {
+        let index =
+            self.first_match(Category::Os, &self.os_matchers, scan, user_agent, os);
+
+        if index.is_none() {
+            set(&mut os.family, &self.fallbacks.os);
+            os.major = None;
+            os.minor = None;
+            os.patch = None;
+            os.patch_minor = None;
+        }
+
+        detect_os_extras(user_agent, os);
+        self.aliases.apply(Category::Os, &mut os.family);
+
+        #[cfg(feature = "unicode")]
+        self.unicode.apply(
+            std::iter::once(&mut os.family)
+                .chain(&mut os.major)
+                .chain(&mut os.minor)
+                .chain(&mut os.patch)
+                .chain(&mut os.patch_minor),
+        );
+
+        index
+    }
+
+    fn parse_user_agent_into(
+        &self,
+        user_agent: &str,
+        ua: &mut UserAgent,
+        scan: Option<&Scan>,
+    ) -> Option<usize> {
+        let index = self.first_match(
+            Category::UserAgent,
+            &self.user_agent_matchers,
+            scan,
+            user_agent,
+            ua,
+        );
+
+        if index.is_none() {
+            set(&mut ua.family, &self.fallbacks.user_agent);
+            ua.major = None;
+            ua.minor = None;
+            ua.patch = None;
+        }
+
+        ua.chromium_version = if self.chromium_version {
+            chromium::detect(user_agent)
+        } else {
+            None
+        };
+        let detected = if self.apps {
+            app::detect(user_agent)
+        } else {
+            None
+        };
+        let (app, runtime) = match detected {
+            Some((app, runtime)) => (app, Some(runtime)),
+            None => (None, None),
+        };
+        ua.app = app;
+        ua.runtime = runtime;
+        ua.framework = if self.frameworks {
+            framework::detect(user_agent)
+        } else {
+            None
+        };
+
+        if self.safari_from_webkit && ua.major.is_none() {
+            if let Some((major, minor)) = webkit::resolve_safari(user_agent, &ua.family) {
+                ua.major = Some(major.to_owned());
+                ua.minor = Some(minor.to_owned());
+            }
+        }
+        self.aliases.apply(Category::UserAgent, &mut ua.family);
+
+        #[cfg(feature = "unicode")]
+        self.unicode.apply(
+            std::iter::once(&mut ua.family)
+                .chain(&mut ua.major)
+                .chain(&mut ua.minor)
+                .chain(&mut ua.patch),
+        );
+
+        index
+    }
+
+    /// Returns the indices of all device rules matching `user_agent`, in
+    /// ascending order, rather than only the first. With the prefilter
+    /// enabled, only the hits of its `RegexSet` are evaluated. Indices refer
+    /// to the dataset, so rules the parser adds itself aren't included.
+    pub fn device_match_indices(&self, user_agent: &str) -> impl Iterator<Item = usize> {
+        let patterns: Vec<_> = self.device_matchers.iter().map(|m| m.pattern()).collect();
+        self.match_indices(Category::Device, &patterns, user_agent)
+    }
+
+    /// Returns the indices of all OS rules matching `user_agent`, see
+    /// `device_match_indices`
+    pub fn os_match_indices(&self, user_agent: &str) -> impl Iterator<Item = usize> {
+        let patterns: Vec<_> = self.os_matchers.iter().map(|m| m.pattern()).collect();
+        self.match_indices(Category::Os, &patterns, user_agent)
+    }
+
+    /// Returns the indices of all user agent rules matching `user_agent`, see
+    /// `device_match_indices`
+    pub fn user_agent_match_indices(
+        &self,
+        user_agent: &str,
+    ) -> impl Iterator<Item = usize> {
+        let patterns: Vec<_> = (self.user_agent_matchers.iter())
+            .map(|m| m.pattern())
+            .collect();
+        self.match_indices(Category::UserAgent, &patterns, user_agent)
+    }
+
+    fn match_indices(
+        &self,
+        category: Category,
+        patterns: &[&Pattern],
+        user_agent: &str,
+    ) -> std::vec::IntoIter<usize> {
+        let matches = |&index: &usize| patterns[index].is_match(user_agent);
+
+        let positions: Vec<usize> = match &self.prefilter {
+            Some(prefilter) => {
+                let scan = prefilter.scan(user_agent);
+                let candidates = prefilter.candidates(category, &scan);
+                candidates.filter(matches).collect()
+            }
+            None => (0..patterns.len()).filter(matches).collect(),
+        };
+
+        let indices: Vec<usize> = (positions.into_iter())
+            .filter_map(|position| self.dataset_index(category, position))
+            .collect();
+        indices.into_iter()
+    }
+
+    /// Returns the rules whose regexes were rewritten before compiling them,
+    /// see `UserAgentParserBuilder::translate_pcre`
+    pub fn translations(&self) -> Vec<RuleTranslation> {
+        let patterns = (self.user_agent_matchers.iter())
+            .map(|m| (Category::UserAgent, m.pattern()))
+            .enumerate()
+            .chain(
+                (self.os_matchers.iter())
+                    .map(|m| (Category::Os, m.pattern()))
+                    .enumerate(),
+            )
+            .chain(
+                (self.device_matchers.iter())
+                    .map(|m| (Category::Device, m.pattern()))
+                    .enumerate(),
+            );
+
+        patterns
+            .filter_map(|(position, (category, pattern))| {
+                let translation = pattern.translation()?;
+                Some(RuleTranslation::new(
+                    category,
+                    self.dataset_index(category, position),
+                    pattern.source(),
+                    translation,
+                ))
+            })
+            .collect()
+    }
+
+    /// Returns the compile time and size of every rule, if diagnostics were
+    /// enabled on the builder
+    pub fn load_report(&self) -> Option<&LoadReport> {
+        self.load_report.as_ref()
+    }
+
+    /// Returns the time spent in every rule so far, if profiling was enabled
+    /// on the builder
+    pub fn profile_report(&self) -> Option<ProfileReport> {
+        let profiler = self.profiler.as_ref()?;
+        let user_agent = self.user_agent_matchers.iter().map(|m| m.pattern());
+        let os = self.os_matchers.iter().map(|m| m.pattern());
+        let device = self.device_matchers.iter().map(|m| m.pattern());
+
+        let patterns = (user_agent
+            .enumerate()
+            .map(|(i, p)| (Category::UserAgent, i, p)))
+        .chain(os.enumerate().map(|(i, p)| (Category::Os, i, p)))
+        .chain(device.enumerate().map(|(i, p)| (Category::Device, i, p)))
+        .map(|(category, position, pattern)| {
+            let index = self.dataset_index(category, position);
+            (category, position, index, pattern.source())
+        });
+        Some(profiler.report(patterns))
+    }
+
+    /// Approximates the memory held by the parser. The compiled regex programs
+    /// are estimated from their sources, as the regex engine doesn't expose
+    /// their size; see `UserAgentParserBuilder::diagnostics` for a
+    /// measurement per rule.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let rules = self.device_matchers.len()
+            + self.os_matchers.len()
+            + self.user_agent_matchers.len();
+
+        let rules_bytes = self.device_matchers.heap_size()
+            + self.os_matchers.heap_size()
+            + self.user_agent_matchers.heap_size();
+
+        let compiled_bytes =
+            budget::cost(&self.device_matchers, device::Matcher::pattern)
+                + budget::cost(&self.os_matchers, os::Matcher::pattern)
+                + budget::cost(&self.user_agent_matchers, user_agent::Matcher::pattern);
+
+        MemoryUsage {
+            rules,
+            rules_bytes,
+            compiled_bytes,
+            dropped_rules: self.dropped_rules,
+        }
+    }
+
+    /// Counts how often every rule of the dataset is the first to match one of
+    /// `user_agents`, e.g. to reduce the dataset with `RuleHits::minimize`.
+    /// Matches of rules the parser adds itself aren't counted.
+    pub fn rule_hits<'a, I>(&self, user_agents: I) -> RuleHits
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut hits = RuleHits::new();
+        for user_agent in user_agents {
+            let scan = self.scan(user_agent);
+            let scan = scan.as_ref();
+
+            let matches = [
+                (
+                    Category::UserAgent,
+                    self.match_user_agent(user_agent, scan).0,
+                ),
+                (Category::Os, self.match_os(user_agent, scan).0),
+                (Category::Device, self.match_device(user_agent, scan).0),
+            ];
+            for (category, position) in matches {
+                let index = position.and_then(|p| self.dataset_index(category, p));
+                if let Some(index) = index {
+                    hits.record(category, index);
+                }
+            }
+        }
+        hits
+    }
+
+    /// Returns a builder to configure the parser before loading the regexes
+    pub fn builder() -> UserAgentParserBuilder {
+        UserAgentParserBuilder::default()
+    }
+
     /// Attempts to construct a `UserAgentParser` from the path to a file
+    #[cfg(feature = "fs")]
     pub fn from_yaml(path: &str) -> Result<UserAgentParser, Error> {
-        let file = std::fs::File::open(path)?;
-        Ok(UserAgentParser::from_file(file)?)
+        UserAgentParser::builder().build_from_yaml(path)
+    }
+
+    /// Attempts to construct a `UserAgentParser` from the path to a file
+    /// without blocking the async runtime, see
+    /// `UserAgentParserBuilder::build_from_path_async`
+    ///
+    /// ```rust
+    /// # use uaparser::*;
+    /// # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    /// # runtime.block_on(async {
+    /// let parser = UserAgentParser::from_path_async("./src/core/regexes.yaml").await?;
+    /// # Ok::<_, Error>(())
+    /// # }).unwrap();
+    /// ```
+    #[cfg(all(feature = "async", feature = "fs"))]
+    pub async fn from_path_async(
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<UserAgentParser, Error> {
+        UserAgentParser::builder().build_from_path_async(path).await
+    }
+
+    /// Attempts to construct a `UserAgentParser` from an async reader over a
+    /// `regexes.yaml` file, see `UserAgentParserBuilder::build_from_reader_async`
+    #[cfg(feature = "async")]
+    pub async fn from_reader_async<R>(reader: R) -> Result<UserAgentParser, Error>
+    where
+        R: tokio::io::AsyncRead + Unpin,
+    {
+        UserAgentParser::builder()
+            .build_from_reader_async(reader)
+            .await
     }
 
     /// Attempts to construct a `UserAgentParser` from a slice of raw bytes. The
@@ -99,61 +717,167 @@ impl UserAgentParser {
     /// let parser = UserAgentParser::from_bytes(regexes);
     /// ```
     pub fn from_bytes(bytes: &[u8]) -> Result<UserAgentParser, Error> {
-        let regex_file: RegexFile = serde_yaml::from_slice(bytes)?;
-        Ok(UserAgentParser::try_from(regex_file)?)
+        UserAgentParser::builder().build_from_bytes(bytes)
+    }
+
+    /// Attempts to construct a `UserAgentParser` from a `regexes.yaml` dataset
+    /// converted to JSON, see `UserAgentParserBuilder::build_from_json`
+    #[cfg(feature = "json")]
+    pub fn from_json(bytes: &[u8]) -> Result<UserAgentParser, Error> {
+        UserAgentParser::builder().build_from_json(bytes)
+    }
+
+    /// Attempts to construct a `UserAgentParser` from a dataset written as
+    /// TOML, see `UserAgentParserBuilder::build_from_toml_bytes`
+    #[cfg(feature = "toml")]
+    pub fn from_toml_bytes(bytes: &[u8]) -> Result<UserAgentParser, Error> {
+        UserAgentParser::builder().build_from_toml_bytes(bytes)
+    }
+
+    /// Attempts to construct a `UserAgentParser` from a reader over a dataset
+    /// written as TOML, see `UserAgentParserBuilder::build_from_toml_bytes`
+    #[cfg(feature = "toml")]
+    pub fn from_toml_reader<R: std::io::Read>(
+        reader: R,
+    ) -> Result<UserAgentParser, Error> {
+        UserAgentParser::builder().build_from_toml_reader(reader)
     }
 
     /// Attempts to construct a `UserAgentParser` from a reference to an open
     /// `File`. This `File` should be a the `regexes.yaml` depended on by
     /// all the various implementations of the UA Parser library.
+    #[cfg(feature = "fs")]
     pub fn from_file(file: std::fs::File) -> Result<UserAgentParser, Error> {
-        let regex_file: RegexFile = serde_yaml::from_reader(file)?;
-        Ok(UserAgentParser::try_from(regex_file)?)
+        UserAgentParser::builder().build_from_file(file)
+    }
+
+    /// Attempts to construct a `UserAgentParser` from a zlib compressed
+    /// `regexes.yaml`. Embedding the dataset this way keeps binaries small,
+    /// which matters on edge runtimes that limit the size of a deployment.
+    /// The compressed file can be produced in a build script:
+    ///
+    /// ```rust,ignore
+    /// let yaml = std::fs::read("regexes.yaml")?;
+    /// let compressed = miniz_oxide::deflate::compress_to_vec_zlib(&yaml, 10);
+    /// std::fs::write(out_dir.join("regexes.yaml.zz"), compressed)?;
+    /// ```
+    ///
+    /// and then be embedded with
+    /// `include_bytes!(concat!(env!("OUT_DIR"), "/regexes.yaml.zz"))`.
+    #[cfg(feature = "compressed")]
+    pub fn from_compressed_bytes(bytes: &[u8]) -> Result<UserAgentParser, Error> {
+        UserAgentParser::builder().build_from_compressed_bytes(bytes)
     }
 
     pub fn try_from(regex_file: RegexFile) -> Result<UserAgentParser, Error> {
-        let mut device_matchers = Vec::new();
-        let mut os_matchers = Vec::new();
-        let mut user_agent_matchers = Vec::new();
+        UserAgentParser::builder().build(regex_file)
+    }
+}
 
-        for parser in regex_file.device_parsers.into_iter() {
-            device_matchers.push(device::Matcher::try_from(parser)?);
-        }
+/// Approximates the memory owned by a value, including its own size for
+/// values stored in a collection
+pub(self) trait HeapSize {
+    fn heap_size(&self) -> usize;
+}
 
-        for parser in regex_file.os_parsers.into_iter() {
-            os_matchers.push(os::Matcher::try_from(parser)?);
-        }
+impl<T: HeapSize> HeapSize for Option<T> {
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, HeapSize::heap_size)
+    }
+}
 
-        for parser in regex_file.user_agent_parsers.into_iter() {
-            user_agent_matchers.push(user_agent::Matcher::try_from(parser)?);
-        }
+impl<T: HeapSize> HeapSize for Box<[T]> {
+    fn heap_size(&self) -> usize {
+        self.len() * std::mem::size_of::<T>()
+            + self.iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
 
-        Ok(UserAgentParser {
-            device_matchers,
-            os_matchers,
-            user_agent_matchers,
-        })
+impl HeapSize for Box<str> {
+    fn heap_size(&self) -> usize {
+        self.len()
     }
 }
 
-pub(self) fn none_if_empty<T: AsRef<str>>(s: T) -> Option<T> {
-    if !s.as_ref().is_empty() {
-        Some(s)
-    } else {
-        None
+/// Describes where a field comes from, given its optional replacement and the
+/// capture group used otherwise
+pub(self) fn source<T>(replacement: &Option<T>, group: usize) -> FieldSource {
+    match replacement {
+        Some(_) => FieldSource::Replacement,
+        None => FieldSource::Capture(group),
     }
 }
 
-pub(self) fn replace(replacement: &str, captures: &fancy_regex::Captures) -> String {
-    if replacement.contains('$') && captures.len() > 0 {
-        (1..=captures.len())
-            .fold(replacement.to_owned(), |state: String, i: usize| {
-                let group = captures.get(i).map(|x| x.as_str()).unwrap_or("");
-                state.replace(&format!("${}", i), &group)
-            })
-            .trim()
-            .to_owned()
-    } else {
-        replacement.to_owned()
+/// Reports every field of a computed rule as coming from a replacement, since
+/// the computation provides all of them
+pub(self) fn computed_sources<T>(
+    compute: &Option<T>,
+    sources: Vec<(&'static str, FieldSource)>,
+) -> Vec<(&'static str, FieldSource)> {
+    match compute {
+        Some(_) => (sources.into_iter())
+            .map(|(field, _)| (field, FieldSource::Replacement))
+            .collect(),
+        None => sources,
+    }
+}
+
+/// Fills in the parts of an `OS` that are detected independently of the
+/// matched rule
+fn detect_os_extras(user_agent: &str, os: &mut OS) {
+    let (arch, bitness) = match arch::detect(user_agent) {
+        Some((arch, bitness)) => (Some(arch), Some(bitness)),
+        None => (None, None),
+    };
+    os.arch = arch;
+    os.bitness = bitness;
+
+    let (distro, version) = match distro::detect(user_agent, &os.family) {
+        Some((distro, version)) => (Some(distro), version),
+        None => (None, None),
+    };
+    os.distro = distro;
+    os.distro_version = version;
+}
+
+/// Returns the text of a capture group, unless it didn't participate in the
+/// match or matched the empty string
+pub(self) fn group<'t>(
+    captures: &fancy_regex::Captures<'t>,
+    index: usize,
+) -> Option<&'t str> {
+    captures
+        .get(index)
+        .map(|x| x.as_str())
+        .filter(|x| !x.is_empty())
+}
+
+/// Overwrites `slot` with `value`, reusing its allocation
+pub(self) fn set(slot: &mut String, value: &str) {
+    slot.clear();
+    slot.push_str(value);
+}
+
+/// Overwrites `slot` with `value`, reusing the allocation of a present value
+pub(self) fn set_opt(slot: &mut Option<String>, value: Option<&str>) {
+    match (slot.as_mut(), value) {
+        (Some(buffer), Some(value)) => set(buffer, value),
+        (None, Some(value)) => *slot = Some(value.to_owned()),
+        (_, None) => *slot = None,
+    }
+}
+
+/// Expands `template` into `slot`, reusing the allocation of a present
+/// value. An empty expansion leaves `slot` empty.
+pub(self) fn replace_opt(
+    slot: &mut Option<String>,
+    template: &Template,
+    captures: &fancy_regex::Captures,
+) {
+    let mut buffer = slot.take().unwrap_or_default();
+    buffer.clear();
+    template.expand_into(captures, &mut buffer);
+    if !buffer.is_empty() {
+        *slot = Some(buffer);
     }
 }
@@ -7,90 +7,163 @@ pub enum Error {
 
 #[derive(Debug)]
 pub struct Matcher {
-    regex: fancy_regex::Regex,
-    os_replacement: Option<String>,
-    os_v1_replacement: Option<String>,
-    os_v2_replacement: Option<String>,
-    os_v3_replacement: Option<String>,
+    regex: Pattern,
+    os_replacement: Option<Template>,
+    os_v1_replacement: Option<Template>,
+    os_v2_replacement: Option<Template>,
+    os_v3_replacement: Option<Template>,
+    os_v4_replacement: Option<Template>,
+    compute: Option<Compute<OS>>,
 }
 
 impl SubParser for Matcher {
     type Item = OS;
 
     fn try_parse(&self, text: &str) -> Option<Self::Item> {
-        if let Ok(Some(captures)) = self.regex.captures(text) {
-            let family: String = if let Some(os_replacement) = &self.os_replacement {
-                replace(&os_replacement, &captures)
-            } else {
-                captures
-                    .get(1)
-                    .map(|x| x.as_str())
-                    .and_then(none_if_empty)
-                    .map(ToString::to_string)?
-            };
-
-            let major: Option<String> =
-                if let Some(os_v1_replacement) = &self.os_v1_replacement {
-                    none_if_empty(replace(&os_v1_replacement, &captures))
-                } else {
-                    captures
-                        .get(2)
-                        .map(|x| x.as_str())
-                        .and_then(none_if_empty)
-                        .map(ToString::to_string)
-                };
-
-            let minor: Option<String> =
-                if let Some(os_v2_replacement) = &self.os_v2_replacement {
-                    none_if_empty(replace(&os_v2_replacement, &captures))
-                } else {
-                    captures
-                        .get(3)
-                        .map(|x| x.as_str())
-                        .and_then(none_if_empty)
-                        .map(ToString::to_string)
-                };
-
-            let patch: Option<String> =
-                if let Some(os_v3_replacement) = &self.os_v3_replacement {
-                    none_if_empty(replace(&os_v3_replacement, &captures))
-                } else {
-                    captures
-                        .get(4)
-                        .map(|x| x.as_str())
-                        .and_then(none_if_empty)
-                        .map(ToString::to_string)
-                };
-
-            let patch_minor: Option<String> = captures
-                .get(5)
-                .map(|x| x.as_str())
-                .and_then(none_if_empty)
-                .map(ToString::to_string);
-
-            Some(OS {
-                family,
-                major,
-                minor,
-                patch,
-                patch_minor,
-            })
+        let mut os = OS {
+            family: String::new(),
+            major: None,
+            minor: None,
+            patch: None,
+            patch_minor: None,
+            arch: None,
+            bitness: None,
+            distro: None,
+            distro_version: None,
+        };
+
+        if self.try_parse_into(text, &mut os) {
+            Some(os)
         } else {
             None
         }
     }
+
+    fn try_parse_into(&self, text: &str, os: &mut OS) -> bool {
+        let captures = match self.regex.captures(text) {
+            Some(captures) => captures,
+            None => return false,
+        };
+
+        if let Some(compute) = &self.compute {
+            *os = compute.apply(&captures);
+            return true;
+        }
+
+        if let Some(os_replacement) = &self.os_replacement {
+            os.family.clear();
+            os_replacement.expand_into(&captures, &mut os.family);
+        } else {
+            match group(&captures, 1) {
+                Some(family) => set(&mut os.family, family),
+                None => return false,
+            }
+        }
+
+        let versions = [
+            (&mut os.major, &self.os_v1_replacement, 2),
+            (&mut os.minor, &self.os_v2_replacement, 3),
+            (&mut os.patch, &self.os_v3_replacement, 4),
+            (&mut os.patch_minor, &self.os_v4_replacement, 5),
+        ];
+
+        for (slot, replacement, index) in versions {
+            if let Some(replacement) = replacement {
+                replace_opt(slot, replacement, &captures);
+            } else {
+                set_opt(slot, group(&captures, index));
+            }
+        }
+
+        true
+    }
 }
 
 impl Matcher {
-    pub fn try_from(entry: OSParserEntry) -> Result<Matcher, Error> {
-        let regex = fancy_regex::Regex::new(&entry.regex);
+    pub fn pattern(&self) -> &Pattern {
+        &self.regex
+    }
+
+    pub fn pattern_mut(&mut self) -> &mut Pattern {
+        &mut self.regex
+    }
+
+    /// Returns every family the rule can produce, if they can be told
+    pub fn families(&self) -> Option<Vec<String>> {
+        match self.compute {
+            Some(_) => None,
+            None => known::values(&self.regex, self.os_replacement.as_ref(), 1),
+        }
+    }
+
+    pub fn field_sources(&self) -> Vec<(&'static str, FieldSource)> {
+        let sources = vec![
+            ("family", source(&self.os_replacement, 1)),
+            ("major", source(&self.os_v1_replacement, 2)),
+            ("minor", source(&self.os_v2_replacement, 3)),
+            ("patch", source(&self.os_v3_replacement, 4)),
+            ("patch_minor", source(&self.os_v4_replacement, 5)),
+        ];
+
+        computed_sources(&self.compute, sources)
+    }
+
+    pub fn try_from(
+        entry: OSParserEntry<'_>,
+        options: &RuleOptions,
+    ) -> Result<Matcher, Error> {
+        let regex = options.pattern(Category::Os, entry.regex, None);
+
+        Ok(Matcher {
+            regex: regex?,
+            os_replacement: entry.os_replacement.as_deref().map(|r| options.template(r)),
+            os_v1_replacement: entry
+                .os_v1_replacement
+                .as_deref()
+                .map(|r| options.template(r)),
+            os_v2_replacement: entry
+                .os_v2_replacement
+                .as_deref()
+                .map(|r| options.template(r)),
+            os_v3_replacement: entry
+                .os_v3_replacement
+                .as_deref()
+                .map(|r| options.template(r)),
+            os_v4_replacement: entry
+                .os_v4_replacement
+                .as_deref()
+                .map(|r| options.template(r)),
+            compute: None,
+        })
+    }
+
+    /// Returns a rule computing its result from the captures of `regex`
+    pub fn computed(
+        regex: String,
+        compute: Compute<OS>,
+        options: &RuleOptions,
+    ) -> Result<Matcher, Error> {
+        let regex = options.pattern(Category::Os, regex.into(), None);
 
         Ok(Matcher {
             regex: regex?,
-            os_replacement: entry.os_replacement,
-            os_v1_replacement: entry.os_v1_replacement,
-            os_v2_replacement: entry.os_v2_replacement,
-            os_v3_replacement: entry.os_v3_replacement,
+            os_replacement: None,
+            os_v1_replacement: None,
+            os_v2_replacement: None,
+            os_v3_replacement: None,
+            os_v4_replacement: None,
+            compute: Some(compute),
         })
     }
 }
+
+impl HeapSize for Matcher {
+    fn heap_size(&self) -> usize {
+        self.regex.heap_size()
+            + self.os_replacement.heap_size()
+            + self.os_v1_replacement.heap_size()
+            + self.os_v2_replacement.heap_size()
+            + self.os_v3_replacement.heap_size()
+            + self.os_v4_replacement.heap_size()
+    }
+}
@@ -0,0 +1,231 @@
+#[cfg(feature = "lazy")]
+use std::sync::OnceLock;
+use std::{
+    borrow::Cow,
+    time::{Duration, Instant},
+};
+
+use super::{HeapSize, Translation};
+
+/// The limits a rule's regex is compiled with, see `RegexConfig`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Limits {
+    pub size: Option<usize>,
+    pub dfa_size: Option<usize>,
+}
+
+/// The regex of a single rule. It is compiled right away, unless the `lazy`
+/// feature is enabled, in which case compilation is deferred until the first
+/// time the rule is evaluated. Rules that fail to compile lazily never match.
+#[derive(Debug)]
+pub struct Pattern {
+    #[cfg(feature = "lazy")]
+    source: Box<str>,
+    limits: Limits,
+    #[cfg(feature = "lazy")]
+    regex: OnceLock<Option<fancy_regex::Regex>>,
+    #[cfg(not(feature = "lazy"))]
+    regex: Compiled,
+    /// The regex of the dataset, if it was rewritten into `source`
+    translation: Option<Box<Translation>>,
+}
+
+/// A compiled regex, or the source of one that isn't compiled until the
+/// rules within the memory budget are known, see `Pattern::deferred`
+#[cfg(not(feature = "lazy"))]
+#[derive(Debug)]
+enum Compiled {
+    Regex(fancy_regex::Regex),
+    Deferred(Box<str>),
+}
+
+impl Pattern {
+    #[cfg(not(feature = "lazy"))]
+    pub fn new(
+        source: Cow<'_, str>,
+        limits: Limits,
+    ) -> Result<Pattern, fancy_regex::Error> {
+        Ok(Pattern {
+            regex: Compiled::Regex(compile(&source, limits)?),
+            limits,
+            translation: None,
+        })
+    }
+
+    /// Keeps the source without compiling it until `compile` is called. The
+    /// pattern doesn't match anything until then.
+    #[cfg(not(feature = "lazy"))]
+    pub fn deferred(source: Cow<'_, str>, limits: Limits) -> Pattern {
+        Pattern {
+            regex: Compiled::Deferred(source.into()),
+            limits,
+            translation: None,
+        }
+    }
+
+    /// Compiles a deferred pattern
+    #[cfg(not(feature = "lazy"))]
+    pub fn compile(&mut self) -> Result<(), fancy_regex::Error> {
+        if let Compiled::Deferred(source) = &self.regex {
+            self.regex = Compiled::Regex(compile(source, self.limits)?);
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "lazy")]
+    pub fn new(
+        source: Cow<'_, str>,
+        limits: Limits,
+    ) -> Result<Pattern, fancy_regex::Error> {
+        Ok(Pattern::deferred(source, limits))
+    }
+
+    #[cfg(feature = "lazy")]
+    pub fn deferred(source: Cow<'_, str>, limits: Limits) -> Pattern {
+        Pattern {
+            source: source.into(),
+            limits,
+            regex: OnceLock::new(),
+            translation: None,
+        }
+    }
+
+    /// Lazily compiled patterns are compiled on their first evaluation
+    #[cfg(feature = "lazy")]
+    pub fn compile(&mut self) -> Result<(), fancy_regex::Error> {
+        Ok(())
+    }
+
+    pub fn translated(mut self, translation: Option<Translation>) -> Pattern {
+        self.translation = translation.map(Box::new);
+        self
+    }
+
+    pub fn translation(&self) -> Option<&Translation> {
+        self.translation.as_deref()
+    }
+
+    #[cfg(not(feature = "lazy"))]
+    fn regex(&self) -> Option<&fancy_regex::Regex> {
+        match &self.regex {
+            Compiled::Regex(regex) => Some(regex),
+            Compiled::Deferred(_) => None,
+        }
+    }
+
+    #[cfg(feature = "lazy")]
+    fn regex(&self) -> Option<&fancy_regex::Regex> {
+        self.regex
+            .get_or_init(|| compile(&self.source, self.limits).ok())
+            .as_ref()
+    }
+
+    #[cfg(not(feature = "lazy"))]
+    pub fn source(&self) -> &str {
+        match &self.regex {
+            Compiled::Regex(regex) => regex.as_str(),
+            Compiled::Deferred(source) => source,
+        }
+    }
+
+    #[cfg(feature = "lazy")]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Compiles the source once more to measure how long that takes and the
+    /// approximate size of the result. The size is found by searching for the
+    /// smallest size limit, in KiB, the regex still compiles with.
+    pub fn measure(&self) -> (Duration, Option<usize>) {
+        let start = Instant::now();
+        let compiled = compile(self.source(), self.limits);
+        let compile_time = start.elapsed();
+
+        if compiled.is_err() {
+            return (compile_time, None);
+        }
+
+        let size_limit = self.limits.size.unwrap_or(usize::MAX >> 10);
+        let (mut low, mut high) = (0, size_limit >> 10);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let limits = Limits {
+                size: Some(mid << 10),
+                ..self.limits
+            };
+            if compile(self.source(), limits).is_ok() {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+
+        (compile_time, Some(high << 10))
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        (self.regex()).is_some_and(|regex| regex.is_match(text).unwrap_or(false))
+    }
+
+    /// Returns the byte range of the first match in `text`
+    pub fn find(&self, text: &str) -> Option<std::ops::Range<usize>> {
+        let found = self.regex()?.find(text).ok().flatten()?;
+        Some(found.start()..found.end())
+    }
+
+    pub fn captures<'t>(&self, text: &'t str) -> Option<fancy_regex::Captures<'t>> {
+        self.regex()?.captures(text).ok().flatten()
+    }
+}
+
+fn compile(
+    source: &str,
+    limits: Limits,
+) -> Result<fancy_regex::Regex, fancy_regex::Error> {
+    let mut builder = fancy_regex::RegexBuilder::new(source);
+    if let Some(limit) = limits.size {
+        builder.delegate_size_limit(limit);
+    }
+    if let Some(limit) = limits.dfa_size {
+        builder.delegate_dfa_size_limit(limit);
+    }
+    builder.build()
+}
+
+impl Pattern {
+    fn translation_size(&self) -> usize {
+        self.translation.as_ref().map_or(0, |translation| {
+            std::mem::size_of::<Translation>()
+                + translation.original.heap_size()
+                + translation.rewrites.capacity() * std::mem::size_of::<super::Rewrite>()
+        })
+    }
+}
+
+impl Pattern {
+    /// The memory of the source of a deferred pattern, which is released once
+    /// it's compiled
+    pub fn deferred_size(&self) -> usize {
+        #[cfg(not(feature = "lazy"))]
+        if let Compiled::Deferred(source) = &self.regex {
+            return source.heap_size();
+        }
+        0
+    }
+}
+
+impl HeapSize for Pattern {
+    #[cfg(not(feature = "lazy"))]
+    fn heap_size(&self) -> usize {
+        let source = match &self.regex {
+            Compiled::Regex(_) => 0,
+            Compiled::Deferred(source) => source.heap_size(),
+        };
+        source + self.translation_size()
+    }
+
+    #[cfg(feature = "lazy")]
+    fn heap_size(&self) -> usize {
+        self.source.heap_size() + self.translation_size()
+    }
+}
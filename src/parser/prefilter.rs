@@ -0,0 +1,148 @@
+//! The `RegexSet` prefilter of `UserAgentParserBuilder::prefilter`. Without
+//! the `prefilter` feature, `Prefilter` can't be constructed, so parsers never
+//! hold one.
+
+#[cfg(feature = "prefilter")]
+use std::iter::Peekable;
+
+#[cfg(feature = "prefilter")]
+use regex::{RegexSet, RegexSetBuilder};
+
+use super::Category;
+#[cfg(feature = "prefilter")]
+use super::Pattern;
+
+/// The rules a `Prefilter` found in a user agent
+#[cfg(feature = "prefilter")]
+pub(super) type Scan = regex::SetMatches;
+
+#[cfg(not(feature = "prefilter"))]
+pub(super) struct Scan;
+
+#[cfg(not(feature = "prefilter"))]
+#[derive(Debug)]
+pub(super) enum Prefilter {}
+
+#[cfg(not(feature = "prefilter"))]
+impl Prefilter {
+    pub fn scan(&self, _user_agent: &str) -> Scan {
+        match *self {}
+    }
+
+    pub fn candidates(
+        &self,
+        _category: Category,
+        _matches: &Scan,
+    ) -> std::iter::Empty<usize> {
+        match *self {}
+    }
+}
+
+/// The rules of one category within the combined `RegexSet`
+#[cfg(feature = "prefilter")]
+#[derive(Debug, Default)]
+struct Lane {
+    /// Position of the first rule of the category in the set
+    offset: usize,
+    /// Index of the rule behind every position of the lane
+    rules: Vec<usize>,
+    /// Rules the set can't express, like those with look-around, which are
+    /// always evaluated
+    always: Vec<usize>,
+}
+
+/// A single `RegexSet` over the rules of every category, so that a user agent
+/// is scanned once to find the rules that can match it, instead of evaluating
+/// every rule in turn
+#[cfg(feature = "prefilter")]
+#[derive(Debug)]
+pub(super) struct Prefilter {
+    set: RegexSet,
+    lanes: [Lane; 3],
+}
+
+#[cfg(feature = "prefilter")]
+impl Prefilter {
+    /// Builds the set over `patterns`, unless it exceeds the size limits of
+    /// the `regex` crate
+    pub fn new<'a>(
+        patterns: impl Iterator<Item = (Category, &'a Pattern)>,
+    ) -> Option<Prefilter> {
+        let mut sources = Vec::new();
+        let mut lanes = [Lane::default(), Lane::default(), Lane::default()];
+        let mut indices = [0; 3];
+        let mut current = None;
+
+        for (category, pattern) in patterns {
+            let lane = &mut lanes[category as usize];
+            let index = &mut indices[category as usize];
+            if current != Some(category) {
+                lane.offset = sources.len();
+                current = Some(category);
+            }
+
+            if regex_syntax::Parser::new().parse(pattern.source()).is_ok() {
+                lane.rules.push(*index);
+                sources.push(pattern.source());
+            } else {
+                lane.always.push(*index);
+            }
+            *index += 1;
+        }
+
+        let set = RegexSetBuilder::new(sources)
+            .size_limit(256 * (1 << 20))
+            .dfa_size_limit(64 * (1 << 20))
+            .build()
+            .ok()?;
+
+        Some(Prefilter { set, lanes })
+    }
+
+    /// Scans `user_agent` for the rules of all categories at once
+    pub fn scan(&self, user_agent: &str) -> Scan {
+        self.set.matches(user_agent)
+    }
+
+    /// Returns the indices of the rules of `category` that can match, in
+    /// ascending order
+    pub fn candidates<'a>(
+        &'a self,
+        category: Category,
+        matches: &'a Scan,
+    ) -> impl Iterator<Item = usize> + 'a {
+        let lane = &self.lanes[category as usize];
+        let hits = (lane.rules.iter().enumerate())
+            .filter(move |&(slot, _)| matches.matched(lane.offset + slot))
+            .map(|(_, &rule)| rule);
+
+        Merge {
+            left: hits.peekable(),
+            right: lane.always.iter().copied().peekable(),
+        }
+    }
+}
+
+/// Merges two ascending iterators into one
+#[cfg(feature = "prefilter")]
+struct Merge<L: Iterator, R: Iterator> {
+    left: Peekable<L>,
+    right: Peekable<R>,
+}
+
+#[cfg(feature = "prefilter")]
+impl<L, R> Iterator for Merge<L, R>
+where
+    L: Iterator<Item = usize>,
+    R: Iterator<Item = usize>,
+{
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        match (self.left.peek(), self.right.peek()) {
+            (Some(left), Some(right)) if right < left => self.right.next(),
+            (Some(_), _) => self.left.next(),
+            (None, _) => self.right.next(),
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use super::{Category, Device, Pattern, UserAgent, UserAgentParser, OS};
+
+/// The capture groups of the rule a result came from, for values a rule
+/// captures without a field to hold them, see
+/// `UserAgentParser::parse_device_raw` and its siblings
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RawCaptures {
+    /// The position of the rule within its category of the dataset, like
+    /// `RuleMatch::index`, or `None` for rules the parser adds itself
+    pub index: Option<usize>,
+    /// The text of every group, starting with the whole match at `0`. Groups
+    /// that didn't participate in the match are `None`.
+    pub groups: Vec<Option<String>>,
+}
+
+impl RawCaptures {
+    fn new(index: Option<usize>, pattern: &Pattern, user_agent: &str) -> RawCaptures {
+        let groups = match pattern.captures(user_agent) {
+            Some(captures) => (0..captures.len())
+                .map(|group| captures.get(group).map(|m| m.as_str().to_owned()))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        RawCaptures { index, groups }
+    }
+
+    /// Returns the text of the group at `index`, which may be empty
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.groups.get(index)?.as_deref()
+    }
+}
+
+impl UserAgentParser {
+    /// Parses the `Device` like `parse_device`, along with the captures of
+    /// the rule it came from, unless none matched
+    ///
+    /// ```rust
+    /// # use uaparser::*;
+    /// let regexes = r#"
+    /// user_agent_parsers: []
+    /// os_parsers: []
+    /// device_parsers:
+    ///   - regex: 'Kiosk (\w+) rev(\d+)'
+    /// "#;
+    /// let parser = UserAgentParser::from_bytes(regexes.as_bytes())?;
+    ///
+    /// let (device, captures) = parser.parse_device_raw("Mozilla/5.0 (Kiosk K1 rev7)");
+    /// assert_eq!(device.family, "K1");
+    /// assert_eq!(captures.unwrap().get(2), Some("7"));
+    /// # Ok::<_, Error>(())
+    /// ```
+    pub fn parse_device_raw(&self, user_agent: &str) -> (Device, Option<RawCaptures>) {
+        let (index, device) =
+            self.match_device(user_agent, self.scan(user_agent).as_ref());
+        let captures = index.map(|position| {
+            let pattern = self.device_matchers[position].pattern();
+            let index = self.dataset_index(Category::Device, position);
+            RawCaptures::new(index, pattern, user_agent)
+        });
+        (device, captures)
+    }
+
+    /// Parses the `OS` like `parse_os`, along with the captures of the rule
+    /// it came from, unless none matched
+    pub fn parse_os_raw(&self, user_agent: &str) -> (OS, Option<RawCaptures>) {
+        let (index, os) = self.match_os(user_agent, self.scan(user_agent).as_ref());
+        let captures = index.map(|position| {
+            let pattern = self.os_matchers[position].pattern();
+            let index = self.dataset_index(Category::Os, position);
+            RawCaptures::new(index, pattern, user_agent)
+        });
+        (os, captures)
+    }
+
+    /// Parses the `UserAgent` like `parse_user_agent`, along with the
+    /// captures of the rule it came from, unless none matched
+    pub fn parse_user_agent_raw(
+        &self,
+        user_agent: &str,
+    ) -> (UserAgent, Option<RawCaptures>) {
+        let (index, parsed) =
+            self.match_user_agent(user_agent, self.scan(user_agent).as_ref());
+        let captures = index.map(|position| {
+            let pattern = self.user_agent_matchers[position].pattern();
+            let index = self.dataset_index(Category::UserAgent, position);
+            RawCaptures::new(index, pattern, user_agent)
+        });
+        (parsed, captures)
+    }
+}
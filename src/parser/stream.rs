@@ -0,0 +1,414 @@
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    marker::PhantomData,
+};
+
+use serde::{
+    de::{
+        self, value::MapAccessDeserializer, DeserializeSeed, IgnoredAny, MapAccess,
+        SeqAccess, Visitor,
+    },
+    Deserialize,
+};
+
+use super::{
+    builder::RuleOptions, device, os, user_agent, Category, DeviceParserEntry, Error,
+    OSParserEntry, RuleEntry, RuleError, UserAgentParserEntry,
+};
+
+/// A matcher that is compiled from a single dataset entry
+pub(super) trait Compile: Sized {
+    type Entry<'de>: Deserialize<'de>;
+    fn view<'a>(entry: &'a Self::Entry<'_>) -> RuleEntry<'a>;
+    fn compile(entry: Self::Entry<'_>, options: &RuleOptions) -> Result<Self, Error>;
+}
+
+impl Compile for device::Matcher {
+    type Entry<'de> = DeviceParserEntry<'de>;
+
+    fn view<'a>(entry: &'a DeviceParserEntry<'_>) -> RuleEntry<'a> {
+        RuleEntry::Device(entry)
+    }
+
+    fn compile(
+        entry: DeviceParserEntry<'_>,
+        options: &RuleOptions,
+    ) -> Result<Self, Error> {
+        Ok(device::Matcher::try_from(entry, options)?)
+    }
+}
+
+impl Compile for os::Matcher {
+    type Entry<'de> = OSParserEntry<'de>;
+
+    fn view<'a>(entry: &'a OSParserEntry<'_>) -> RuleEntry<'a> {
+        RuleEntry::Os(entry)
+    }
+
+    fn compile(entry: OSParserEntry<'_>, options: &RuleOptions) -> Result<Self, Error> {
+        Ok(os::Matcher::try_from(entry, options)?)
+    }
+}
+
+impl Compile for user_agent::Matcher {
+    type Entry<'de> = UserAgentParserEntry<'de>;
+
+    fn view<'a>(entry: &'a UserAgentParserEntry<'_>) -> RuleEntry<'a> {
+        RuleEntry::UserAgent(entry)
+    }
+
+    fn compile(
+        entry: UserAgentParserEntry<'_>,
+        options: &RuleOptions,
+    ) -> Result<Self, Error> {
+        Ok(user_agent::Matcher::try_from(entry, options)?)
+    }
+}
+
+/// Compiles the entries of one category that pass the filters of `options`
+pub(super) fn compile_all<M: Compile>(
+    entries: Vec<M::Entry<'_>>,
+    category: Category,
+    options: &RuleOptions,
+) -> Result<Compiled<M>, Error> {
+    let mut compiled = Compiled {
+        matchers: Vec::with_capacity(entries.len()),
+        indices: Vec::with_capacity(entries.len()),
+        read: entries.len(),
+    };
+
+    for (index, entry) in entries.into_iter().enumerate() {
+        if options.keep(category, M::view(&entry)) {
+            compiled.matchers.push(RuleError::wrap(
+                M::compile(entry, options),
+                category,
+                index,
+            )?);
+            compiled.indices.push(index);
+        }
+    }
+
+    Ok(compiled)
+}
+
+/// The matchers compiled from the rules of one category, along with the
+/// position of every rule among all rules of the category that were read,
+/// including those filtered out
+pub(super) struct Compiled<M> {
+    pub matchers: Vec<M>,
+    pub indices: Vec<usize>,
+    pub read: usize,
+}
+
+impl<M> Compiled<M> {
+    fn extend(&mut self, other: Compiled<M>) {
+        let offset = self.read;
+        self.matchers.extend(other.matchers);
+        (self.indices).extend(other.indices.into_iter().map(|index| offset + index));
+        self.read += other.read;
+    }
+}
+
+/// The matchers of every category, compiled while the dataset is read.
+/// `indices` holds the position in the dataset of the rule behind every
+/// matcher by category, or `None` for rules the parser adds itself.
+pub(super) struct Matchers {
+    pub device: Vec<device::Matcher>,
+    pub os: Vec<os::Matcher>,
+    pub user_agent: Vec<user_agent::Matcher>,
+    pub indices: [Vec<Option<usize>>; 3],
+}
+
+/// The matchers read from one document of a dataset, along with the files it
+/// includes. Categories are optional, since a dataset may be split up so that
+/// each document only carries some of them.
+#[derive(Default)]
+pub(super) struct Fragment {
+    pub device: Option<Compiled<device::Matcher>>,
+    pub os: Option<Compiled<os::Matcher>>,
+    pub user_agent: Option<Compiled<user_agent::Matcher>>,
+    pub include: Vec<String>,
+}
+
+impl Fragment {
+    /// Appends the matchers of `other` after the ones read so far
+    pub fn extend(&mut self, other: Fragment) {
+        append(&mut self.device, other.device);
+        append(&mut self.os, other.os);
+        append(&mut self.user_agent, other.user_agent);
+        self.include.extend(other.include);
+    }
+
+    /// Requires every category to be present in at least one document
+    pub fn finish<E: de::Error>(self) -> Result<Matchers, E> {
+        let device = (self.device).ok_or_else(|| E::missing_field("device_parsers"))?;
+        let os = self.os.ok_or_else(|| E::missing_field("os_parsers"))?;
+        let user_agent =
+            (self.user_agent).ok_or_else(|| E::missing_field("user_agent_parsers"))?;
+
+        let indices = |indices: Vec<usize>| indices.into_iter().map(Some).collect();
+        Ok(Matchers {
+            indices: [
+                indices(user_agent.indices),
+                indices(os.indices),
+                indices(device.indices),
+            ],
+            device: device.matchers,
+            os: os.matchers,
+            user_agent: user_agent.matchers,
+        })
+    }
+}
+
+fn append<M>(compiled: &mut Option<Compiled<M>>, other: Option<Compiled<M>>) {
+    match (compiled.as_mut(), other) {
+        (Some(compiled), Some(other)) => compiled.extend(other),
+        (None, other) => *compiled = other,
+        (Some(_), None) => (),
+    }
+}
+
+/// Reads every document of a YAML dataset in order. The files listed under
+/// `include` are loaded through `include` and their matchers placed before
+/// those of the including document.
+pub(super) fn read_documents(
+    deserializer: serde_yaml::Deserializer,
+    options: &RuleOptions,
+    include: &mut dyn FnMut(&str) -> Result<Fragment, Error>,
+) -> Result<Fragment, Error> {
+    let mut dataset = Fragment::default();
+
+    for document in deserializer {
+        let failure = Failure::default();
+        let seed = DatasetSeed {
+            failure: &failure,
+            options,
+        };
+        let mut fragment = streamed(seed.deserialize(document), failure)?;
+
+        for path in std::mem::take(&mut fragment.include) {
+            dataset.extend(include(&path)?);
+        }
+        dataset.extend(fragment);
+    }
+
+    Ok(dataset)
+}
+
+/// What is known about a failure while a dataset is deserialized, which
+/// serde errors can't carry
+#[derive(Default)]
+pub(super) struct Failure {
+    /// The error of a rule that failed to compile
+    error: RefCell<Option<Error>>,
+    /// The category and index of the rule being read
+    rule: Cell<Option<(Category, usize)>>,
+}
+
+/// Attributes a failed deserialization to the rule it happened in, if any,
+/// preferring the compile error kept aside over the deserialization error it
+/// caused. The location of the rule is taken from the deserialization error.
+pub(super) fn streamed<T, E: Into<Error>>(
+    result: Result<T, E>,
+    failure: Failure,
+) -> Result<T, Error> {
+    let error = match result {
+        Ok(value) => return Ok(value),
+        Err(error) => error.into(),
+    };
+
+    let (category, index) = match failure.rule.get() {
+        Some(rule) => rule,
+        None => return Err(error),
+    };
+
+    let location = location(&error);
+    Err(Error::Rule(RuleError {
+        category,
+        index,
+        line: location.map(|(line, _)| line),
+        column: location.map(|(_, column)| column),
+        error: Box::new(failure.error.into_inner().unwrap_or(error)),
+    }))
+}
+
+/// Returns the one-based line and column a deserialization error occurred at
+fn location(error: &Error) -> Option<(usize, usize)> {
+    match error {
+        Error::Yaml(error) => error
+            .location()
+            .map(|location| (location.line(), location.column())),
+        #[cfg(feature = "json")]
+        Error::Json(error) => Some((error.line(), error.column())),
+        #[cfg(feature = "toml")]
+        Error::Toml(error) => error.line_col().map(|(line, col)| (line + 1, col + 1)),
+        _ => None,
+    }
+}
+
+/// Deserializes a document of a `regexes.yaml` dataset straight into a
+/// `Fragment`, compiling every entry as soon as it's read instead of
+/// collecting all of them into a `RegexFile` first. A failure to compile
+/// aborts deserialization and is kept in `failure`, see `streamed`.
+pub(super) struct DatasetSeed<'a> {
+    pub failure: &'a Failure,
+    pub options: &'a RuleOptions,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for DatasetSeed<'a> {
+    type Value = Fragment;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Fragment, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for DatasetSeed<'a> {
+    type Value = Fragment;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a regexes.yaml dataset")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Fragment, A::Error> {
+        let mut fragment = Fragment::default();
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "device_parsers" => append(
+                    &mut fragment.device,
+                    Some(map.next_value_seed(self.category(Category::Device))?),
+                ),
+                "os_parsers" => append(
+                    &mut fragment.os,
+                    Some(map.next_value_seed(self.category(Category::Os))?),
+                ),
+                "user_agent_parsers" => append(
+                    &mut fragment.user_agent,
+                    Some(map.next_value_seed(self.category(Category::UserAgent))?),
+                ),
+                "include" => fragment.include.extend(map.next_value::<Vec<String>>()?),
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        Ok(fragment)
+    }
+}
+
+impl<'a> DatasetSeed<'a> {
+    fn category<M>(&self, category: Category) -> CategorySeed<'a, M> {
+        CategorySeed {
+            failure: self.failure,
+            options: self.options,
+            category,
+            matcher: PhantomData,
+        }
+    }
+}
+
+/// Deserializes the entries of one category, compiling each right away
+struct CategorySeed<'a, M> {
+    failure: &'a Failure,
+    options: &'a RuleOptions,
+    category: Category,
+    matcher: PhantomData<M>,
+}
+
+impl<'de, 'a, M: Compile> DeserializeSeed<'de> for CategorySeed<'a, M> {
+    type Value = Compiled<M>;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Compiled<M>, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'a, M: Compile> Visitor<'de> for CategorySeed<'a, M> {
+    type Value = Compiled<M>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of rules")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Compiled<M>, A::Error> {
+        let mut compiled = Compiled {
+            matchers: Vec::with_capacity(seq.size_hint().unwrap_or(0)),
+            indices: Vec::with_capacity(seq.size_hint().unwrap_or(0)),
+            read: 0,
+        };
+
+        for index in 0.. {
+            (self.failure.rule).set(Some((self.category, index)));
+            let entry = EntrySeed {
+                failure: self.failure,
+                options: self.options,
+                category: self.category,
+                matcher: PhantomData,
+            };
+
+            match seq.next_element_seed(entry)? {
+                Some(Some(matcher)) => {
+                    compiled.matchers.push(matcher);
+                    compiled.indices.push(index);
+                }
+                Some(None) => (),
+                None => break,
+            }
+            compiled.read += 1;
+        }
+
+        self.failure.rule.set(None);
+        Ok(compiled)
+    }
+}
+
+/// Deserializes and compiles a single entry, unless it's filtered out.
+/// Compiling within the visitor of the entry lets deserializers attach the
+/// location of the entry to the error.
+struct EntrySeed<'a, M> {
+    failure: &'a Failure,
+    options: &'a RuleOptions,
+    category: Category,
+    matcher: PhantomData<M>,
+}
+
+impl<'de, 'a, M: Compile> DeserializeSeed<'de> for EntrySeed<'a, M> {
+    type Value = Option<M>;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Option<M>, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, 'a, M: Compile> Visitor<'de> for EntrySeed<'a, M> {
+    type Value = Option<M>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a rule")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Option<M>, A::Error> {
+        let entry = M::Entry::<'de>::deserialize(MapAccessDeserializer::new(map))?;
+        if !self.options.keep(self.category, M::view(&entry)) {
+            return Ok(None);
+        }
+
+        M::compile(entry, self.options).map(Some).map_err(|error| {
+            let message = error.to_string();
+            *self.failure.error.borrow_mut() = Some(error);
+            de::Error::custom(message)
+        })
+    }
+}
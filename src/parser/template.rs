@@ -0,0 +1,201 @@
+use std::sync::Arc;
+
+use fancy_regex::Captures;
+
+use super::HeapSize;
+
+/// A step applied to values produced by replacements that refer to capture
+/// groups, see `UserAgentParserBuilder::post_process`
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PostProcess {
+    /// Removes leading and trailing whitespace
+    Trim,
+    /// Replaces every run of whitespace with a single space
+    CollapseWhitespace,
+    /// Removes ASCII punctuation, like `;` or `)`, from the end
+    StripTrailingPunctuation,
+}
+
+impl PostProcess {
+    fn apply(self, value: &mut String, start: usize) {
+        match self {
+            PostProcess::Trim => {
+                let trimmed_end = value.trim_end().len().max(start);
+                value.truncate(trimmed_end);
+                let leading = value[start..].len() - value[start..].trim_start().len();
+                value.drain(start..start + leading);
+            }
+            PostProcess::CollapseWhitespace => {
+                let mut collapsed = String::with_capacity(value.len() - start);
+                let mut in_whitespace = false;
+                for c in value[start..].chars() {
+                    if c.is_whitespace() {
+                        if !in_whitespace {
+                            collapsed.push(' ');
+                        }
+                        in_whitespace = true;
+                    } else {
+                        collapsed.push(c);
+                        in_whitespace = false;
+                    }
+                }
+                value.truncate(start);
+                value.push_str(&collapsed);
+            }
+            PostProcess::StripTrailingPunctuation => {
+                let stripped = value[start..]
+                    .trim_end_matches(|c: char| c.is_ascii_punctuation())
+                    .len();
+                value.truncate(start + stripped);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Segment {
+    Literal(Box<str>),
+    Group(u8),
+}
+
+/// A replacement string split into literal text and references to capture
+/// groups once at load time, so matching doesn't have to scan it again
+#[derive(Debug)]
+pub struct Template {
+    segments: Box<[Segment]>,
+    post_process: Option<Arc<[PostProcess]>>,
+}
+
+impl Template {
+    /// Splits `replacement` at every `$1` through `$9`. A `$$` stands for a
+    /// literal `$`, and so does a `$` that isn't followed by a group number.
+    /// The `post_process` steps only apply if there are group references.
+    pub fn parse(replacement: &str, post_process: &Arc<[PostProcess]>) -> Template {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = replacement.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c != '$' {
+                literal.push(c);
+                continue;
+            }
+
+            let index = match chars.peek() {
+                Some('$') => {
+                    chars.next();
+                    literal.push('$');
+                    continue;
+                }
+                Some(&d) => match d.to_digit(10) {
+                    Some(index) if index >= 1 => index as u8,
+                    _ => {
+                        literal.push('$');
+                        continue;
+                    }
+                },
+                None => {
+                    literal.push('$');
+                    continue;
+                }
+            };
+
+            chars.next();
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(std::mem::take(&mut literal).into()));
+            }
+            segments.push(Segment::Group(index));
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal.into()));
+        }
+
+        let has_groups = segments.iter().any(|s| matches!(s, Segment::Group(_)));
+        Template {
+            segments: segments.into_boxed_slice(),
+            post_process: Some(post_process.clone())
+                .filter(|steps| has_groups && !steps.is_empty()),
+        }
+    }
+
+    /// Appends the expansion to `out`. Templates referring to capture groups
+    /// are post-processed, while plain ones are used verbatim.
+    pub fn expand_into(&self, captures: &Captures, out: &mut String) {
+        let start = out.len();
+
+        for segment in &self.segments {
+            match *segment {
+                Segment::Literal(ref literal) => out.push_str(literal),
+                // References beyond the groups of the regex are kept as is
+                Segment::Group(index) if usize::from(index) > captures.len() => {
+                    out.push('$');
+                    out.push_str(&index.to_string());
+                }
+                Segment::Group(index) => out.push_str(
+                    captures
+                        .get(usize::from(index))
+                        .map(|x| x.as_str())
+                        .unwrap_or(""),
+                ),
+            }
+        }
+
+        for step in self.post_process.iter().flat_map(|steps| steps.iter()) {
+            step.apply(out, start);
+        }
+    }
+}
+
+impl Template {
+    /// Returns every expansion, given the values each referenced group can
+    /// take, unless those of a group are unknown or there are more than
+    /// `limit` expansions
+    pub fn expansions(
+        &self,
+        group_values: impl Fn(usize) -> Option<Vec<String>>,
+        limit: usize,
+    ) -> Option<Vec<String>> {
+        let mut expansions = vec![String::new()];
+
+        for segment in self.segments.iter() {
+            match segment {
+                Segment::Literal(literal) => {
+                    for expansion in &mut expansions {
+                        expansion.push_str(literal);
+                    }
+                }
+                Segment::Group(index) => {
+                    let values = group_values(usize::from(*index))?;
+                    if expansions.len() * values.len() > limit {
+                        return None;
+                    }
+                    expansions = (expansions.iter())
+                        .flat_map(|expansion| {
+                            values.iter().map(move |value| expansion.clone() + value)
+                        })
+                        .collect();
+                }
+            }
+        }
+
+        for expansion in &mut expansions {
+            for step in self.post_process.iter().flat_map(|steps| steps.iter()) {
+                step.apply(expansion, 0);
+            }
+        }
+        Some(expansions)
+    }
+}
+
+impl HeapSize for Template {
+    fn heap_size(&self) -> usize {
+        self.segments.len() * std::mem::size_of::<Segment>()
+            + (self.segments.iter())
+                .map(|segment| match segment {
+                    Segment::Literal(literal) => literal.heap_size(),
+                    Segment::Group(_) => 0,
+                })
+                .sum::<usize>()
+    }
+}
@@ -0,0 +1,301 @@
+use std::{borrow::Cow, sync::Arc};
+
+use super::Category;
+
+/// A change made to the regex of a rule to compile it with the `regex` crate,
+/// see `UserAgentParserBuilder::translate_pcre`
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Rewrite {
+    /// A superfluous escape of a character without special meaning, like
+    /// `\!` or `\/`, which was dropped
+    Escape(char),
+    /// A possessive quantifier, like `a++`, which became an atomic group,
+    /// like `(?>a+)`
+    Possessive,
+    /// An inline flag the `regex` crate doesn't know, which was dropped
+    InlineFlag(char),
+    /// `\Z`, which became `\z`
+    EndAnchor,
+    /// A rewrite registered with `UserAgentParserBuilder::regex_rewrite`
+    Custom(String),
+}
+
+/// A rule whose regex was rewritten before compiling it
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RuleTranslation {
+    pub category: Category,
+    /// The position of the rule within its category of the dataset, or
+    /// `None` for rules the parser adds itself
+    pub index: Option<usize>,
+    /// The regex as found in the dataset
+    pub original: String,
+    /// The regex that was compiled instead
+    pub translated: String,
+    pub rewrites: Vec<Rewrite>,
+}
+
+/// The original regex of a translated rule, kept along with its pattern
+#[derive(Debug)]
+pub(super) struct Translation {
+    pub original: Box<str>,
+    pub rewrites: Vec<Rewrite>,
+}
+
+/// Characters that keep their escape, since they have a special meaning in
+/// the `regex` crate either in or outside of classes
+const META: &str = "\\.+*?()|[]{}^$-&~# ";
+
+/// Inline flags supported by the `regex` crate
+const FLAGS: &str = "imsUxuR-";
+
+/// Rewrites the regexes of rules written for PCRE so that the `regex` crate
+/// accepts them and interprets them as intended
+#[derive(Clone, Debug, Default)]
+pub(super) struct Translator {
+    pub pcre: bool,
+    /// Literal replacements applied before the built-in translations
+    pub custom: Arc<[(String, String)]>,
+}
+
+impl Translator {
+    pub fn is_enabled(&self) -> bool {
+        self.pcre || !self.custom.is_empty()
+    }
+
+    /// Returns the translated `regex` along with the original, unless it's
+    /// unchanged
+    pub fn translate<'a>(
+        &self,
+        regex: Cow<'a, str>,
+    ) -> (Cow<'a, str>, Option<Translation>) {
+        if !self.is_enabled() {
+            return (regex, None);
+        }
+
+        let mut rewrites = Vec::new();
+        let mut translated = regex.to_string();
+        for (from, to) in self.custom.iter() {
+            if translated.contains(from.as_str()) {
+                translated = translated.replace(from.as_str(), to);
+                rewrites.push(Rewrite::Custom(from.clone()));
+            }
+        }
+
+        if self.pcre {
+            translated = translate_pcre(&translated, &mut rewrites);
+        }
+
+        if rewrites.is_empty() {
+            return (regex, None);
+        }
+
+        let translation = Translation {
+            original: regex.into(),
+            rewrites,
+        };
+        (Cow::Owned(translated), Some(translation))
+    }
+}
+
+fn translate_pcre(regex: &str, rewrites: &mut Vec<Rewrite>) -> String {
+    let mut translated = String::with_capacity(regex.len());
+    let mut chars = regex.chars().peekable();
+    let mut in_class = false;
+    let mut quantified = false;
+    // Where the last atom and every open group start in `translated`, to
+    // wrap a quantified atom into an atomic group
+    let mut atom = 0;
+    let mut groups = Vec::new();
+    let mut in_repetition = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if !in_class {
+                    atom = translated.len();
+                }
+                quantified = false;
+                match chars.next() {
+                    Some('Z') if !in_class => {
+                        translated.push_str("\\z");
+                        rewrites.push(Rewrite::EndAnchor);
+                    }
+                    Some(escaped)
+                        if !escaped.is_alphanumeric() && !META.contains(escaped) =>
+                    {
+                        translated.push(escaped);
+                        rewrites.push(Rewrite::Escape(escaped));
+                    }
+                    Some(escaped) => {
+                        translated.push('\\');
+                        translated.push(escaped);
+                    }
+                    None => translated.push('\\'),
+                }
+                continue;
+            }
+            '[' if !in_class => {
+                atom = translated.len();
+                in_class = true;
+                translated.push(c);
+                // A `]` right after the opening bracket is a literal
+                for literal in ['^', ']'] {
+                    if chars.peek() == Some(&literal) {
+                        translated.push(literal);
+                        chars.next();
+                    }
+                }
+            }
+            ']' if in_class => {
+                in_class = false;
+                translated.push(c);
+            }
+            '+' if !in_class && quantified => {
+                // PCRE never backtracks into a possessively quantified atom,
+                // just as with an atomic group
+                translated.insert_str(atom, "(?>");
+                translated.push(')');
+                rewrites.push(Rewrite::Possessive);
+                quantified = false;
+                continue;
+            }
+            '(' if !in_class && chars.peek() == Some(&'?') => {
+                groups.push(translated.len());
+                translated.push_str("(?");
+                chars.next();
+                // Inline flags run up to `)` or `:`, unless the group is of
+                // another kind like `(?P<name>` or `(?=`
+                let mut flags = String::new();
+                while let Some(&flag) = chars.peek() {
+                    if !flag.is_ascii_alphabetic() && flag != '-' {
+                        break;
+                    }
+                    flags.push(flag);
+                    chars.next();
+                }
+                if matches!(chars.peek(), Some(')') | Some(':')) {
+                    for flag in flags.chars() {
+                        if FLAGS.contains(flag) {
+                            translated.push(flag);
+                        } else {
+                            rewrites.push(Rewrite::InlineFlag(flag));
+                        }
+                    }
+                    // Nothing is left of a group that only set unknown flags
+                    if translated.ends_with("(?") && chars.peek() == Some(&')') {
+                        translated.truncate(translated.len() - 2);
+                        groups.pop();
+                        chars.next();
+                    }
+                } else {
+                    translated.push_str(&flags);
+                }
+                quantified = false;
+                continue;
+            }
+            '(' if !in_class => {
+                groups.push(translated.len());
+                translated.push(c);
+            }
+            ')' if !in_class => {
+                atom = groups.pop().unwrap_or(0);
+                translated.push(c);
+            }
+            '{' if !in_class => {
+                in_repetition = true;
+                translated.push(c);
+            }
+            '}' if in_repetition => {
+                in_repetition = false;
+                translated.push(c);
+            }
+            _ => {
+                // Quantifiers apply to the atom before them
+                if !in_class && !in_repetition && !matches!(c, '*' | '+' | '?') {
+                    atom = translated.len();
+                }
+                translated.push(c);
+            }
+        }
+
+        // Whether `c` ends a quantifier a possessive `+` may follow
+        quantified = !in_class && matches!(c, '*' | '+' | '?' | '}');
+    }
+
+    translated
+}
+
+/// Restricts the Perl classes `\d`, `\w` and `\s` of `regex` to ASCII, see
+/// `RegexConfig::unicode`. Negated classes within brackets stay as they are.
+pub(super) fn ascii_classes(regex: &str) -> String {
+    let mut translated = String::with_capacity(regex.len());
+    let mut chars = regex.chars().peekable();
+    let mut in_class = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let escaped = match chars.next() {
+                    Some(escaped) => escaped,
+                    None => {
+                        translated.push('\\');
+                        break;
+                    }
+                };
+                let ascii = match escaped.to_ascii_lowercase() {
+                    'd' => "0-9",
+                    'w' => "0-9A-Za-z_",
+                    's' => "\\t\\n\\x0B\\f\\r ",
+                    _ => "",
+                };
+                let negated = escaped.is_ascii_uppercase();
+
+                if ascii.is_empty() || in_class && negated {
+                    translated.push('\\');
+                    translated.push(escaped);
+                } else if in_class {
+                    translated.push_str(ascii);
+                } else {
+                    translated.push_str(if negated { "[^" } else { "[" });
+                    translated.push_str(ascii);
+                    translated.push(']');
+                }
+            }
+            '[' if !in_class => {
+                in_class = true;
+                translated.push(c);
+                // A `]` right after the opening bracket is a literal
+                for literal in ['^', ']'] {
+                    if chars.peek() == Some(&literal) {
+                        translated.push(literal);
+                        chars.next();
+                    }
+                }
+            }
+            ']' if in_class => {
+                in_class = false;
+                translated.push(c);
+            }
+            _ => translated.push(c),
+        }
+    }
+
+    translated
+}
+
+impl RuleTranslation {
+    pub(super) fn new(
+        category: Category,
+        index: Option<usize>,
+        translated: &str,
+        translation: &Translation,
+    ) -> RuleTranslation {
+        RuleTranslation {
+            category,
+            index,
+            original: translation.original.to_string(),
+            translated: translated.to_owned(),
+            rewrites: translation.rewrites.clone(),
+        }
+    }
+}
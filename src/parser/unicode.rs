@@ -0,0 +1,36 @@
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// How output fields are normalized, see
+/// `UserAgentParserBuilder::normalize_unicode`
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct UnicodeOptions {
+    pub nfc: bool,
+    pub ascii_fold: bool,
+}
+
+impl UnicodeOptions {
+    pub fn apply<'a>(&self, fields: impl Iterator<Item = &'a mut String>) {
+        if !self.nfc && !self.ascii_fold {
+            return;
+        }
+
+        for field in fields.filter(|field| !field.is_ascii()) {
+            *field = if self.ascii_fold {
+                fold(field)
+            } else {
+                field.nfc().collect()
+            };
+        }
+    }
+}
+
+/// Replaces compatibility characters, like fullwidth letters, with their
+/// canonical counterparts and strips diacritics. Characters without an ASCII
+/// counterpart, such as CJK, are kept.
+fn fold(value: &str) -> String {
+    value
+        .nfkd()
+        .filter(|&c| !is_combining_mark(c))
+        .nfc()
+        .collect()
+}
@@ -7,78 +7,140 @@ pub enum Error {
 
 #[derive(Debug)]
 pub struct Matcher {
-    regex: fancy_regex::Regex,
-    family_replacement: Option<String>,
-    v1_replacement: Option<String>,
-    v2_replacement: Option<String>,
-    v3_replacement: Option<String>,
+    regex: Pattern,
+    family_replacement: Option<Template>,
+    v1_replacement: Option<Box<str>>,
+    v2_replacement: Option<Box<str>>,
+    v3_replacement: Option<Box<str>>,
+    compute: Option<Compute<UserAgent>>,
 }
 
 impl SubParser for Matcher {
     type Item = UserAgent;
 
     fn try_parse(&self, text: &str) -> Option<Self::Item> {
-        if let Ok(Some(captures)) = self.regex.captures(text) {
-            let family: String =
-                if let Some(family_replacement) = &self.family_replacement {
-                    replace(&family_replacement, &captures)
-                } else {
-                    captures
-                        .get(1)
-                        .map(|x| x.as_str())
-                        .and_then(none_if_empty)
-                        .map(ToString::to_string)?
-                }
-                .to_owned();
-
-            let major = self.v1_replacement.to_owned().or_else(|| {
-                captures
-                    .get(2)
-                    .map(|x| x.as_str())
-                    .and_then(none_if_empty)
-                    .map(ToString::to_string)
-            });
-
-            let minor = self.v2_replacement.to_owned().or_else(|| {
-                captures
-                    .get(3)
-                    .map(|x| x.as_str())
-                    .and_then(none_if_empty)
-                    .map(ToString::to_string)
-            });
-
-            let patch = self.v3_replacement.to_owned().or_else(|| {
-                captures
-                    .get(4)
-                    .map(|x| x.as_str())
-                    .and_then(none_if_empty)
-                    .map(ToString::to_string)
-            });
-
-            Some(UserAgent {
-                family,
-                major,
-                minor,
-                patch,
-            })
+        let mut user_agent = UserAgent {
+            family: String::new(),
+            ..UserAgent::default()
+        };
+
+        if self.try_parse_into(text, &mut user_agent) {
+            Some(user_agent)
         } else {
             None
         }
     }
+
+    fn try_parse_into(&self, text: &str, user_agent: &mut UserAgent) -> bool {
+        let captures = match self.regex.captures(text) {
+            Some(captures) => captures,
+            None => return false,
+        };
+
+        if let Some(compute) = &self.compute {
+            *user_agent = compute.apply(&captures);
+            return true;
+        }
+
+        if let Some(family_replacement) = &self.family_replacement {
+            user_agent.family.clear();
+            family_replacement.expand_into(&captures, &mut user_agent.family);
+        } else {
+            match group(&captures, 1) {
+                Some(family) => set(&mut user_agent.family, family),
+                None => return false,
+            }
+        }
+
+        let versions = [
+            (&mut user_agent.major, &self.v1_replacement, 2),
+            (&mut user_agent.minor, &self.v2_replacement, 3),
+            (&mut user_agent.patch, &self.v3_replacement, 4),
+        ];
+
+        for (slot, replacement, index) in versions {
+            set_opt(
+                slot,
+                replacement.as_deref().or_else(|| group(&captures, index)),
+            );
+        }
+
+        true
+    }
 }
 
 impl Matcher {
-    pub fn try_from(entry: UserAgentParserEntry) -> Result<Matcher, Error> {
-        let regex = fancy_regex::RegexBuilder::new(&entry.regex)
-            .delegate_size_limit(20 * (1 << 20))
-            .build();
+    pub fn pattern(&self) -> &Pattern {
+        &self.regex
+    }
+
+    pub fn pattern_mut(&mut self) -> &mut Pattern {
+        &mut self.regex
+    }
+
+    /// Returns every family the rule can produce, if they can be told
+    pub fn families(&self) -> Option<Vec<String>> {
+        match self.compute {
+            Some(_) => None,
+            None => known::values(&self.regex, self.family_replacement.as_ref(), 1),
+        }
+    }
+
+    pub fn field_sources(&self) -> Vec<(&'static str, FieldSource)> {
+        let sources = vec![
+            ("family", source(&self.family_replacement, 1)),
+            ("major", source(&self.v1_replacement, 2)),
+            ("minor", source(&self.v2_replacement, 3)),
+            ("patch", source(&self.v3_replacement, 4)),
+        ];
+
+        computed_sources(&self.compute, sources)
+    }
+
+    pub fn try_from(
+        entry: UserAgentParserEntry<'_>,
+        options: &RuleOptions,
+    ) -> Result<Matcher, Error> {
+        let regex = options.pattern(Category::UserAgent, entry.regex, None);
 
         Ok(Matcher {
             regex: regex?,
-            family_replacement: entry.family_replacement,
-            v1_replacement: entry.v1_replacement,
-            v2_replacement: entry.v2_replacement,
-            v3_replacement: entry.v3_replacement,
+            family_replacement: entry
+                .family_replacement
+                .as_deref()
+                .map(|r| options.template(r)),
+            v1_replacement: entry.v1_replacement.map(Into::into),
+            v2_replacement: entry.v2_replacement.map(Into::into),
+            v3_replacement: entry.v3_replacement.map(Into::into),
+            compute: None,
         })
     }
+
+    /// Returns a rule computing its result from the captures of `regex`
+    pub fn computed(
+        regex: String,
+        compute: Compute<UserAgent>,
+        options: &RuleOptions,
+    ) -> Result<Matcher, Error> {
+        let regex = options.pattern(Category::UserAgent, regex.into(), None);
+
+        Ok(Matcher {
+            regex: regex?,
+            family_replacement: None,
+            v1_replacement: None,
+            v2_replacement: None,
+            v3_replacement: None,
+            compute: Some(compute),
+        })
+    }
+}
+
+impl HeapSize for Matcher {
+    fn heap_size(&self) -> usize {
+        self.regex.heap_size()
+            + self.family_replacement.heap_size()
+            + self.v1_replacement.heap_size()
+            + self.v2_replacement.heap_size()
+            + self.v3_replacement.heap_size()
+    }
 }
@@ -0,0 +1,112 @@
+use super::{Client, Device, Parser, UserAgent, OS};
+
+type InputFn = dyn Fn(&mut String) + Send + Sync;
+type OutputFn = dyn Fn(&mut Client) + Send + Sync;
+
+/// A `Parser` running user agents through input transformers before they
+/// reach another parser, and its results through output transformers, each
+/// in the order they were added. Normalization, marketing names and similar
+/// adjustments can be composed this way without touching the dataset.
+///
+/// ```rust
+/// # use uaparser::*;
+/// # let regexes = UserAgentParser::from_yaml("./src/core/regexes.yaml").unwrap();
+/// let parser = Pipeline::new(regexes)
+///     .input(|user_agent| *user_agent = user_agent.replace("FxiOS", "Firefox"))
+///     .output(|client| {
+///         if client.device.model.as_deref() == Some("SM-G991B") {
+///             client.device.model = Some("Galaxy S21".to_owned());
+///         }
+///     });
+/// ```
+///
+/// With output transformers in place, the results of single categories are
+/// taken from a full parse, so that the transformers see the whole `Client`.
+pub struct Pipeline<P> {
+    parser: P,
+    inputs: Vec<Box<InputFn>>,
+    outputs: Vec<Box<OutputFn>>,
+}
+
+impl<P: std::fmt::Debug> std::fmt::Debug for Pipeline<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pipeline")
+            .field("parser", &self.parser)
+            .field("inputs", &self.inputs.len())
+            .field("outputs", &self.outputs.len())
+            .finish()
+    }
+}
+
+impl<P: Parser> Pipeline<P> {
+    pub fn new(parser: P) -> Pipeline<P> {
+        Pipeline {
+            parser,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Appends a transformer of the user agent, run before matching
+    pub fn input<F>(mut self, transform: F) -> Pipeline<P>
+    where
+        F: Fn(&mut String) + Send + Sync + 'static,
+    {
+        self.inputs.push(Box::new(transform));
+        self
+    }
+
+    /// Appends a transformer of the parsed `Client`
+    pub fn output<F>(mut self, transform: F) -> Pipeline<P>
+    where
+        F: Fn(&mut Client) + Send + Sync + 'static,
+    {
+        self.outputs.push(Box::new(transform));
+        self
+    }
+
+    /// Runs `f` on the transformed user agent, copying it only if there are
+    /// input transformers
+    fn with_input<T>(&self, user_agent: &str, f: impl FnOnce(&str) -> T) -> T {
+        if self.inputs.is_empty() {
+            return f(user_agent);
+        }
+
+        let mut user_agent = user_agent.to_owned();
+        for transform in &self.inputs {
+            transform(&mut user_agent);
+        }
+        f(&user_agent)
+    }
+}
+
+impl<P: Parser> Parser for Pipeline<P> {
+    fn parse(&self, user_agent: &str) -> Client {
+        let mut client = self.with_input(user_agent, |ua| self.parser.parse(ua));
+        for transform in &self.outputs {
+            transform(&mut client);
+        }
+        client
+    }
+
+    fn parse_device(&self, user_agent: &str) -> Device {
+        if !self.outputs.is_empty() {
+            return self.parse(user_agent).device;
+        }
+        self.with_input(user_agent, |ua| self.parser.parse_device(ua))
+    }
+
+    fn parse_os(&self, user_agent: &str) -> OS {
+        if !self.outputs.is_empty() {
+            return self.parse(user_agent).os;
+        }
+        self.with_input(user_agent, |ua| self.parser.parse_os(ua))
+    }
+
+    fn parse_user_agent(&self, user_agent: &str) -> UserAgent {
+        if !self.outputs.is_empty() {
+            return self.parse(user_agent).user_agent;
+        }
+        self.with_input(user_agent, |ua| self.parser.parse_user_agent(ua))
+    }
+}
@@ -0,0 +1,199 @@
+use std::cmp::Ordering::{self, Equal, Greater, Less};
+
+use serde_derive::Deserialize;
+
+use super::{Client, Error};
+
+/// What to do with a client matched by a `Policy`
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyAction {
+    Allow,
+    Warn,
+    Block,
+}
+
+/// A single named rule of a `PolicyEngine`
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Policy {
+    pub name: String,
+    pub action: PolicyAction,
+    /// Shown to users or logged when the policy matches
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    when: Condition,
+}
+
+/// The conditions of a `Policy`, all of which have to hold. Families are
+/// given as a single name or a list of names.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct Condition {
+    #[serde(default)]
+    user_agent: Option<Families>,
+    #[serde(default)]
+    os: Option<Families>,
+    #[serde(default)]
+    device: Option<Families>,
+    /// Whether the client is a crawler, i.e. its device family is `Spider`
+    #[serde(default)]
+    bot: Option<bool>,
+    /// Matches user agents with a version lower than this, e.g. `"100"`
+    #[serde(default)]
+    version_below: Option<String>,
+    /// Matches user agents with at least this version
+    #[serde(default)]
+    version_at_least: Option<String>,
+    /// Inverts a nested condition, e.g. to exempt some families
+    #[serde(default)]
+    not: Option<Box<Condition>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(untagged)]
+enum Families {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Families {
+    fn contains(&self, family: &str) -> bool {
+        match self {
+            Families::One(name) => name == family,
+            Families::Many(names) => names.iter().any(|name| name == family),
+        }
+    }
+}
+
+impl Condition {
+    fn matches(&self, client: &Client) -> bool {
+        let families = [
+            (&self.user_agent, &client.user_agent.family),
+            (&self.os, &client.os.family),
+            (&self.device, &client.device.family),
+        ];
+        let version = [
+            &client.user_agent.major,
+            &client.user_agent.minor,
+            &client.user_agent.patch,
+        ];
+
+        families
+            .iter()
+            .all(|(names, family)| names.as_ref().map_or(true, |n| n.contains(family)))
+            && self
+                .bot
+                .map_or(true, |bot| bot == (client.device.family == "Spider"))
+            && (self.version_below.as_deref())
+                .map_or(true, |below| compare(&version, below) == Some(Less))
+            && (self.version_at_least.as_deref()).map_or(true, |least| {
+                matches!(compare(&version, least), Some(Greater | Equal))
+            })
+            && self.not.as_ref().map_or(true, |not| !not.matches(client))
+    }
+}
+
+/// Compares the numeric components of a version to a dotted version string.
+/// Missing components count as zero, but a version without a major component
+/// or with non-numeric ones isn't comparable.
+fn compare(version: &[&Option<String>], other: &str) -> Option<Ordering> {
+    version[0].as_ref()?;
+
+    let mut other = other.split('.');
+    for component in version {
+        let own = match component {
+            Some(component) => component.parse::<u64>().ok()?,
+            None => 0,
+        };
+        let other = other.next().map_or(Some(0), |c| c.parse::<u64>().ok())?;
+
+        match own.cmp(&other) {
+            Equal => continue,
+            ordering => return Some(ordering),
+        }
+    }
+
+    Some(Equal)
+}
+
+#[derive(Debug, Deserialize)]
+struct PolicyFile {
+    policies: Vec<Policy>,
+}
+
+/// Evaluates declarative policies, such as warning about outdated browsers or
+/// blocking crawlers, against parsed clients. Policies are loaded from YAML,
+/// or JSON with the `json` feature:
+///
+/// ```rust
+/// # use uaparser::*;
+/// let engine = PolicyEngine::from_bytes(br#"
+/// policies:
+///   - name: outdated-chrome
+///     action: warn
+///     when: { user_agent: Chrome, version_below: "100" }
+///   - name: internet-explorer
+///     action: block
+///     when: { user_agent: IE }
+///   - name: crawlers
+///     action: block
+///     when: { bot: true, not: { user_agent: Googlebot } }
+/// "#).unwrap();
+///
+/// let mut client = Client::default();
+/// client.user_agent.family = "Chrome".to_owned();
+/// client.user_agent.major = Some("99".to_owned());
+///
+/// assert_eq!(engine.action(&client), Some(PolicyAction::Warn));
+/// assert_eq!(engine.evaluate(&client)[0].name, "outdated-chrome");
+/// ```
+#[derive(Clone, Debug)]
+pub struct PolicyEngine {
+    policies: Vec<Policy>,
+}
+
+impl PolicyEngine {
+    /// Attempts to construct a `PolicyEngine` from the path to a policy file
+    #[cfg(feature = "fs")]
+    pub fn from_yaml(path: &str) -> Result<PolicyEngine, Error> {
+        let file = std::fs::File::open(path)?;
+        let policy_file: PolicyFile = serde_yaml::from_reader(file)?;
+        Ok(PolicyEngine {
+            policies: policy_file.policies,
+        })
+    }
+
+    /// Attempts to construct a `PolicyEngine` from the raw bytes of a policy
+    /// file
+    pub fn from_bytes(bytes: &[u8]) -> Result<PolicyEngine, Error> {
+        let policy_file: PolicyFile = serde_yaml::from_slice(bytes)?;
+        Ok(PolicyEngine {
+            policies: policy_file.policies,
+        })
+    }
+
+    /// Attempts to construct a `PolicyEngine` from a policy file written as
+    /// JSON
+    #[cfg(feature = "json")]
+    pub fn from_json(bytes: &[u8]) -> Result<PolicyEngine, Error> {
+        let policy_file: PolicyFile = serde_json::from_slice(bytes)?;
+        Ok(PolicyEngine {
+            policies: policy_file.policies,
+        })
+    }
+
+    /// Returns every policy matching `client`, in the order they were defined
+    pub fn evaluate(&self, client: &Client) -> Vec<&Policy> {
+        (self.policies.iter())
+            .filter(|policy| policy.when.matches(client))
+            .collect()
+    }
+
+    /// Returns the action of the first policy matching `client`, if any
+    pub fn action(&self, client: &Client) -> Option<PolicyAction> {
+        (self.policies.iter())
+            .find(|policy| policy.when.matches(client))
+            .map(|policy| policy.action)
+    }
+}
@@ -0,0 +1,19 @@
+//! Re-exports the parsers, their results and the traits needed to call them,
+//! for a single glob import:
+//!
+//! ```rust
+//! use uaparser::prelude::*;
+//!
+//! let parser = UserAgentParser::from_yaml("./src/core/regexes.yaml")?;
+//! let user_agent = "Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Firefox/70.0";
+//!
+//! let client: Client = user_agent.parse_client(&parser);
+//! assert_eq!(client.device_type(), DeviceType::Desktop);
+//! assert_eq!(parser.parse_os(user_agent).family, "Linux");
+//! # Ok::<_, Error>(())
+//! ```
+
+pub use super::{
+    Category, Client, Device, DeviceType, Error, Parser, UaCache, UserAgent,
+    UserAgentExt, UserAgentParser, UserAgentParserBuilder, OS,
+};
@@ -0,0 +1,48 @@
+use super::{Deserialize, Serialize};
+
+/// A named piece of software found in a user agent, like the application
+/// embedding a browser engine, along with its version if stated
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "async-graphql", derive(async_graphql::SimpleObject))]
+pub struct Product {
+    pub name: String,
+    pub version: Option<String>,
+}
+
+impl Product {
+    /// Parses a `Name/1.2.3` token, taking the version up to the first
+    /// character that isn't a digit or dot
+    pub(crate) fn from_token(token: &str) -> Option<Product> {
+        let (name, version) = match token.split_once('/') {
+            Some((name, version)) => (name, Some(version)),
+            None => (token, None),
+        };
+        if name.is_empty() {
+            return None;
+        }
+
+        let version = version.and_then(|version| {
+            let end = version
+                .find(|c: char| !c.is_ascii_digit() && c != '.')
+                .unwrap_or(version.len());
+            let version = version[..end].trim_end_matches('.');
+            Some(version.to_owned()).filter(|v| !v.is_empty())
+        });
+
+        Some(Product {
+            name: name.to_owned(),
+            version,
+        })
+    }
+}
+
+impl std::fmt::Display for Product {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{} {}", self.name, version),
+            None => f.write_str(&self.name),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    cmp::Reverse,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use super::Category;
+
+/// The sampled cost of matching a single rule at runtime
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RuleProfile {
+    pub category: Category,
+    /// The position of the rule within its category of the dataset, or
+    /// `None` for rules the parser adds itself
+    pub index: Option<usize>,
+    pub pattern: String,
+    /// How often the rule was evaluated in sampled parses
+    pub evaluations: u64,
+    /// How often the rule matched in sampled parses
+    pub hits: u64,
+    pub total_time: Duration,
+}
+
+impl RuleProfile {
+    /// Returns the average time of a single evaluation
+    pub fn mean_time(&self) -> Duration {
+        if self.evaluations == 0 {
+            return Duration::ZERO;
+        }
+        let nanos = self.total_time.as_nanos() / u128::from(self.evaluations);
+        Duration::from_nanos(nanos as u64)
+    }
+}
+
+/// The time spent in every rule while parsing, sampled at runtime. Only
+/// recorded if enabled with `UserAgentParserBuilder::profile`.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ProfileReport {
+    /// Every rule that was evaluated at least once
+    pub rules: Vec<RuleProfile>,
+}
+
+impl ProfileReport {
+    /// Returns the `n` rules that took the most time in total, slowest first
+    pub fn slowest(&self, n: usize) -> Vec<&RuleProfile> {
+        let mut rules: Vec<&RuleProfile> = self.rules.iter().collect();
+        rules.sort_by_key(|rule| Reverse(rule.total_time));
+        rules.truncate(n);
+        rules
+    }
+
+    /// Returns the `n` rules that are slowest per evaluation, slowest first
+    pub fn slowest_mean(&self, n: usize) -> Vec<&RuleProfile> {
+        let mut rules: Vec<&RuleProfile> = self.rules.iter().collect();
+        rules.sort_by_key(|rule| Reverse(rule.mean_time()));
+        rules.truncate(n);
+        rules
+    }
+}
+
+#[derive(Debug, Default)]
+struct RuleStats {
+    evaluations: AtomicU64,
+    hits: AtomicU64,
+    nanos: AtomicU64,
+}
+
+/// Collects the time spent in every rule for every `rate`th evaluation of a
+/// category
+#[derive(Debug)]
+pub(crate) struct Profiler {
+    rate: u64,
+    counter: AtomicU64,
+    rules: [Box<[RuleStats]>; 3],
+}
+
+impl Profiler {
+    pub fn new(rate: u32, lens: [usize; 3]) -> Profiler {
+        let stats = |len| (0..len).map(|_| RuleStats::default()).collect();
+        Profiler {
+            rate: u64::from(rate.max(1)),
+            counter: AtomicU64::new(0),
+            rules: [stats(lens[0]), stats(lens[1]), stats(lens[2])],
+        }
+    }
+
+    /// Returns whether the next evaluation should be timed
+    pub fn sample(&self) -> bool {
+        self.counter.fetch_add(1, Ordering::Relaxed) % self.rate == 0
+    }
+
+    pub fn record(&self, category: Category, index: usize, time: Duration, hit: bool) {
+        let stats = &self.rules[category as usize][index];
+        stats.evaluations.fetch_add(1, Ordering::Relaxed);
+        stats.hits.fetch_add(u64::from(hit), Ordering::Relaxed);
+        stats
+            .nanos
+            .fetch_add(time.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Builds the report, looking up the dataset index and pattern of every
+    /// evaluated rule by its position among the parser's rules
+    pub fn report<'a>(
+        &self,
+        patterns: impl Iterator<Item = (Category, usize, Option<usize>, &'a str)>,
+    ) -> ProfileReport {
+        let rules = patterns
+            .filter_map(|(category, position, index, pattern)| {
+                let stats = self.rules[category as usize].get(position)?;
+                let evaluations = stats.evaluations.load(Ordering::Relaxed);
+                if evaluations == 0 {
+                    return None;
+                }
+
+                Some(RuleProfile {
+                    category,
+                    index,
+                    pattern: pattern.to_owned(),
+                    evaluations,
+                    hits: stats.hits.load(Ordering::Relaxed),
+                    total_time: Duration::from_nanos(stats.nanos.load(Ordering::Relaxed)),
+                })
+            })
+            .collect();
+
+        ProfileReport { rules }
+    }
+}
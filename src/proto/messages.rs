@@ -0,0 +1,103 @@
+// This file is @generated by prost-build.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Client {
+    #[prost(message, optional, tag = "1")]
+    pub device: ::core::option::Option<Device>,
+    #[prost(message, optional, tag = "2")]
+    pub os: ::core::option::Option<Os>,
+    #[prost(message, optional, tag = "3")]
+    pub user_agent: ::core::option::Option<UserAgent>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Device {
+    #[prost(string, tag = "1")]
+    pub family: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "2")]
+    pub brand: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "3")]
+    pub model: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "4")]
+    pub kind: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Os {
+    #[prost(string, tag = "1")]
+    pub family: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "2")]
+    pub major: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "3")]
+    pub minor: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "4")]
+    pub patch: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "5")]
+    pub patch_minor: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(enumeration = "Arch", optional, tag = "6")]
+    pub arch: ::core::option::Option<i32>,
+    #[prost(uint32, optional, tag = "7")]
+    pub bitness: ::core::option::Option<u32>,
+    #[prost(string, optional, tag = "8")]
+    pub distro: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "9")]
+    pub distro_version: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UserAgent {
+    #[prost(string, tag = "1")]
+    pub family: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "2")]
+    pub major: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "3")]
+    pub minor: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "4")]
+    pub patch: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "5")]
+    pub chromium_version: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(message, optional, tag = "6")]
+    pub app: ::core::option::Option<Product>,
+    #[prost(message, optional, tag = "7")]
+    pub runtime: ::core::option::Option<Product>,
+    #[prost(message, optional, tag = "8")]
+    pub framework: ::core::option::Option<Product>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Product {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "2")]
+    pub version: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum Arch {
+    Unspecified = 0,
+    X86 = 1,
+    X8664 = 2,
+    Arm = 3,
+    Arm64 = 4,
+}
+impl Arch {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "ARCH_UNSPECIFIED",
+            Self::X86 => "ARCH_X86",
+            Self::X8664 => "ARCH_X86_64",
+            Self::Arm => "ARCH_ARM",
+            Self::Arm64 => "ARCH_ARM64",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "ARCH_UNSPECIFIED" => Some(Self::Unspecified),
+            "ARCH_X86" => Some(Self::X86),
+            "ARCH_X86_64" => Some(Self::X8664),
+            "ARCH_ARM" => Some(Self::Arm),
+            "ARCH_ARM64" => Some(Self::Arm64),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Protobuf messages mirroring `Client`, generated from
+//! `proto/uaparser.proto`, for shipping parse results over gRPC or Kafka.
+//! Conversions from and to the parser's own types are provided via `From`.
+//!
+//! ```rust
+//! # use uaparser::*;
+//! use prost::Message;
+//!
+//! let client = Client::default();
+//! let bytes = proto::Client::from(client.clone()).encode_to_vec();
+//! let decoded = proto::Client::decode(bytes.as_slice()).unwrap();
+//! assert_eq!(Client::from(decoded), client);
+//! ```
+
+use std::convert::TryFrom;
+
+#[rustfmt::skip]
+#[allow(clippy::all)]
+mod messages;
+
+pub use messages::{Arch, Client, Device, Os, Product, UserAgent};
+
+impl From<crate::Arch> for Arch {
+    fn from(arch: crate::Arch) -> Arch {
+        match arch {
+            crate::Arch::X86 => Arch::X86,
+            crate::Arch::X86_64 => Arch::X8664,
+            crate::Arch::Arm => Arch::Arm,
+            crate::Arch::Arm64 => Arch::Arm64,
+        }
+    }
+}
+
+impl From<crate::Device> for Device {
+    fn from(device: crate::Device) -> Device {
+        Device {
+            family: device.family,
+            brand: device.brand,
+            model: device.model,
+            kind: device.kind,
+        }
+    }
+}
+
+impl From<crate::OS> for Os {
+    fn from(os: crate::OS) -> Os {
+        Os {
+            family: os.family,
+            major: os.major,
+            minor: os.minor,
+            patch: os.patch,
+            patch_minor: os.patch_minor,
+            arch: os.arch.map(|arch| Arch::from(arch) as i32),
+            bitness: os.bitness.map(u32::from),
+            distro: os.distro,
+            distro_version: os.distro_version,
+        }
+    }
+}
+
+impl From<crate::Product> for Product {
+    fn from(product: crate::Product) -> Product {
+        Product {
+            name: product.name,
+            version: product.version,
+        }
+    }
+}
+
+impl From<crate::UserAgent> for UserAgent {
+    fn from(user_agent: crate::UserAgent) -> UserAgent {
+        UserAgent {
+            family: user_agent.family,
+            major: user_agent.major,
+            minor: user_agent.minor,
+            patch: user_agent.patch,
+            chromium_version: user_agent.chromium_version,
+            app: user_agent.app.map(Into::into),
+            runtime: user_agent.runtime.map(Into::into),
+            framework: user_agent.framework.map(Into::into),
+        }
+    }
+}
+
+impl From<crate::Client> for Client {
+    fn from(client: crate::Client) -> Client {
+        Client {
+            device: Some(client.device.into()),
+            os: Some(client.os.into()),
+            user_agent: Some(client.user_agent.into()),
+        }
+    }
+}
+
+impl From<Device> for crate::Device {
+    fn from(device: Device) -> crate::Device {
+        crate::Device {
+            family: device.family,
+            brand: device.brand,
+            model: device.model,
+            kind: device.kind,
+        }
+    }
+}
+
+/// Unknown architectures and bitnesses, e.g. sent by a newer schema, are
+/// dropped rather than failing the conversion
+impl From<Os> for crate::OS {
+    fn from(os: Os) -> crate::OS {
+        let arch = match os.arch.and_then(|arch| Arch::try_from(arch).ok()) {
+            Some(Arch::X86) => Some(crate::Arch::X86),
+            Some(Arch::X8664) => Some(crate::Arch::X86_64),
+            Some(Arch::Arm) => Some(crate::Arch::Arm),
+            Some(Arch::Arm64) => Some(crate::Arch::Arm64),
+            Some(Arch::Unspecified) | None => None,
+        };
+
+        crate::OS {
+            family: os.family,
+            major: os.major,
+            minor: os.minor,
+            patch: os.patch,
+            patch_minor: os.patch_minor,
+            arch,
+            bitness: os.bitness.and_then(|bitness| u8::try_from(bitness).ok()),
+            distro: os.distro,
+            distro_version: os.distro_version,
+        }
+    }
+}
+
+impl From<Product> for crate::Product {
+    fn from(product: Product) -> crate::Product {
+        crate::Product {
+            name: product.name,
+            version: product.version,
+        }
+    }
+}
+
+impl From<UserAgent> for crate::UserAgent {
+    fn from(user_agent: UserAgent) -> crate::UserAgent {
+        crate::UserAgent {
+            family: user_agent.family,
+            major: user_agent.major,
+            minor: user_agent.minor,
+            patch: user_agent.patch,
+            chromium_version: user_agent.chromium_version,
+            app: user_agent.app.map(Into::into),
+            runtime: user_agent.runtime.map(Into::into),
+            framework: user_agent.framework.map(Into::into),
+        }
+    }
+}
+
+/// Missing messages are filled in with the defaults, i.e. `"Other"` families
+impl From<Client> for crate::Client {
+    fn from(client: Client) -> crate::Client {
+        crate::Client {
+            device: client.device.map(Into::into).unwrap_or_default(),
+            os: client.os.map(Into::into).unwrap_or_default(),
+            user_agent: client.user_agent.map(Into::into).unwrap_or_default(),
+            match_info: None,
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::{sync::Mutex, time::Duration};
+
+use redis::Commands;
+
+use super::{Client, Error, UaCache};
+
+/// A `UaCache` in Redis, which instances of a service share. Results are
+/// stored as JSON under the user agent, prefixed with a namespace that should
+/// change along with the dataset, e.g. its `dataset_checksum`.
+///
+/// Every call blocks on a round trip to Redis. Concurrent calls use separate
+/// connections, which are kept open for later calls, and a connection is
+/// dropped after an error. Fleets with stricter latency requirements should
+/// front it with an `LruCache`.
+///
+/// ```rust,no_run
+/// # use uaparser::*;
+/// let cache = RedisCache::open("redis://127.0.0.1/", "uaparser:v1:")?;
+/// let parser = CachedParser::new(UserAgentParser::from_yaml("./src/core/regexes.yaml")?, cache);
+/// # Ok::<_, Error>(())
+/// ```
+pub struct RedisCache {
+    client: redis::Client,
+    /// Connections not in use by any call
+    idle: Mutex<Vec<redis::Connection>>,
+    prefix: String,
+}
+
+impl RedisCache {
+    /// Connects to the Redis server at `url`, like `redis://127.0.0.1/`
+    pub fn open(url: &str, prefix: impl Into<String>) -> Result<RedisCache, Error> {
+        let client = redis::Client::open(url)?;
+        let connection = client.get_connection()?;
+        Ok(RedisCache {
+            client,
+            idle: Mutex::new(vec![connection]),
+            prefix: prefix.into(),
+        })
+    }
+
+    /// Runs `f` on an idle connection, or a new one if there's none, and
+    /// returns it to the idle ones unless `f` fails
+    fn with_connection<T>(
+        &self,
+        f: impl FnOnce(&mut redis::Connection) -> redis::RedisResult<T>,
+    ) -> Option<T> {
+        let idle = self.idle.lock().unwrap_or_else(|e| e.into_inner()).pop();
+        let mut connection = match idle {
+            Some(connection) => connection,
+            None => self.client.get_connection().ok()?,
+        };
+
+        let result = f(&mut connection).ok()?;
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        idle.push(connection);
+        Some(result)
+    }
+
+    fn key(&self, user_agent: &str) -> String {
+        format!("{}{}", self.prefix, user_agent)
+    }
+}
+
+impl std::fmt::Debug for RedisCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("RedisCache")
+            .field("client", &self.client)
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+impl UaCache for RedisCache {
+    fn get(&self, user_agent: &str) -> Option<Client> {
+        let key = self.key(user_agent);
+        let value: Option<Vec<u8>> = self.with_connection(|c| c.get(&key))?;
+        serde_json::from_slice(&value?).ok()
+    }
+
+    fn put(&self, user_agent: &str, client: &Client, ttl: Option<Duration>) {
+        let value = match serde_json::to_vec(client) {
+            Ok(value) => value,
+            Err(_) => return,
+        };
+
+        let key = self.key(user_agent);
+        self.with_connection(|c| match ttl {
+            Some(ttl) => c.set_ex::<_, _, ()>(&key, value, ttl.as_secs().max(1)),
+            None => c.set::<_, _, ()>(&key, value),
+        });
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use serde_derive::Deserialize;
+
+use super::Error;
+
+/// The kind of traffic a referrer represents
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Medium {
+    Search,
+    Social,
+    Email,
+    Paid,
+    Internal,
+    Unknown,
+}
+
+impl Medium {
+    fn from_key(key: &str) -> Medium {
+        match key {
+            "search" => Medium::Search,
+            "social" => Medium::Social,
+            "email" => Medium::Email,
+            "paid" => Medium::Paid,
+            "internal" => Medium::Internal,
+            _ => Medium::Unknown,
+        }
+    }
+}
+
+/// Describes the `Medium`, the name of the source and, for search engines,
+/// the search term of a referrer URL
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Referer {
+    pub medium: Medium,
+    pub source: Option<String>,
+    pub term: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourceEntry {
+    domains: Vec<String>,
+    #[serde(default)]
+    parameters: Vec<String>,
+}
+
+type RefererFile = HashMap<String, HashMap<String, SourceEntry>>;
+
+#[derive(Debug)]
+struct Source {
+    medium: Medium,
+    name: String,
+    parameters: Vec<String>,
+}
+
+/// Classifies referrer URLs using the Snowplow referer-parser database
+/// (`referers.yml`)
+#[derive(Debug)]
+pub struct RefererParser {
+    sources: Vec<Source>,
+    domains: HashMap<String, usize>,
+    internal_domains: Vec<String>,
+}
+
+impl RefererParser {
+    /// Attempts to construct a `RefererParser` from the path to a Snowplow
+    /// `referers.yml` file
+    #[cfg(feature = "fs")]
+    pub fn from_yaml(path: &str) -> Result<RefererParser, Error> {
+        let file = std::fs::File::open(path)?;
+        let referer_file: RefererFile = serde_yaml::from_reader(file)?;
+        Ok(RefererParser::from_referer_file(referer_file))
+    }
+
+    /// Attempts to construct a `RefererParser` from the raw bytes of a
+    /// Snowplow `referers.yml` file
+    pub fn from_bytes(bytes: &[u8]) -> Result<RefererParser, Error> {
+        let referer_file: RefererFile = serde_yaml::from_slice(bytes)?;
+        Ok(RefererParser::from_referer_file(referer_file))
+    }
+
+    /// Treats referrers from the given hosts, and their subdomains, as
+    /// internal traffic
+    pub fn with_internal_domains<I, S>(mut self, domains: I) -> RefererParser
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.internal_domains.extend(
+            domains
+                .into_iter()
+                .map(|domain| domain.into().to_lowercase()),
+        );
+        self
+    }
+
+    fn from_referer_file(referer_file: RefererFile) -> RefererParser {
+        let mut sources = Vec::new();
+        let mut domains = HashMap::new();
+
+        for (medium, entries) in referer_file {
+            let medium = Medium::from_key(&medium);
+            for (name, entry) in entries {
+                for domain in entry.domains {
+                    domains.insert(domain.to_lowercase(), sources.len());
+                }
+                sources.push(Source {
+                    medium,
+                    name,
+                    parameters: entry.parameters,
+                });
+            }
+        }
+
+        RefererParser {
+            sources,
+            domains,
+            internal_domains: Vec::new(),
+        }
+    }
+
+    /// Classifies `referer_url`. When the URL of the page that was referred to
+    /// is given, referrers from the same host are reported as internal.
+    /// Returns `None` for URLs that cannot be parsed or aren't HTTP(S).
+    pub fn parse(&self, referer_url: &str, page_url: Option<&str>) -> Option<Referer> {
+        let url = url::Url::parse(referer_url).ok()?;
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return None;
+        }
+
+        let host = url.host_str()?.to_lowercase();
+        let page_host = page_url
+            .and_then(|page| url::Url::parse(page).ok())
+            .and_then(|page| page.host_str().map(str::to_lowercase));
+
+        let is_internal = page_host.as_deref() == Some(host.as_str())
+            || self
+                .internal_domains
+                .iter()
+                .any(|domain| is_same_or_subdomain(&host, domain));
+
+        if is_internal {
+            return Some(Referer {
+                medium: Medium::Internal,
+                source: None,
+                term: None,
+            });
+        }
+
+        let source = match self.lookup(&host, url.path()) {
+            Some(source) => source,
+            None => {
+                return Some(Referer {
+                    medium: Medium::Unknown,
+                    source: None,
+                    term: None,
+                })
+            }
+        };
+
+        let term = url
+            .query_pairs()
+            .find(|(key, _)| source.parameters.iter().any(|p| p == key))
+            .map(|(_, value)| value.into_owned())
+            .filter(|value| !value.is_empty());
+
+        Some(Referer {
+            medium: source.medium,
+            source: Some(source.name.clone()),
+            term,
+        })
+    }
+
+    /// Looks up the most specific known domain, first trying the host with
+    /// ever shorter path prefixes and then the parent domains of the host.
+    fn lookup(&self, host: &str, path: &str) -> Option<&Source> {
+        let mut host = host;
+
+        loop {
+            let mut path = path.trim_end_matches('/');
+            loop {
+                let key = format!("{}{}", host, path);
+                if let Some(&index) = self.domains.get(&key) {
+                    return Some(&self.sources[index]);
+                }
+                match path.rfind('/') {
+                    Some(end) if !path.is_empty() => path = &path[..end],
+                    _ => break,
+                }
+            }
+
+            host = &host[host.find('.')? + 1..];
+        }
+    }
+}
+
+fn is_same_or_subdomain(host: &str, domain: &str) -> bool {
+    host == domain
+        || (host.ends_with(domain) && host[..host.len() - domain.len()].ends_with('.'))
+}
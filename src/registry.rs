@@ -0,0 +1,218 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use super::{
+    Client, Device, Error, LruCache, Parser, UaCache, UserAgent, UserAgentParser, OS,
+};
+
+#[derive(Debug)]
+struct Entry {
+    parser: Arc<UserAgentParser>,
+    /// Identifies the parser in the shared cache. Tenants registered with the
+    /// same `Arc` share a generation, and with it their cached results.
+    generation: u64,
+}
+
+/// Manages the parsers of several tenants, e.g. customers or markets, built
+/// from different datasets. Parsers are replaced atomically: callers that
+/// obtained a tenant's parser keep using it, while later calls see the new one.
+///
+/// Optionally, full results are cached in a single `LruCache` shared by all
+/// tenants, keyed by the parser and the user agent. Tenants using the same
+/// parser share cached results, and the results of a replaced parser are
+/// dropped. Like with `CachedParser`, results carry no match info.
+///
+/// ```rust
+/// # use uaparser::*;
+/// let registry = ParserRegistry::new().cache(10_000);
+/// registry.insert("eu", UserAgentParser::from_yaml("./src/core/regexes.yaml")?);
+/// registry.reload("us", || UserAgentParser::from_yaml("./src/core/regexes.yaml"))?;
+///
+/// let parser = registry.tenant("eu").unwrap();
+/// let client = parser.parse("Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Firefox/70.0");
+/// assert_eq!(client.user_agent.family, "Firefox");
+/// # Ok::<_, Error>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct ParserRegistry {
+    tenants: RwLock<HashMap<String, Entry>>,
+    generations: AtomicU64,
+    cache: Option<LruCache>,
+}
+
+impl ParserRegistry {
+    pub fn new() -> ParserRegistry {
+        ParserRegistry::default()
+    }
+
+    /// Caches the full results of all tenants for up to `capacity` user
+    /// agents in total. Results of single categories aren't cached.
+    pub fn cache(mut self, capacity: usize) -> ParserRegistry {
+        self.cache = Some(LruCache::new(capacity));
+        self
+    }
+
+    /// Registers the parser of `tenant`, replacing and returning the previous
+    /// one. Pass the same `Arc` for several tenants to share one parser.
+    pub fn insert(
+        &self,
+        tenant: impl Into<String>,
+        parser: impl Into<Arc<UserAgentParser>>,
+    ) -> Option<Arc<UserAgentParser>> {
+        let parser = parser.into();
+        let mut tenants = self.write();
+
+        let shared = (tenants.values())
+            .find(|entry| Arc::ptr_eq(&entry.parser, &parser))
+            .map(|entry| entry.generation);
+        let generation =
+            shared.unwrap_or_else(|| self.generations.fetch_add(1, Ordering::Relaxed));
+
+        let previous = tenants.insert(tenant.into(), Entry { parser, generation });
+        self.retire(&tenants, previous)
+    }
+
+    /// Builds a new parser for `tenant` and swaps it in once it's ready. The
+    /// current parser keeps serving until then, and stays in place if `build`
+    /// fails.
+    pub fn reload<F>(&self, tenant: impl Into<String>, build: F) -> Result<(), Error>
+    where
+        F: FnOnce() -> Result<UserAgentParser, Error>,
+    {
+        self.insert(tenant, build()?);
+        Ok(())
+    }
+
+    /// Unregisters `tenant` and returns its parser
+    pub fn remove(&self, tenant: &str) -> Option<Arc<UserAgentParser>> {
+        let mut tenants = self.write();
+        let previous = tenants.remove(tenant);
+        self.retire(&tenants, previous)
+    }
+
+    /// Returns the current parser of `tenant`
+    pub fn get(&self, tenant: &str) -> Option<Arc<UserAgentParser>> {
+        let tenants = self.tenants.read().unwrap_or_else(|e| e.into_inner());
+        tenants.get(tenant).map(|entry| entry.parser.clone())
+    }
+
+    /// Returns a `Parser` for `tenant` that answers from the shared cache. It
+    /// holds on to the tenant's current parser, even if it's replaced.
+    pub fn tenant(&self, tenant: &str) -> Option<TenantParser<'_>> {
+        let tenants = self.tenants.read().unwrap_or_else(|e| e.into_inner());
+        let entry = tenants.get(tenant)?;
+        Some(TenantParser {
+            registry: self,
+            parser: entry.parser.clone(),
+            generation: entry.generation,
+        })
+    }
+
+    /// Returns the number of results in the shared cache
+    pub fn cached(&self) -> usize {
+        self.cache.as_ref().map_or(0, LruCache::len)
+    }
+
+    /// Returns the names of all registered tenants
+    pub fn tenants(&self) -> Vec<String> {
+        let tenants = self.tenants.read().unwrap_or_else(|e| e.into_inner());
+        tenants.keys().cloned().collect()
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<String, Entry>> {
+        self.tenants.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Drops the cached results of a replaced parser no tenant uses anymore
+    fn retire(
+        &self,
+        tenants: &HashMap<String, Entry>,
+        previous: Option<Entry>,
+    ) -> Option<Arc<UserAgentParser>> {
+        let previous = previous?;
+        let in_use =
+            (tenants.values()).any(|entry| entry.generation == previous.generation);
+
+        if let (false, Some(cache)) = (in_use, &self.cache) {
+            let prefix = cache_key(previous.generation, "");
+            cache.retain(|key| !key.starts_with(&prefix));
+        }
+
+        Some(previous.parser)
+    }
+}
+
+/// The parser of a tenant in a `ParserRegistry`, see `ParserRegistry::tenant`.
+/// Once its parser is replaced, its results are no longer cached.
+#[derive(Debug)]
+pub struct TenantParser<'a> {
+    registry: &'a ParserRegistry,
+    parser: Arc<UserAgentParser>,
+    generation: u64,
+}
+
+/// Returns the key of `user_agent` parsed by the parser of `generation` in
+/// the shared cache
+fn cache_key(generation: u64, user_agent: &str) -> String {
+    format!("{}\t{}", generation, user_agent)
+}
+
+impl TenantParser<'_> {
+    fn lookup(&self, user_agent: &str) -> Option<Client> {
+        let cache = self.registry.cache.as_ref()?;
+        cache.get(&cache_key(self.generation, user_agent))
+    }
+}
+
+impl Parser for TenantParser<'_> {
+    fn parse(&self, user_agent: &str) -> Client {
+        if let Some(client) = self.lookup(user_agent) {
+            return client;
+        }
+
+        let mut client = self.parser.parse(user_agent);
+        if let Some(cache) = &self.registry.cache {
+            client.match_info = None;
+            // Parsers are only retired under the write lock, so holding the
+            // read lock keeps a retired generation from caching results again
+            let tenants = self
+                .registry
+                .tenants
+                .read()
+                .unwrap_or_else(|e| e.into_inner());
+            if tenants
+                .values()
+                .any(|entry| entry.generation == self.generation)
+            {
+                cache.put(&cache_key(self.generation, user_agent), &client, None);
+            }
+        }
+        client
+    }
+
+    fn parse_device(&self, user_agent: &str) -> Device {
+        match self.lookup(user_agent) {
+            Some(client) => client.device,
+            None => self.parser.parse_device(user_agent),
+        }
+    }
+
+    fn parse_os(&self, user_agent: &str) -> OS {
+        match self.lookup(user_agent) {
+            Some(client) => client.os,
+            None => self.parser.parse_os(user_agent),
+        }
+    }
+
+    fn parse_user_agent(&self, user_agent: &str) -> UserAgent {
+        match self.lookup(user_agent) {
+            Some(client) => client.user_agent,
+            None => self.parser.parse_user_agent(user_agent),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::time::{Duration, Instant};
+
+use super::{Category, Parser, UserAgentParser};
+
+/// A canonical user agent along with the families uap-core assigns it. Fields
+/// left at `None` would fall back to `Other`, which depends on the builder.
+struct Case {
+    user_agent: &'static str,
+    user_agent_family: &'static str,
+    os_family: Option<&'static str>,
+    device_family: Option<&'static str>,
+}
+
+const CASES: &[Case] = &[
+    Case {
+        user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
+                     (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+        user_agent_family: "Chrome",
+        os_family: Some("Windows"),
+        device_family: None,
+    },
+    Case {
+        user_agent: "Mozilla/5.0 (iPhone; CPU iPhone OS 17_1 like Mac OS X) \
+                     AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.1 \
+                     Mobile/15E148 Safari/604.1",
+        user_agent_family: "Mobile Safari",
+        os_family: Some("iOS"),
+        device_family: Some("iPhone"),
+    },
+    Case {
+        user_agent: "Mozilla/5.0 (Linux; Android 13; SM-S911B) AppleWebKit/537.36 \
+                     (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36",
+        user_agent_family: "Chrome Mobile",
+        os_family: Some("Android"),
+        device_family: Some("Samsung SM-S911B"),
+    },
+    Case {
+        user_agent: "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) \
+                     AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.1 \
+                     Safari/605.1.15",
+        user_agent_family: "Safari",
+        os_family: Some("Mac OS X"),
+        device_family: Some("Mac"),
+    },
+    Case {
+        user_agent: "Mozilla/5.0 (X11; Ubuntu; Linux x86_64; rv:120.0) Gecko/20100101 \
+                     Firefox/120.0",
+        user_agent_family: "Firefox",
+        os_family: Some("Ubuntu"),
+        device_family: None,
+    },
+    Case {
+        user_agent: "Mozilla/5.0 (compatible; Googlebot/2.1; \
+                     +http://www.google.com/bot.html)",
+        user_agent_family: "Googlebot",
+        os_family: None,
+        device_family: Some("Spider"),
+    },
+];
+
+/// The outcome of a single check of `UserAgentParser::self_test`
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SelfTestCheck {
+    pub user_agent: &'static str,
+    pub category: Category,
+    pub expected: &'static str,
+    pub actual: String,
+}
+
+impl SelfTestCheck {
+    pub fn passed(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+/// The result of `UserAgentParser::self_test`
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct SelfTestReport {
+    pub checks: Vec<SelfTestCheck>,
+    /// The time it took to run all checks
+    pub elapsed: Duration,
+}
+
+impl SelfTestReport {
+    /// Returns whether every check passed
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(SelfTestCheck::passed)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &SelfTestCheck> {
+        self.checks.iter().filter(|check| !check.passed())
+    }
+}
+
+impl UserAgentParser {
+    /// Runs a small set of canonical user agents through every category and
+    /// checks the families of the results, e.g. to probe the readiness of a
+    /// service after reloading its dataset.
+    ///
+    /// ```rust
+    /// # use uaparser::*;
+    /// # let parser = UserAgentParser::from_yaml("./src/core/regexes.yaml").unwrap();
+    /// let report = parser.self_test();
+    /// for failure in report.failures() {
+    ///     eprintln!("{:?}: expected {}, got {}", failure.category, failure.expected, failure.actual);
+    /// }
+    /// ```
+    pub fn self_test(&self) -> SelfTestReport {
+        let start = Instant::now();
+        let mut checks = Vec::new();
+        let mut check = |case: &Case, category, expected, actual| {
+            checks.push(SelfTestCheck {
+                user_agent: case.user_agent,
+                category,
+                expected,
+                actual,
+            })
+        };
+
+        for case in CASES {
+            let user_agent = self.parse_user_agent(case.user_agent);
+            check(
+                case,
+                Category::UserAgent,
+                case.user_agent_family,
+                user_agent.family,
+            );
+
+            if let Some(expected) = case.os_family {
+                let os = self.parse_os(case.user_agent);
+                check(case, Category::Os, expected, os.family);
+            }
+
+            if let Some(expected) = case.device_family {
+                let device = self.parse_device(case.user_agent);
+                check(case, Category::Device, expected, device.family);
+            }
+        }
+
+        SelfTestReport {
+            checks,
+            elapsed: start.elapsed(),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use serde_derive::Serialize;
+
+use super::{Arch, Client};
+
+/// The `browser`, `os` and `device` contexts of a Sentry event. Serializes to
+/// an object keyed by context name that can be merged into `contexts`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SentryContexts {
+    pub browser: BrowserContext,
+    pub os: OsContext,
+    pub device: DeviceContext,
+}
+
+/// Sentry's `browser` context
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename = "browser")]
+pub struct BrowserContext {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// Sentry's `os` context
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename = "os")]
+pub struct OsContext {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// Sentry's `device` context
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename = "device")]
+pub struct DeviceContext {
+    pub family: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brand: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arch: Option<&'static str>,
+}
+
+impl Client {
+    /// Converts this `Client` into the contexts Sentry derives from a user
+    /// agent, using the field names of the Sentry event protocol
+    pub fn sentry_contexts(&self) -> SentryContexts {
+        SentryContexts {
+            browser: BrowserContext {
+                name: self.user_agent.family.clone(),
+                version: self.user_agent.version(),
+            },
+            os: OsContext {
+                name: self.os.family.clone(),
+                version: self.os.version(),
+            },
+            device: DeviceContext {
+                family: self.device.family.clone(),
+                brand: self.device.brand.clone(),
+                model: self.device.model.clone(),
+                arch: self.os.arch.map(Arch::as_str),
+            },
+        }
+    }
+}
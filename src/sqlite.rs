@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use rusqlite::{functions::FunctionFlags, Connection};
+
+use super::Parser;
+
+/// Registers the `ua_family(ua)`, `ua_os(ua)` and `ua_device(ua)` scalar
+/// functions, each returning the family of the respective category, on the
+/// given SQLite connection
+pub fn register_sqlite_functions<P>(
+    conn: &Connection,
+    parser: Arc<P>,
+) -> rusqlite::Result<()>
+where
+    P: Parser + Send + Sync + 'static,
+{
+    let flags = FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC;
+
+    let ua_parser = Arc::clone(&parser);
+    conn.create_scalar_function("ua_family", 1, flags, move |ctx| {
+        let user_agent: Option<String> = ctx.get(0)?;
+        Ok(user_agent.map(|ua| ua_parser.parse_user_agent(&ua).family))
+    })?;
+
+    let os_parser = Arc::clone(&parser);
+    conn.create_scalar_function("ua_os", 1, flags, move |ctx| {
+        let user_agent: Option<String> = ctx.get(0)?;
+        Ok(user_agent.map(|ua| os_parser.parse_os(&ua).family))
+    })?;
+
+    conn.create_scalar_function("ua_device", 1, flags, move |ctx| {
+        let user_agent: Option<String> = ctx.get(0)?;
+        Ok(user_agent.map(|ua| parser.parse_device(&ua).family))
+    })?;
+
+    Ok(())
+}
+
+/// Entry point of the loadable extension, built as a `cdylib` with the
+/// `sqlite-extension` feature:
+///
+/// ```sh
+/// cargo rustc --release --features sqlite-extension --crate-type cdylib
+/// ```
+///
+/// The regexes are read from the file named by the `UAPARSER_REGEXES`
+/// environment variable when the extension is loaded:
+///
+/// ```sql
+/// .load target/release/libuaparser
+/// SELECT ua_family(user_agent), count(*) FROM requests GROUP BY 1;
+/// ```
+///
+/// # Safety
+///
+/// Must only be called by SQLite while loading the extension.
+#[cfg(feature = "sqlite-extension")]
+#[no_mangle]
+pub unsafe extern "C" fn sqlite3_uaparser_init(
+    db: *mut rusqlite::ffi::sqlite3,
+    pz_err_msg: *mut *mut std::os::raw::c_char,
+    p_api: *mut rusqlite::ffi::sqlite3_api_routines,
+) -> std::os::raw::c_int {
+    Connection::extension_init2(db, pz_err_msg, p_api, extension_init)
+}
+
+#[cfg(feature = "sqlite-extension")]
+fn extension_init(conn: Connection) -> rusqlite::Result<bool> {
+    let failure = |message: String| {
+        rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_ERROR),
+            Some(message),
+        )
+    };
+
+    let path = std::env::var("UAPARSER_REGEXES")
+        .map_err(|_| failure("UAPARSER_REGEXES is not set".to_string()))?;
+    let parser = super::UserAgentParser::from_yaml(&path)
+        .map_err(|e| failure(format!("failed to load {}: {}", path, e)))?;
+
+    register_sqlite_functions(&conn, Arc::new(parser))?;
+    Ok(false)
+}
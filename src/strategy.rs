@@ -0,0 +1,135 @@
+//! proptest strategies for user agent strings and parse results, to property
+//! test the parser and code consuming its results:
+//!
+//! ```rust
+//! # use uaparser::*;
+//! use proptest::test_runner::TestRunner;
+//!
+//! let parser = UserAgentParser::from_yaml("./src/core/regexes.yaml")?;
+//! let mut runner = TestRunner::default();
+//! runner
+//!     .run(&strategy::user_agent(), |user_agent| {
+//!         assert_eq!(parser.parse(&user_agent), parser.parse(&user_agent));
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! # Ok::<_, Error>(())
+//! ```
+
+use proptest::{collection, option, prelude::*, sample::select};
+
+use super::{
+    fuzz::{ENGINES, PLATFORMS, PRODUCTS},
+    Arch, Client, Device, UserAgent, OS,
+};
+
+const USER_AGENT_FAMILIES: &[&str] = &[
+    "Chrome",
+    "Chrome Mobile",
+    "Firefox",
+    "Safari",
+    "Mobile Safari",
+    "Edge",
+    "Opera",
+    "Samsung Internet",
+    "Googlebot",
+    "curl",
+    "Other",
+];
+
+const OS_FAMILIES: &[&str] = &[
+    "Windows",
+    "Mac OS X",
+    "Linux",
+    "Ubuntu",
+    "Android",
+    "iOS",
+    "Chrome OS",
+    "Other",
+];
+
+/// Families of devices along with their brand and model
+const DEVICES: &[(&str, Option<&str>, Option<&str>)] = &[
+    ("iPhone", Some("Apple"), Some("iPhone")),
+    ("iPad", Some("Apple"), Some("iPad")),
+    ("Mac", Some("Apple"), Some("Mac")),
+    ("Pixel 7", Some("Google"), Some("Pixel 7")),
+    ("Samsung SM-S901B", Some("Samsung"), Some("SM-S901B")),
+    ("Spider", Some("Spider"), Some("Desktop")),
+    ("Other", None, None),
+];
+
+const ARCHS: &[Arch] = &[Arch::X86, Arch::X86_64, Arch::Arm, Arch::Arm64];
+
+/// User agent strings shaped like the ones sent by real clients, built from
+/// the same tokens as `UserAgentString` with arbitrary versions and noise
+pub fn user_agent() -> impl Strategy<Value = String> {
+    let product = (select(PRODUCTS), 0..=130u16, 0..=9u16)
+        .prop_map(|(product, major, minor)| format!("{}/{}.{}", product, major, minor));
+
+    (
+        any::<bool>(),
+        option::of(select(PLATFORMS)),
+        option::of(select(ENGINES)),
+        collection::vec(product, 1..=3),
+        option::of("\\PC{0,16}"),
+    )
+        .prop_map(|(mozilla, platform, engine, products, noise)| {
+            let mut tokens = Vec::new();
+            if mozilla {
+                tokens.push("Mozilla/5.0".to_owned());
+            }
+            tokens.extend(platform.map(|platform| format!("({})", platform)));
+            tokens.extend(engine.map(str::to_owned));
+            tokens.extend(products);
+            tokens.extend(noise);
+            tokens.join(" ").trim().to_owned()
+        })
+}
+
+/// Versions like `118`, or none
+fn version() -> impl Strategy<Value = Option<String>> {
+    option::of((0..200u16).prop_map(|version| version.to_string()))
+}
+
+/// Parse results with common families and arbitrary versions, as returned
+/// by a parser without match info. Fields beyond the families, versions,
+/// brand, model and architecture keep their defaults.
+pub fn client() -> impl Strategy<Value = Client> {
+    let user_agent = (select(USER_AGENT_FAMILIES), version(), version(), version())
+        .prop_map(|(family, major, minor, patch)| UserAgent {
+            family: family.to_owned(),
+            major,
+            minor,
+            patch,
+            ..UserAgent::default()
+        });
+
+    let os = (
+        select(OS_FAMILIES),
+        version(),
+        version(),
+        option::of(select(ARCHS)),
+    )
+        .prop_map(|(family, major, minor, arch)| OS {
+            family: family.to_owned(),
+            major,
+            minor,
+            arch,
+            ..OS::default()
+        });
+
+    let device = select(DEVICES).prop_map(|(family, brand, model)| Device {
+        family: family.to_owned(),
+        brand: brand.map(str::to_owned),
+        model: model.map(str::to_owned),
+        ..Device::default()
+    });
+
+    (user_agent, os, device).prop_map(|(user_agent, os, device)| Client {
+        device,
+        os,
+        user_agent,
+        match_info: None,
+    })
+}
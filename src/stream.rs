@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use futures_util::stream::{self, Stream, StreamExt};
+
+use super::{CachedParser, Client, LruCache, Parser};
+
+/// Maps a stream of user agents, e.g. consumed from a message queue, to a
+/// stream of their `Client`s in the same order.
+///
+/// Up to `batch_size` user agents that are ready at once are taken from the
+/// stream and parsed together, so that repeated ones within a batch are only
+/// parsed once, see `Parser::parse_batch`. Results are cached across batches
+/// for up to `cache_size` distinct user agents, see `CachedParser`.
+///
+/// Batches are parsed on tokio's blocking thread pool rather than on the task
+/// polling the stream, with at most `concurrency` of them in flight, so the
+/// stream has to be polled within a tokio runtime.
+///
+/// ```rust
+/// # use uaparser::*;
+/// use futures_util::stream::{self, StreamExt};
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let parser = UserAgentParser::from_yaml("./src/core/regexes.yaml").unwrap();
+/// let user_agents = stream::iter(vec!["curl/7.64.1".to_owned(); 3]);
+///
+/// let clients = parse_stream(parser, user_agents, 64, 4, 10_000);
+/// assert_eq!(clients.collect::<Vec<_>>().await.len(), 3);
+/// # });
+/// ```
+pub fn parse_stream<P, S>(
+    parser: P,
+    user_agents: S,
+    batch_size: usize,
+    concurrency: usize,
+    cache_size: usize,
+) -> impl Stream<Item = Client>
+where
+    P: Parser + Send + Sync + 'static,
+    S: Stream<Item = String>,
+{
+    let parser = CachedParser::new(parser, LruCache::new(cache_size));
+    let parser = Arc::new(parser);
+
+    user_agents
+        .ready_chunks(batch_size.max(1))
+        .map(move |batch| {
+            let parser = Arc::clone(&parser);
+            tokio::task::spawn_blocking(move || {
+                parser.parse_batch(batch.iter().map(String::as_str))
+            })
+        })
+        .buffered(concurrency.max(1))
+        .flat_map(|parsed| match parsed {
+            Ok(clients) => stream::iter(clients),
+            Err(error) => std::panic::resume_unwind(error.into_panic()),
+        })
+}
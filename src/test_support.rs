@@ -0,0 +1,234 @@
+use std::fmt::Display;
+
+use super::{Arch, Client, Device, UserAgent, OS};
+
+/// Splits a dotted version into its first four components
+fn split_version(version: impl Display) -> [Option<String>; 4] {
+    let version = version.to_string();
+    let mut parts = version
+        .split('.')
+        .filter(|part| !part.is_empty())
+        .map(ToOwned::to_owned);
+
+    [parts.next(), parts.next(), parts.next(), parts.next()]
+}
+
+/// Builds a `Device` for use in tests, see `Device::test`
+#[derive(Clone, Debug)]
+pub struct DeviceBuilder(Device);
+
+impl Device {
+    /// Starts building a `Device` with the `Other` family
+    pub fn test() -> DeviceBuilder {
+        DeviceBuilder(Device::default())
+    }
+}
+
+impl DeviceBuilder {
+    pub fn family(mut self, family: impl Into<String>) -> DeviceBuilder {
+        self.0.family = family.into();
+        self
+    }
+
+    pub fn brand(mut self, brand: impl Into<String>) -> DeviceBuilder {
+        self.0.brand = Some(brand.into());
+        self
+    }
+
+    pub fn model(mut self, model: impl Into<String>) -> DeviceBuilder {
+        self.0.model = Some(model.into());
+        self
+    }
+
+    pub fn kind(mut self, kind: impl Into<String>) -> DeviceBuilder {
+        self.0.kind = Some(kind.into());
+        self
+    }
+
+    pub fn build(self) -> Device {
+        self.0
+    }
+}
+
+/// Builds an `OS` for use in tests, see `OS::test`
+#[derive(Clone, Debug)]
+pub struct OsBuilder(OS);
+
+impl OS {
+    /// Starts building an `OS` with the `Other` family
+    pub fn test() -> OsBuilder {
+        OsBuilder(OS::default())
+    }
+}
+
+impl OsBuilder {
+    pub fn family(mut self, family: impl Into<String>) -> OsBuilder {
+        self.0.family = family.into();
+        self
+    }
+
+    /// Sets all version components from a dotted version like `"10.15.7"`
+    pub fn version(mut self, version: impl Display) -> OsBuilder {
+        let [major, minor, patch, patch_minor] = split_version(version);
+        self.0.major = major;
+        self.0.minor = minor;
+        self.0.patch = patch;
+        self.0.patch_minor = patch_minor;
+        self
+    }
+
+    pub fn arch(mut self, arch: Arch, bitness: u8) -> OsBuilder {
+        self.0.arch = Some(arch);
+        self.0.bitness = Some(bitness);
+        self
+    }
+
+    pub fn distro(
+        mut self,
+        distro: impl Into<String>,
+        version: Option<&str>,
+    ) -> OsBuilder {
+        self.0.distro = Some(distro.into());
+        self.0.distro_version = version.map(ToOwned::to_owned);
+        self
+    }
+
+    pub fn build(self) -> OS {
+        self.0
+    }
+}
+
+/// Builds a `UserAgent` for use in tests, see `UserAgent::test`
+#[derive(Clone, Debug)]
+pub struct UserAgentBuilder(UserAgent);
+
+impl UserAgent {
+    /// Starts building a `UserAgent` with the `Other` family
+    pub fn test() -> UserAgentBuilder {
+        UserAgentBuilder(UserAgent::default())
+    }
+}
+
+impl UserAgentBuilder {
+    pub fn family(mut self, family: impl Into<String>) -> UserAgentBuilder {
+        self.0.family = family.into();
+        self
+    }
+
+    /// Sets all version components from a dotted version like `"120.0.1"`
+    pub fn version(mut self, version: impl Display) -> UserAgentBuilder {
+        let [major, minor, patch, _] = split_version(version);
+        self.0.major = major;
+        self.0.minor = minor;
+        self.0.patch = patch;
+        self
+    }
+
+    pub fn chromium_version(mut self, version: impl Into<String>) -> UserAgentBuilder {
+        self.0.chromium_version = Some(version.into());
+        self
+    }
+
+    pub fn build(self) -> UserAgent {
+        self.0
+    }
+}
+
+/// Builds a `Client` for use in tests, with shortcuts for the most common
+/// browsers and platforms named the way the uap-core dataset reports them.
+///
+/// ```rust
+/// # use uaparser::*;
+/// let client = Client::test().chrome("120.0.6099").windows(10).build();
+///
+/// assert_eq!(client.user_agent.family, "Chrome");
+/// assert_eq!(client.user_agent.major.as_deref(), Some("120"));
+/// assert_eq!(client.os.family, "Windows");
+/// assert_eq!(client.os.major.as_deref(), Some("10"));
+/// assert_eq!(client.device.family, "Other");
+/// ```
+#[derive(Clone, Debug)]
+pub struct ClientBuilder(Client);
+
+impl Client {
+    /// Starts building a `Client` with `Other` for every family
+    pub fn test() -> ClientBuilder {
+        ClientBuilder(Client::default())
+    }
+}
+
+impl ClientBuilder {
+    pub fn device(mut self, device: Device) -> ClientBuilder {
+        self.0.device = device;
+        self
+    }
+
+    pub fn os(mut self, os: OS) -> ClientBuilder {
+        self.0.os = os;
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: UserAgent) -> ClientBuilder {
+        self.0.user_agent = user_agent;
+        self
+    }
+
+    /// Sets a device the way it's reported for most phones and tablets, e.g.
+    /// `Samsung SM-G960F` for the `Samsung` brand and `SM-G960F` model
+    pub fn phone(self, brand: &str, model: &str) -> ClientBuilder {
+        let device = Device::test()
+            .family(format!("{} {}", brand, model))
+            .brand(brand)
+            .model(model)
+            .build();
+        self.device(device)
+    }
+
+    pub fn browser(self, family: &str, version: impl Display) -> ClientBuilder {
+        self.user_agent(UserAgent::test().family(family).version(version).build())
+    }
+
+    pub fn chrome(self, version: impl Display) -> ClientBuilder {
+        self.browser("Chrome", version)
+    }
+
+    pub fn edge(self, version: impl Display) -> ClientBuilder {
+        self.browser("Edge", version)
+    }
+
+    pub fn firefox(self, version: impl Display) -> ClientBuilder {
+        self.browser("Firefox", version)
+    }
+
+    pub fn safari(self, version: impl Display) -> ClientBuilder {
+        self.browser("Safari", version)
+    }
+
+    pub fn platform(self, family: &str, version: impl Display) -> ClientBuilder {
+        self.os(OS::test().family(family).version(version).build())
+    }
+
+    pub fn windows(self, version: impl Display) -> ClientBuilder {
+        self.platform("Windows", version)
+    }
+
+    pub fn mac_os(self, version: impl Display) -> ClientBuilder {
+        self.platform("Mac OS X", version)
+    }
+
+    pub fn ios(self, version: impl Display) -> ClientBuilder {
+        self.platform("iOS", version)
+    }
+
+    pub fn android(self, version: impl Display) -> ClientBuilder {
+        self.platform("Android", version)
+    }
+
+    pub fn linux(self) -> ClientBuilder {
+        self.os(OS::test().family("Linux").build())
+    }
+
+    pub fn build(self) -> Client {
+        self.0
+    }
+}
@@ -0,0 +1,86 @@
+use headers::{Header, HeaderName, HeaderValue};
+
+use super::{Client, Device, Parser, UserAgent, UserAgentExt, UserAgentParser, OS};
+
+/// Parses the value of a typed `User-Agent` header, like a `str`
+impl UserAgentExt for headers::UserAgent {
+    fn parse_client<P: Parser + ?Sized>(&self, parser: &P) -> Client {
+        parser.parse(self.as_str())
+    }
+
+    fn parse_device<P: Parser + ?Sized>(&self, parser: &P) -> Device {
+        parser.parse_device(self.as_str())
+    }
+
+    fn parse_os<P: Parser + ?Sized>(&self, parser: &P) -> OS {
+        parser.parse_os(self.as_str())
+    }
+
+    fn parse_user_agent<P: Parser + ?Sized>(&self, parser: &P) -> UserAgent {
+        parser.parse_user_agent(self.as_str())
+    }
+}
+
+/// A typed `User-Agent` header that is parsed with the bundled parser as
+/// it's decoded, e.g. to extract it with axum's `TypedHeader`. It encodes
+/// back to the original value.
+///
+/// ```rust
+/// # use uaparser::*;
+/// use headers::HeaderMapExt;
+///
+/// let mut headers = headers::HeaderMap::new();
+/// headers.insert("user-agent", "Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Firefox/70.0".parse().unwrap());
+///
+/// let parsed = headers.typed_get::<ParsedUserAgent>().unwrap();
+/// assert_eq!(parsed.client().user_agent.family, "Firefox");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedUserAgent {
+    header: headers::UserAgent,
+    client: Client,
+}
+
+impl ParsedUserAgent {
+    /// Parses `header` with `parser` rather than the bundled parser
+    pub fn new<P: Parser + ?Sized>(header: headers::UserAgent, parser: &P) -> Self {
+        let client = header.parse_client(parser);
+        ParsedUserAgent { header, client }
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub fn header(&self) -> &headers::UserAgent {
+        &self.header
+    }
+
+    pub fn into_client(self) -> Client {
+        self.client
+    }
+}
+
+impl From<ParsedUserAgent> for Client {
+    fn from(parsed: ParsedUserAgent) -> Client {
+        parsed.client
+    }
+}
+
+impl Header for ParsedUserAgent {
+    fn name() -> &'static HeaderName {
+        headers::UserAgent::name()
+    }
+
+    fn decode<'i, I>(values: &mut I) -> Result<Self, headers::Error>
+    where
+        I: Iterator<Item = &'i HeaderValue>,
+    {
+        let header = headers::UserAgent::decode(values)?;
+        Ok(ParsedUserAgent::new(header, UserAgentParser::bundled()))
+    }
+
+    fn encode<E: Extend<HeaderValue>>(&self, values: &mut E) {
+        self.header.encode(values)
+    }
+}
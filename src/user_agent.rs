@@ -1,4 +1,4 @@
-use super::Deserialize;
+use super::{os::join_version, Deserialize, Product, Serialize};
 
 pub type Family = String;
 pub type Major = String;
@@ -6,13 +6,31 @@ pub type Minor = String;
 pub type Patch = String;
 
 /// Describes the `Family` as well as the `Major`, `Minor`, and `Patch` versions
-/// of a `UserAgent` client
-#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+/// of a `UserAgent` client, along with the version of the Chromium engine, the
+/// desktop application embedding it, and the framework of native apps where
+/// the user agent reveals them
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "async-graphql", derive(async_graphql::SimpleObject))]
 pub struct UserAgent {
     pub family: Family,
     pub major: Option<Major>,
     pub minor: Option<Minor>,
     pub patch: Option<Patch>,
+    /// The full version of Chromium, e.g. `"118.0.5993.159"`, which can
+    /// differ from the version of browsers based on it. Only detected if
+    /// enabled with `UserAgentParserBuilder::detect_chromium_version`.
+    pub chromium_version: Option<String>,
+    /// The application embedding the browser engine, e.g. `Slack`. Desktop
+    /// apps are only detected if enabled with
+    /// `UserAgentParserBuilder::detect_apps`.
+    pub app: Option<Product>,
+    /// The runtime the `app` is built with, e.g. `Electron`
+    pub runtime: Option<Product>,
+    /// The networking stack or framework of a native app, e.g. `okhttp`. Only
+    /// detected if enabled with `UserAgentParserBuilder::detect_frameworks`.
+    pub framework: Option<Product>,
 }
 
 impl Default for UserAgent {
@@ -22,6 +40,32 @@ impl Default for UserAgent {
             major: None,
             minor: None,
             patch: None,
+            chromium_version: None,
+            app: None,
+            runtime: None,
+            framework: None,
         }
     }
 }
+
+impl UserAgent {
+    /// Returns the user agent of user agents no rule matches
+    pub fn unknown() -> UserAgent {
+        UserAgent::default()
+    }
+
+    /// Returns whether the family is `Other`, as when no rule matched
+    pub fn is_unknown(&self) -> bool {
+        self.family == "Other"
+    }
+
+    /// Returns whether the family is unknown and no other field is set
+    pub fn is_empty(&self) -> bool {
+        *self == UserAgent::default()
+    }
+
+    /// Joins the known version components with dots, e.g. `"120.0.1"`
+    pub fn version(&self) -> Option<String> {
+        join_version(&[&self.major, &self.minor, &self.patch])
+    }
+}
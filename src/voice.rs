@@ -0,0 +1,89 @@
+use super::{Client, Device};
+
+/// The voice assistant platform behind a smart speaker or its content fetcher
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum VoicePlatform {
+    Alexa,
+    GoogleAssistant,
+    Siri,
+}
+
+/// Markers of every platform, along with the device family and brand its
+/// requests are reported as. `AppleCoreMedia` alone isn't a marker, since it
+/// plays media on every Apple device, but it is on a HomePod.
+const PLATFORMS: &[(VoicePlatform, &[&str], &str, &str)] = &[
+    (
+        VoicePlatform::Alexa,
+        &[
+            "AlexaMediaPlayer",
+            "AlexaService",
+            "Amazon-Alexa",
+            "Echo Dot",
+        ],
+        "Amazon Echo",
+        "Amazon",
+    ),
+    (
+        VoicePlatform::GoogleAssistant,
+        &[
+            "Google-Home",
+            "GoogleHome",
+            "Google-Assistant",
+            "Google Assistant",
+        ],
+        "Google Home",
+        "Google",
+    ),
+    (
+        VoicePlatform::Siri,
+        &["HomePod", "Siri/", "SiriKit"],
+        "HomePod",
+        "Apple",
+    ),
+];
+
+impl VoicePlatform {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            VoicePlatform::Alexa => "Alexa",
+            VoicePlatform::GoogleAssistant => "Google Assistant",
+            VoicePlatform::Siri => "Siri",
+        }
+    }
+
+    /// Returns the platform of a device family reported for smart speakers
+    pub fn from_device(family: &str) -> Option<VoicePlatform> {
+        PLATFORMS
+            .iter()
+            .find(|&&(_, _, device, _)| device == family)
+            .map(|&(platform, ..)| platform)
+    }
+}
+
+impl std::fmt::Display for VoicePlatform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Reports requests of voice assistants as their smart speaker, see
+/// `UserAgentParserBuilder::detect_voice_assistants`
+pub(crate) fn apply(user_agent: &str, device: &mut Device) {
+    let platform = PLATFORMS
+        .iter()
+        .find(|(_, markers, ..)| markers.iter().any(|m| user_agent.contains(m)));
+
+    if let Some(&(_, _, family, brand)) = platform {
+        device.family = family.to_owned();
+        device.brand = Some(brand.to_owned());
+        device.model = Some(family.to_owned());
+    }
+}
+
+impl Client {
+    /// Returns the voice assistant platform of smart speakers, as reported
+    /// with `UserAgentParserBuilder::detect_voice_assistants`
+    pub fn voice_platform(&self) -> Option<VoicePlatform> {
+        VoicePlatform::from_device(&self.device.family)
+    }
+}
@@ -0,0 +1,74 @@
+/// Safari releases by the WebKit build they shipped with, as `(major build,
+/// minor build, Safari major, Safari minor)`. A minor build of `None` covers
+/// every build of the major one not listed before. Builds from 605.1.15 on
+/// are frozen across Safari releases and can't be resolved.
+const SAFARI_BUILDS: &[(u32, Option<u32>, &str, &str)] = &[
+    (604, None, "11", "0"),
+    (603, None, "10", "1"),
+    (602, None, "10", "0"),
+    (601, Some(5), "9", "1"),
+    (601, None, "9", "0"),
+    (600, None, "8", "0"),
+    (538, None, "8", "0"),
+    (537, Some(71), "7", "0"),
+    (537, Some(43), "6", "1"),
+    (536, None, "6", "0"),
+    (534, None, "5", "1"),
+    (533, None, "5", "0"),
+    (531, None, "4", "0"),
+    (530, None, "4", "0"),
+    (528, None, "4", "0"),
+    (525, None, "3", "1"),
+    (523, None, "3", "0"),
+    (522, None, "3", "0"),
+    (419, None, "2", "0"),
+    (418, None, "2", "0"),
+    (417, None, "2", "0"),
+    (416, None, "2", "0"),
+    (412, None, "2", "0"),
+    (312, None, "1", "3"),
+    (125, None, "1", "2"),
+    (100, None, "1", "1"),
+    (85, None, "1", "0"),
+];
+
+/// The families of uap-core that denote Safari and its web views
+const SAFARI_FAMILIES: &[&str] =
+    &["Safari", "Mobile Safari", "Mobile Safari UI/WKWebView"];
+
+/// Returns the major and minor version of the Safari release that shipped
+/// the WebKit `build`, e.g. `"601.5.17"`, if it's known
+///
+/// ```rust
+/// # use uaparser::*;
+/// assert_eq!(safari_version_from_webkit("601.5.17"), Some(("9", "1")));
+/// assert_eq!(safari_version_from_webkit("605.1.15"), None);
+/// ```
+pub fn safari_version_from_webkit(build: &str) -> Option<(&'static str, &'static str)> {
+    let mut parts = build.split('.').map(|part| part.parse::<u32>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().flatten();
+
+    SAFARI_BUILDS
+        .iter()
+        .find(|&&(m, n, _, _)| m == major && (n.is_none() || n == minor))
+        .map(|&(_, _, major, minor)| (major, minor))
+}
+
+/// Fills in the version of Safari user agents that lack one, like those of
+/// web views, from their `AppleWebKit` build
+pub(crate) fn resolve_safari(
+    user_agent: &str,
+    family: &str,
+) -> Option<(&'static str, &'static str)> {
+    if !SAFARI_FAMILIES.contains(&family) {
+        return None;
+    }
+
+    let start = user_agent.find("AppleWebKit/")? + "AppleWebKit/".len();
+    let build = &user_agent[start..];
+    let end = build
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(build.len());
+    safari_version_from_webkit(&build[..end])
+}
@@ -0,0 +1,13 @@
+#!/bin/sh
+# Vendors the `regexes.yaml` of a uap-core tag as a snapshot that a feature of
+# this crate can pin to, e.g. `./snapshot.sh <tag> 2024-01` for the
+# `regexes-2024-01` feature. Snapshots are never changed once released.
+set -eu
+
+tag=$1
+name=$2
+core=$(dirname "$0")/../src/core
+
+git -C "$core" fetch --tags origin
+mkdir -p "$(dirname "$0")/snapshots"
+git -C "$core" show "$tag:regexes.yaml" > "$(dirname "$0")/snapshots/$name.yaml"
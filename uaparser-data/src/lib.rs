@@ -0,0 +1,20 @@
+//! The datasets bundled with `uaparser`, split off from the engine so that
+//! they can be released whenever uap-core changes, without a new release of
+//! the engine. Alternative data packages only need to provide their regexes
+//! in the same format to plug into the engine, e.g. through
+//! `UserAgentParser::from_bytes`.
+//!
+//! By default, the dataset is the latest revision of uap-core, so updating
+//! this crate may change how user agents are classified. Snapshots that never
+//! change are vendored with `snapshot.sh` and pinned with a feature of their
+//! own, like `regexes-2024-01` for `snapshots/2024-01.yaml`, once released.
+
+#[cfg(not(feature = "regexes-latest"))]
+compile_error!("enable `regexes-latest` or the feature of a pinned snapshot");
+
+/// The `regexes.yaml` of uap-core
+pub static REGEXES: &str = include_str!("../regexes.yaml");
+
+/// The dataset in `REGEXES`, either the name of a snapshot like `2024-01` or
+/// `latest`
+pub static VERSION: &str = "latest";
@@ -0,0 +1,234 @@
+//! Macros that check family names against the uap-core dataset at compile
+//! time. Use them through the `macros` feature of the `uaparser` crate.
+//!
+//! A family is accepted if a rule names it in its replacement, or captures it
+//! from one of a few literals, like `(Firefox|Fennec)`. Families that only
+//! rules capturing arbitrary text could produce are rejected, since any name
+//! would pass that check; compare against plain strings for those.
+
+use std::{collections::BTreeSet, sync::OnceLock};
+
+use proc_macro::TokenStream;
+use quote::quote;
+use regex_syntax::{
+    hir::{literal::Extractor, Hir, HirKind},
+    ParserBuilder,
+};
+use serde_derive::Deserialize;
+use syn::{parse_macro_input, LitStr};
+
+#[derive(Deserialize)]
+struct RegexFile {
+    user_agent_parsers: Vec<UserAgentEntry>,
+    os_parsers: Vec<OsEntry>,
+    device_parsers: Vec<DeviceEntry>,
+}
+
+#[derive(Deserialize)]
+struct UserAgentEntry {
+    regex: String,
+    family_replacement: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct OsEntry {
+    regex: String,
+    os_replacement: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DeviceEntry {
+    regex: String,
+    regex_flag: Option<String>,
+    device_replacement: Option<String>,
+}
+
+/// The most families derived from a single rule, beyond which the rule is
+/// considered open-ended and skipped
+const MAX_FAMILIES: usize = 256;
+
+/// Every family a single category of rules can produce for certain. Rules
+/// whose family is captured from the user agent without being limited to a
+/// few literals, like `(\S+)` or `$1` of such a group, could produce any name
+/// and are skipped, so their families are rejected rather than letting every
+/// name through.
+struct Families(BTreeSet<String>);
+
+impl Families {
+    fn new<'a>(
+        rules: impl Iterator<Item = (&'a str, Option<&'a str>, Option<&'a str>)>,
+    ) -> Families {
+        let mut families = BTreeSet::new();
+        families.insert("Other".to_owned());
+
+        for (regex, flags, replacement) in rules {
+            let values = match replacement {
+                Some(replacement) => {
+                    expand(replacement, |index| group_literals(regex, flags, index))
+                }
+                None => group_literals(regex, flags, 1),
+            };
+
+            let values = values.into_iter().flatten();
+            families.extend(values.map(|value| value.trim().to_owned()));
+        }
+
+        families.remove("");
+        Families(families)
+    }
+
+    fn contains(&self, family: &str) -> bool {
+        self.0.contains(family)
+    }
+}
+
+/// Expands the placeholders of a replacement like `$1 Mobile` with every
+/// value of their groups
+fn expand(
+    replacement: &str,
+    group_values: impl Fn(usize) -> Option<Vec<String>>,
+) -> Option<Vec<String>> {
+    let mut expansions = vec![String::new()];
+    let mut chars = replacement.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let index = match chars.peek().and_then(|d| d.to_digit(10)) {
+            Some(index) if c == '$' => index as usize,
+            _ => {
+                expansions
+                    .iter_mut()
+                    .for_each(|expansion| expansion.push(c));
+                continue;
+            }
+        };
+        chars.next();
+
+        let values = group_values(index)?;
+        if expansions.len() * values.len() > MAX_FAMILIES {
+            return None;
+        }
+        expansions = (expansions.iter())
+            .flat_map(|expansion| {
+                values.iter().map(move |value| expansion.clone() + value)
+            })
+            .collect();
+    }
+
+    Some(expansions)
+}
+
+/// Returns the literals the capture group `index` of `regex` matches, unless
+/// it matches more than a few or isn't limited to literals at all. Regexes
+/// using syntax beyond the `regex` crate, like look-around, are skipped.
+fn group_literals(regex: &str, flags: Option<&str>, index: usize) -> Option<Vec<String>> {
+    let hir = ParserBuilder::new()
+        .case_insensitive(matches!(flags, Some(flags) if flags.contains('i')))
+        .build()
+        .parse(regex)
+        .ok()?;
+
+    let seq = Extractor::new().extract(find_group(&hir, index)?);
+    let literals = seq.literals()?;
+    if literals.len() > MAX_FAMILIES || !literals.iter().all(|literal| literal.is_exact())
+    {
+        return None;
+    }
+
+    (literals.iter())
+        .map(|literal| String::from_utf8(literal.as_bytes().to_vec()).ok())
+        .collect()
+}
+
+/// Finds the capture group with the given `index` within `hir`
+fn find_group(hir: &Hir, index: usize) -> Option<&Hir> {
+    match hir.kind() {
+        HirKind::Capture(capture) if capture.index as usize == index => {
+            Some(&capture.sub)
+        }
+        HirKind::Capture(capture) => find_group(&capture.sub, index),
+        HirKind::Repetition(repetition) => find_group(&repetition.sub, index),
+        HirKind::Concat(hirs) | HirKind::Alternation(hirs) => {
+            hirs.iter().find_map(|hir| find_group(hir, index))
+        }
+        HirKind::Empty | HirKind::Literal(_) | HirKind::Class(_) | HirKind::Look(_) => {
+            None
+        }
+    }
+}
+
+struct Dataset {
+    user_agent: Families,
+    os: Families,
+    device: Families,
+}
+
+fn dataset() -> &'static Dataset {
+    static DATASET: OnceLock<Dataset> = OnceLock::new();
+
+    DATASET.get_or_init(|| {
+        let file: RegexFile = serde_yaml::from_str(uaparser_data::REGEXES)
+            .expect("bundled regexes are invalid");
+
+        Dataset {
+            user_agent: Families::new(file.user_agent_parsers.iter().map(|entry| {
+                (
+                    entry.regex.as_str(),
+                    None,
+                    entry.family_replacement.as_deref(),
+                )
+            })),
+            os: Families::new(file.os_parsers.iter().map(|entry| {
+                (entry.regex.as_str(), None, entry.os_replacement.as_deref())
+            })),
+            device: Families::new(file.device_parsers.iter().map(|entry| {
+                (
+                    entry.regex.as_str(),
+                    entry
+                        .regex_flag
+                        .as_deref()
+                        .filter(|flags| !flags.is_empty()),
+                    entry.device_replacement.as_deref(),
+                )
+            })),
+        }
+    })
+}
+
+fn check(input: TokenStream, families: &Families, category: &str) -> TokenStream {
+    let family = parse_macro_input!(input as LitStr);
+
+    if !families.contains(&family.value()) {
+        let message = format!(
+            "`{}` is not a {} family in the uap-core dataset",
+            family.value(),
+            category
+        );
+        return syn::Error::new(family.span(), message)
+            .to_compile_error()
+            .into();
+    }
+
+    quote!(#family).into()
+}
+
+/// Expands to the given `UserAgent` family if the dataset can produce it, and
+/// fails to compile otherwise. Usable wherever a string literal is, including
+/// `match` patterns.
+#[proc_macro]
+pub fn ua_family(input: TokenStream) -> TokenStream {
+    check(input, &dataset().user_agent, "user agent")
+}
+
+/// Expands to the given `OS` family if the dataset can produce it, and fails
+/// to compile otherwise
+#[proc_macro]
+pub fn os_family(input: TokenStream) -> TokenStream {
+    check(input, &dataset().os, "OS")
+}
+
+/// Expands to the given `Device` family if the dataset can produce it, and
+/// fails to compile otherwise
+#[proc_macro]
+pub fn device_family(input: TokenStream) -> TokenStream {
+    check(input, &dataset().device, "device")
+}
@@ -0,0 +1,7 @@
+#[test]
+fn ui() {
+    let cases = trybuild::TestCases::new();
+    cases.pass("tests/ui/known_families.rs");
+    cases.compile_fail("tests/ui/unknown_family.rs");
+    cases.compile_fail("tests/ui/captured_family.rs");
+}
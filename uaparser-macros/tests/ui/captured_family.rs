@@ -0,0 +1,6 @@
+use uaparser_macros::ua_family;
+
+// Rules capturing arbitrary text could produce this, but no rule names it
+fn main() {
+    let _ = ua_family!("Definitely Not A Browser");
+}
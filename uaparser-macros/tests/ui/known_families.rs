@@ -0,0 +1,11 @@
+use uaparser_macros::{device_family, os_family, ua_family};
+
+fn main() {
+    match "Firefox" {
+        ua_family!("Firefox") | ua_family!("Other") => {}
+        _ => unreachable!(),
+    }
+
+    assert_eq!(os_family!("Windows"), "Windows");
+    assert_eq!(device_family!("Spider"), "Spider");
+}
@@ -0,0 +1,5 @@
+use uaparser_macros::ua_family;
+
+fn main() {
+    let _ = ua_family!("Firefx");
+}
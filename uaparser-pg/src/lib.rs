@@ -0,0 +1,149 @@
+//! PostgreSQL extension exposing the UA Parser to SQL. Build and install it
+//! with [`cargo pgrx`](https://github.com/pgcentralfoundation/pgrx):
+//!
+//! ```sh
+//! cargo pgrx install --release
+//! ```
+//!
+//! ```sql
+//! CREATE EXTENSION uaparser_pg;
+//! SELECT (ua_parse(user_agent)).* FROM requests;
+//! SELECT ua_family(user_agent), count(*) FROM requests GROUP BY 1;
+//! ```
+//!
+//! The `regexes.yaml` of the `src/core` submodule is compiled into the
+//! extension. Every backend compiles the parser once, on first use, and shares
+//! it between all calls within that backend.
+
+use std::sync::OnceLock;
+
+use pgrx::prelude::*;
+use uaparser::{Client, Parser, UserAgentParser};
+
+pgrx::pg_module_magic!();
+
+static PARSER: OnceLock<UserAgentParser> = OnceLock::new();
+
+fn parser() -> &'static UserAgentParser {
+    PARSER.get_or_init(|| {
+        UserAgentParser::from_bytes(include_bytes!("../../src/core/regexes.yaml"))
+            .expect("bundled regexes.yaml is valid")
+    })
+}
+
+extension_sql!(
+    r#"
+CREATE TYPE ua_client AS (
+    ua_family text,
+    ua_major text,
+    ua_minor text,
+    ua_patch text,
+    os_family text,
+    os_major text,
+    os_minor text,
+    os_patch text,
+    os_patch_minor text,
+    device_family text,
+    device_brand text,
+    device_model text
+);
+"#,
+    name = "create_ua_client",
+    bootstrap
+);
+
+/// Parses a user agent into the `ua_client` composite type
+#[pg_extern(immutable, parallel_safe)]
+fn ua_parse(user_agent: &str) -> pgrx::composite_type!('static, "ua_client") {
+    let Client {
+        device,
+        os,
+        user_agent,
+        ..
+    } = parser().parse(user_agent);
+
+    let mut tuple = PgHeapTuple::new_composite_type("ua_client")
+        .expect("ua_client type is created by the extension");
+
+    let fields = [
+        ("ua_family", Some(user_agent.family)),
+        ("ua_major", user_agent.major),
+        ("ua_minor", user_agent.minor),
+        ("ua_patch", user_agent.patch),
+        ("os_family", Some(os.family)),
+        ("os_major", os.major),
+        ("os_minor", os.minor),
+        ("os_patch", os.patch),
+        ("os_patch_minor", os.patch_minor),
+        ("device_family", Some(device.family)),
+        ("device_brand", device.brand),
+        ("device_model", device.model),
+    ];
+
+    for (name, value) in fields {
+        if let Some(value) = value {
+            tuple
+                .set_by_name(name, value)
+                .expect("ua_client attributes are text");
+        }
+    }
+
+    tuple
+}
+
+/// Returns the browser family of a user agent
+#[pg_extern(immutable, parallel_safe)]
+fn ua_family(user_agent: &str) -> String {
+    parser().parse_user_agent(user_agent).family
+}
+
+/// Returns the operating system family of a user agent
+#[pg_extern(immutable, parallel_safe)]
+fn ua_os(user_agent: &str) -> String {
+    parser().parse_os(user_agent).family
+}
+
+/// Returns the device family of a user agent
+#[pg_extern(immutable, parallel_safe)]
+fn ua_device(user_agent: &str) -> String {
+    parser().parse_device(user_agent).family
+}
+
+/// Returns the device brand of a user agent, if known
+#[pg_extern(immutable, parallel_safe)]
+fn ua_device_brand(user_agent: &str) -> Option<String> {
+    parser().parse_device(user_agent).brand
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+#[pg_schema]
+mod tests {
+    use pgrx::prelude::*;
+
+    const FIREFOX: &str =
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:109.0) Gecko/20100101 Firefox/115.0";
+
+    #[pg_test]
+    fn ua_family() {
+        assert_eq!("Firefox", crate::ua_family(FIREFOX));
+    }
+
+    #[pg_test]
+    fn ua_parse() {
+        let family = Spi::get_one::<String>(&format!(
+            "SELECT (ua_parse('{}')).os_family",
+            FIREFOX
+        ));
+        assert_eq!(Ok(Some("Windows".to_string())), family);
+    }
+}
+
+/// Required by `cargo pgrx test`
+#[cfg(test)]
+pub mod pg_test {
+    pub fn setup(_options: Vec<&str>) {}
+
+    pub fn postgresql_conf_options() -> Vec<&'static str> {
+        vec![]
+    }
+}
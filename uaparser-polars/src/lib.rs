@@ -0,0 +1,187 @@
+//! Polars expressions parsing user agents with the UA Parser, registered as
+//! the `ua` namespace of `Expr`. `UaNameSpace::parse` parses every user agent
+//! once into a struct column with a field per part of the `Client`, which can
+//! be unnested into columns:
+//!
+//! ```rust
+//! use polars::prelude::*;
+//! use uaparser_polars::UaExpr;
+//!
+//! let requests = df!(
+//!     "ua" => [Some("Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Firefox/70.0"), None],
+//! )?;
+//!
+//! let enriched = requests
+//!     .lazy()
+//!     .with_column(col("ua").ua().parse().alias("client"))
+//!     .unnest(["client"])
+//!     .collect()?;
+//!
+//! let browsers = enriched.column("ua_family")?.str()?;
+//! assert_eq!(browsers.get(0), Some("Firefox"));
+//! assert_eq!(browsers.get(1), None);
+//! assert_eq!(enriched.column("os_family")?.str()?.get(0), Some("Linux"));
+//! # Ok::<_, PolarsError>(())
+//! ```
+//!
+//! Expressions of single fields parse the user agents on their own, so
+//! `parse` is cheaper for more than one field:
+//!
+//! ```rust
+//! # use polars::prelude::*;
+//! # use uaparser_polars::UaExpr;
+//! # let requests = df!(
+//! #     "ua" => [Some("Mozilla/5.0 (X11; Linux x86_64; rv:70.0) Firefox/70.0"), None],
+//! # )?;
+//! let browsers = requests
+//!     .lazy()
+//!     .select([col("ua").ua().family().alias("browser")])
+//!     .collect()?;
+//!
+//! let browsers = browsers.column("browser")?.str()?;
+//! assert_eq!(browsers.get(0), Some("Firefox"));
+//! assert_eq!(browsers.get(1), None);
+//! # Ok::<_, PolarsError>(())
+//! ```
+//!
+//! Expressions use the parser over the bundled uap-core dataset, unless
+//! another one is passed to `UaNameSpace::with_parser`. Each chunk of a column
+//! is parsed with `Parser::parse_batch`, so repeated user agents are parsed
+//! once per chunk. Null user agents give null structs.
+
+use std::sync::Arc;
+
+use polars::prelude::*;
+use uaparser::{Client, Parser, UserAgentParser};
+
+/// Adds the `ua` namespace to `Expr`
+pub trait UaExpr {
+    fn ua(self) -> UaNameSpace;
+}
+
+impl UaExpr for Expr {
+    fn ua(self) -> UaNameSpace {
+        UaNameSpace {
+            expr: self,
+            parser: Arc::new(UserAgentParser::bundled()),
+        }
+    }
+}
+
+type SharedParser = Arc<dyn BatchParser>;
+
+/// Object-safe access to `Parser::parse_batch`
+trait BatchParser: Send + Sync {
+    fn parse_all(&self, user_agents: Vec<&str>) -> Vec<Client>;
+}
+
+impl<P: Parser + Send + Sync> BatchParser for P {
+    fn parse_all(&self, user_agents: Vec<&str>) -> Vec<Client> {
+        self.parse_batch(user_agents)
+    }
+}
+
+/// Expressions extracting fields of the `Client` parsed from a string column
+pub struct UaNameSpace {
+    expr: Expr,
+    parser: SharedParser,
+}
+
+impl UaNameSpace {
+    /// Parses with `parser` instead of the bundled one
+    pub fn with_parser<P>(mut self, parser: P) -> UaNameSpace
+    where
+        P: Parser + Send + Sync + 'static,
+    {
+        self.parser = Arc::new(parser);
+        self
+    }
+
+    /// Parses into a struct with the string fields `ua_family`, `ua_major`,
+    /// `os_family`, `os_major`, `device_family`, `device_brand` and
+    /// `device_model`
+    pub fn parse(self) -> Expr {
+        let parser = self.parser;
+        let fields = (FIELDS.iter())
+            .map(|&(name, _)| Field::new(name.into(), DataType::String))
+            .collect();
+
+        self.expr.map(
+            move |column| {
+                let user_agents = column.str()?;
+                let present: Vec<&str> = user_agents.into_iter().flatten().collect();
+                let clients = parser.parse_all(present);
+
+                let fields: Vec<Series> = (FIELDS.iter())
+                    .map(|&(name, extract)| {
+                        let mut clients = clients.iter();
+                        let values: StringChunked = (user_agents.into_iter())
+                            .map(|user_agent| {
+                                user_agent?;
+                                clients.next().and_then(extract)
+                            })
+                            .collect();
+                        values.with_name(name.into()).into_series()
+                    })
+                    .collect();
+
+                let parsed = StructChunked::from_series(
+                    column.name().clone(),
+                    user_agents.len(),
+                    fields.iter(),
+                )?;
+                let present = user_agents.is_not_null().rechunk();
+                let validity = present.downcast_iter().next().map(|a| a.values().clone());
+                let parsed = parsed.with_outer_validity(validity);
+                Ok(Some(parsed.into_series().into_column()))
+            },
+            GetOutput::from_type(DataType::Struct(fields)),
+        )
+    }
+
+    /// The family of the user agent, e.g. `Firefox`
+    pub fn family(self) -> Expr {
+        self.parse().struct_().field_by_name("ua_family")
+    }
+
+    /// The major version of the user agent
+    pub fn major(self) -> Expr {
+        self.parse().struct_().field_by_name("ua_major")
+    }
+
+    /// The family of the operating system, e.g. `Linux`
+    pub fn os(self) -> Expr {
+        self.parse().struct_().field_by_name("os_family")
+    }
+
+    /// The major version of the operating system
+    pub fn os_major(self) -> Expr {
+        self.parse().struct_().field_by_name("os_major")
+    }
+
+    /// The family of the device, e.g. `iPhone`
+    pub fn device(self) -> Expr {
+        self.parse().struct_().field_by_name("device_family")
+    }
+
+    pub fn device_brand(self) -> Expr {
+        self.parse().struct_().field_by_name("device_brand")
+    }
+
+    pub fn device_model(self) -> Expr {
+        self.parse().struct_().field_by_name("device_model")
+    }
+}
+
+type Extract = fn(&Client) -> Option<String>;
+
+/// The fields of the struct returned by `UaNameSpace::parse`
+const FIELDS: &[(&str, Extract)] = &[
+    ("ua_family", |client| Some(client.user_agent.family.clone())),
+    ("ua_major", |client| client.user_agent.major.clone()),
+    ("os_family", |client| Some(client.os.family.clone())),
+    ("os_major", |client| client.os.major.clone()),
+    ("device_family", |client| Some(client.device.family.clone())),
+    ("device_brand", |client| client.device.brand.clone()),
+    ("device_model", |client| client.device.model.clone()),
+];